@@ -0,0 +1,100 @@
+//! Compare the cost of ticking a large tree with [`BehaviorNodeContainer`] and [`ArenaTree`].
+//!
+//! Run with `cargo bench`.
+
+use behavior_tree_lite::{
+    boxify, parse_file, ArenaTree, BehaviorCallback, BehaviorNode, BehaviorNodeContainer,
+    BehaviorResult, Blueprint, Context, PortSpec, Registry,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+struct Succeed;
+
+impl BehaviorNode for Succeed {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+}
+
+/// Reads a port, to include the cost of the port map lookup
+struct Check;
+
+impl BehaviorNode for Check {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("input")]
+    }
+
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        match ctx.get::<bool>("input") {
+            Some(true) => BehaviorResult::Success,
+            _ => BehaviorResult::Fail,
+        }
+    }
+}
+
+/// A tree of nested Sequences with `width` children each, `depth` levels deep.
+fn source(width: usize, depth: usize) -> String {
+    fn node(width: usize, depth: usize, out: &mut String) {
+        if depth == 0 {
+            out.push_str("Fallback { Check (input <- flag) Succeed }\n");
+            return;
+        }
+        out.push_str("Sequence {\n");
+        for _ in 0..width {
+            node(width, depth - 1, out);
+        }
+        out.push_str("}\n");
+    }
+    let mut ret = "tree main = ".to_string();
+    node(width, depth, &mut ret);
+    ret
+}
+
+fn bench_tick(c: &mut Criterion) {
+    let mut registry = Registry::default();
+    registry.register("Succeed", boxify(|| Succeed));
+    registry.register("Check", boxify(|| Check));
+
+    let mut group = c.benchmark_group("tick");
+    for (width, depth) in [(4, 3), (8, 3), (4, 5)] {
+        let source_text = source(width, depth);
+        let (_, source) = parse_file(&source_text).unwrap();
+        let blueprint = Blueprint::new(&source, &registry, true).unwrap();
+        let name = format!("{width}x{depth}");
+
+        let mut container: BehaviorNodeContainer = blueprint.instantiate().unwrap();
+        let mut ctx = Context::default();
+        ctx.set("flag", false);
+        group.bench_function(BenchmarkId::new("container", &name), |b| {
+            b.iter(|| container.tick(&mut |_| None, &mut ctx))
+        });
+
+        let mut arena: ArenaTree = blueprint.instantiate_arena().unwrap();
+        let mut ctx = Context::default();
+        ctx.set("flag", false);
+        group.bench_function(BenchmarkId::new("arena", &name), |b| {
+            b.iter(|| arena.tick(&mut |_| None, &mut ctx))
+        });
+    }
+    group.finish();
+}
+
+fn bench_instantiate(c: &mut Criterion) {
+    let mut registry = Registry::default();
+    registry.register("Succeed", boxify(|| Succeed));
+    registry.register("Check", boxify(|| Check));
+    let source_text = source(4, 3);
+    let (_, source) = parse_file(&source_text).unwrap();
+    let blueprint = Blueprint::new(&source, &registry, true).unwrap();
+
+    let mut group = c.benchmark_group("instantiate");
+    group.bench_function("load", |b| {
+        b.iter(|| behavior_tree_lite::load(&source, &registry, true).unwrap())
+    });
+    group.bench_function("container", |b| b.iter(|| blueprint.instantiate()));
+    group.bench_function("arena", |b| b.iter(|| blueprint.instantiate_arena()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_tick, bench_instantiate);
+criterion_main!(benches);
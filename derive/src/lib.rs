@@ -0,0 +1,239 @@
+//! Derive macros for the ports of [behavior-tree-lite](https://crates.io/crates/behavior-tree-lite)
+//! nodes, which are re-exported by the main crate with `derive` feature.
+//!
+//! [`Ports`] declares the typed ports of a node with `#[input]`, `#[output]` and `#[inout]`
+//! attributes, and generates accessors that cache the port names as symbols.
+//! [`macro@provided_ports`] fills in `BehaviorNode::provided_ports` with them.
+//!
+//! ```
+//! use behavior_tree_lite::{BehaviorCallback, BehaviorNode, BehaviorResult, Context};
+//! use behavior_tree_lite_derive::{provided_ports, Ports};
+//! # #[derive(Clone)] struct Arm;
+//! # struct Body { left_arm: Arm, right_arm: Arm }
+//!
+//! #[derive(Ports)]
+//! #[input(body: Body)]
+//! #[output(left_arm: Arm, right_arm: Arm)]
+//! struct PrintBodyNode;
+//!
+//! #[provided_ports]
+//! impl BehaviorNode for PrintBodyNode {
+//!     fn tick(&mut self, _: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+//!         let Some(body) = self.input_body(ctx) else {
+//!             return BehaviorResult::Fail;
+//!         };
+//!         let (left_arm, right_arm) = (body.left_arm.clone(), body.right_arm.clone());
+//!         self.output_left_arm(ctx, left_arm);
+//!         self.output_right_arm(ctx, right_arm);
+//!         BehaviorResult::Success
+//!     }
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    Attribute, Data, DeriveInput, Ident, ImplItem, ItemImpl, Token, Type,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Input,
+    Output,
+    InOut,
+}
+
+impl Direction {
+    fn of(attr: &Attribute) -> Option<Self> {
+        if attr.path().is_ident("input") {
+            Some(Self::Input)
+        } else if attr.path().is_ident("output") {
+            Some(Self::Output)
+        } else if attr.path().is_ident("inout") {
+            Some(Self::InOut)
+        } else {
+            None
+        }
+    }
+}
+
+/// A port declared as `name: Type` in a port attribute.
+struct Port {
+    name: Ident,
+    ty: Type,
+}
+
+impl Parse for Port {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(Self { name, ty })
+    }
+}
+
+/// Declare the typed ports of a node with the attributes on the struct:
+///
+/// * `#[input(name: Type, ...)]`
+/// * `#[output(name: Type, ...)]`
+/// * `#[inout(name: Type, ...)]`
+///
+/// or on the named fields, without arguments, in which case the port has the name and the
+/// type of the field. The field itself is an ordinary field, e.g. to keep the last value:
+///
+/// ```
+/// # use behavior_tree_lite_derive::Ports;
+/// #[derive(Ports)]
+/// struct Accumulate {
+///     #[input]
+///     amount: f64,
+///     #[output]
+///     total: f64,
+/// }
+/// ```
+///
+/// It generates an inherent associated function `ports()`, which returns the
+/// `PortSpec`s with the value types, and methods for each port:
+///
+/// * `input_<name>(&self, ctx) -> Option<&Type>` for input and inout ports
+/// * `output_<name>(&self, ctx, value: Type)` for output and inout ports
+///
+/// The methods look up the port with a symbol cached on the first use.
+#[proc_macro_derive(Ports, attributes(input, output, inout))]
+pub fn derive_ports(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match ports_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn ports_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut ports = vec![];
+    for attr in &input.attrs {
+        let Some(direction) = Direction::of(attr) else {
+            continue;
+        };
+        let list = attr.parse_args_with(Punctuated::<Port, Token![,]>::parse_terminated)?;
+        ports.extend(list.into_iter().map(|port| (direction, port)));
+    }
+    if let Data::Struct(data) = &input.data {
+        for field in &data.fields {
+            for attr in &field.attrs {
+                let Some(direction) = Direction::of(attr) else {
+                    continue;
+                };
+                attr.meta.require_path_only()?;
+                let Some(name) = field.ident.clone() else {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "ports can only be declared on named fields",
+                    ));
+                };
+                let ty = field.ty.clone();
+                ports.push((direction, Port { name, ty }));
+            }
+        }
+    }
+
+    let specs = ports.iter().map(|(direction, Port { name, ty })| {
+        let constructor = match direction {
+            Direction::Input => quote!(new_in),
+            Direction::Output => quote!(new_out),
+            Direction::InOut => quote!(new_inout),
+        };
+        let key = name.to_string();
+        quote! {
+            ::behavior_tree_lite::PortSpec::#constructor(#key).with_type::<#ty>()
+        }
+    });
+
+    let accessors = ports.iter().map(|(direction, Port { name, ty })| {
+        let key = name.to_string();
+        let symbol = quote! {
+            static SYMBOL: ::behavior_tree_lite::Lazy<::behavior_tree_lite::Symbol> =
+                ::behavior_tree_lite::Lazy::new(|| #key.into());
+        };
+        let mut methods = TokenStream2::new();
+        if *direction != Direction::Output {
+            let method = format_ident!("input_{}", name);
+            let doc = format!("Get the value of the input port `{key}`.");
+            methods.extend(quote! {
+                #[doc = #doc]
+                #[allow(dead_code)]
+                fn #method<'__ctx, __BtlEnv: ::behavior_tree_lite::Env>(
+                    &self,
+                    ctx: &'__ctx ::behavior_tree_lite::Context<__BtlEnv>,
+                ) -> Option<&'__ctx #ty> {
+                    #symbol
+                    ctx.get::<#ty>(*SYMBOL)
+                }
+            });
+        }
+        if *direction != Direction::Input {
+            let method = format_ident!("output_{}", name);
+            let doc = format!("Set the value of the output port `{key}`.");
+            methods.extend(quote! {
+                #[doc = #doc]
+                #[allow(dead_code)]
+                fn #method<__BtlEnv: ::behavior_tree_lite::Env>(
+                    &self,
+                    ctx: &mut ::behavior_tree_lite::Context<__BtlEnv>,
+                    value: #ty,
+                ) {
+                    #symbol
+                    ctx.set(*SYMBOL, value);
+                }
+            });
+        }
+        methods
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// The ports declared by the attributes, for `BehaviorNode::provided_ports`.
+            pub fn ports() -> ::std::vec::Vec<::behavior_tree_lite::PortSpec> {
+                ::std::vec![#(#specs),*]
+            }
+
+            #(#accessors)*
+        }
+    })
+}
+
+/// Put on an `impl BehaviorNode for ...` block of a type deriving [`Ports`] to implement
+/// `provided_ports` with the declared ports.
+#[proc_macro_attribute]
+pub fn provided_ports(args: TokenStream, item: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = TokenStream2::from(args);
+        return syn::Error::new_spanned(args, "provided_ports takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let mut item = parse_macro_input!(item as ItemImpl);
+    let defined = item.items.iter().any(|item| match item {
+        ImplItem::Fn(method) => method.sig.ident == "provided_ports",
+        _ => false,
+    });
+    if defined {
+        return syn::Error::new_spanned(
+            &item.self_ty,
+            "provided_ports is already defined in this impl block",
+        )
+        .to_compile_error()
+        .into();
+    }
+    item.items.push(parse_quote! {
+        fn provided_ports(&self) -> ::std::vec::Vec<::behavior_tree_lite::PortSpec> {
+            Self::ports()
+        }
+    });
+    quote!(#item).into()
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+
+use crate::{
+    debugger::{debug_hook, DebugEvent},
+    error::{LoadError, TickError},
+    BBMap, BehaviorNode, BehaviorNodeContainer, BehaviorResult, Blueprint, CallbackEnv, Context,
+    Env, NodeId, Shared, TickEvent,
+};
+
+/// A tree that stores its nodes in a contiguous array, as an alternative to
+/// [`BehaviorNodeContainer`] for large trees ticked every frame.
+///
+/// A container moves the child nodes and the port map of each node into the [`Context`]
+/// while ticking it, so that the node can access them.
+/// An arena tree lays out the nodes in breadth-first order, so that the children of a node
+/// are a range of indices, and the context only keeps the index of the node being ticked.
+///
+/// It runs the same [`BehaviorNode`]s, and supports the [observers](crate::TickObserver),
+/// errors, the [debugger](crate::debugger) and [recording](crate::record::Recorder::tick_arena)
+/// like a container, except that [`crate::debugger::DebugView::node`] is `None`.
+///
+/// A node of an arena tree can tick a container of its own, e.g. with
+/// [`crate::tick_child_node`], which resolves its children and ports as usual.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// let (_, source) = parse_file(r#"
+/// tree main = Sequence {
+///     SetBool (value <- "true", output -> done)
+///     IsTrue (input <- done)
+/// }
+/// "#).unwrap();
+/// let mut tree = ArenaTree::from(load(&source, &Registry::default(), true).unwrap());
+/// let mut ctx = Context::default();
+/// assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+/// assert_eq!(tree.len(), 3);
+/// ```
+pub struct ArenaTree<E: Env = CallbackEnv> {
+    nodes: Vec<ArenaNode<E>>,
+    /// The index of the node with each [`NodeId`], since the ids are assigned in depth-first
+    /// order while the nodes are laid out in breadth-first order
+    by_id: Vec<u32>,
+}
+
+pub(crate) struct ArenaNode<E: Env> {
+    /// Taken out while the node is ticked, because the node needs the context with the arena
+    node: Option<Box<dyn BehaviorNode<E>>>,
+    name: Shared<str>,
+    path: Shared<str>,
+    id: NodeId,
+    blackboard_map: Shared<BBMap>,
+    first_child: u32,
+    num_children: u32,
+    last_result: Option<BehaviorResult>,
+}
+
+/// The nodes of an [`ArenaTree`] moved into the [`Context`] during a tick.
+pub(crate) struct ArenaState<E: Env> {
+    nodes: Vec<ArenaNode<E>>,
+    /// The index of the node being ticked
+    current: usize,
+}
+
+impl<E: Env> From<BehaviorNodeContainer<E>> for ArenaTree<E> {
+    fn from(root: BehaviorNodeContainer<E>) -> Self {
+        let mut nodes = vec![];
+        let mut queue = VecDeque::from([root]);
+        while let Some(container) = queue.pop_front() {
+            // The children come after the nodes already in the queue
+            let first_child = (nodes.len() + 1 + queue.len()) as u32;
+            let BehaviorNodeContainer {
+                name,
+                node,
+                blackboard_map,
+                child_nodes,
+                last_result,
+                id,
+                path,
+                ..
+            } = container;
+            nodes.push(ArenaNode {
+                node: Some(node),
+                name,
+                path,
+                id,
+                blackboard_map,
+                first_child,
+                num_children: child_nodes.len() as u32,
+                last_result,
+            });
+            queue.extend(child_nodes);
+        }
+        // The ids of a tree without assigned ids are all zero, in which case the root wins
+        let mut by_id = vec![u32::MAX; nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            if let Some(slot) = by_id.get_mut(node.id.0 as usize) {
+                if *slot == u32::MAX {
+                    *slot = index as u32;
+                }
+            }
+        }
+        Self { nodes, by_id }
+    }
+}
+
+impl<E: Env> Blueprint<E> {
+    /// Create a new instance of the tree as an [`ArenaTree`].
+    pub fn instantiate_arena(&self) -> Result<ArenaTree<E>, LoadError> {
+        self.instantiate().map(ArenaTree::from)
+    }
+}
+
+impl<E: Env> ArenaTree<E> {
+    pub fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        ctx.begin_tick();
+        // Save the state of the outer arena tree in case this tree is ticked by its node
+        let outer = ctx.arena.0.replace(ArenaState {
+            nodes: std::mem::take(&mut self.nodes),
+            current: 0,
+        });
+        let res = Self::tick_node(ctx, 0, arg);
+        let state = std::mem::replace(&mut ctx.arena.0, outer);
+        self.nodes = state.map_or_else(Vec::new, |state| state.nodes);
+        res
+    }
+
+    /// Tick the tree like [`Self::tick`], but return the error if a node reported one,
+    /// see [`BehaviorNodeContainer::try_tick`].
+    pub fn try_tick(
+        &mut self,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Result<BehaviorResult, TickError> {
+        let res = self.tick(arg, ctx);
+        match ctx.error.take() {
+            Some(error) => Err(error),
+            None => Ok(res),
+        }
+    }
+
+    /// Halt the running nodes, see [`BehaviorNodeContainer::halt`].
+    pub fn halt(&mut self) {
+        halt_node(&mut self.nodes, 0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The result of the node with the id in the last tick, like
+    /// [`BehaviorNodeContainer::last_result`].
+    pub fn last_result(&self, id: NodeId) -> Option<BehaviorResult> {
+        let index = *self.by_id.get(id.0 as usize)?;
+        self.nodes.get(index as usize)?.last_result
+    }
+
+    pub(crate) fn tick_child(
+        ctx: &mut Context<E>,
+        idx: usize,
+        arg: E::Arg<'_>,
+    ) -> Option<BehaviorResult> {
+        let index = ctx.arena.as_ref()?.child_index(idx)?;
+        Some(Self::tick_node(ctx, index, arg))
+    }
+
+    fn tick_node(ctx: &mut Context<E>, index: usize, arg: E::Arg<'_>) -> BehaviorResult {
+        if ctx.aborted {
+            return BehaviorResult::Running;
+        }
+        // Only observers and the debugger need the names, so we don't touch the reference
+        // counts otherwise
+        let named = ctx.has_observers() || ctx.debugger.is_some();
+        let Some(state) = ctx.arena.0.as_ref() else {
+            return BehaviorResult::Running;
+        };
+        let entry = &state.nodes[index];
+        if entry.node.is_none() {
+            // The node is already being ticked, which can only happen if it ticks itself
+            return BehaviorResult::Running;
+        }
+        let id = entry.id;
+        let names = named.then(|| (entry.path.clone(), entry.name.clone()));
+        let event = names
+            .as_ref()
+            .map(|(path, name)| TickEvent { id, path, name });
+
+        let start = event.as_ref().and_then(|event| ctx.notify_enter(event));
+        if let Some(ref event) = event {
+            debug_hook(event, None, ctx, DebugEvent::Enter);
+            if ctx.aborted {
+                ctx.notify_exit(event, BehaviorResult::Running, start);
+                return BehaviorResult::Running;
+            }
+        }
+
+        let Some(state) = ctx.arena.0.as_mut() else {
+            return BehaviorResult::Running;
+        };
+        let Some(mut node) = state.nodes[index].node.take() else {
+            return BehaviorResult::Running;
+        };
+        let prev = std::mem::replace(&mut state.current, index);
+        ctx.depth += 1;
+        let prev_node = ctx.tape.as_ref().map(|tape| tape.enter(id));
+        let waits = ctx.waits_declared();
+        let res = node.tick(arg, ctx);
+        ctx.check_waits(waits, res);
+        if let (Some(tape), Some(prev_node)) = (ctx.tape.as_ref(), prev_node) {
+            tape.exit(prev_node, id, res);
+        }
+        ctx.depth -= 1;
+
+        let mut error_path = None;
+        if let Some(state) = ctx.arena.0.as_mut() {
+            state.current = prev;
+            let entry = &mut state.nodes[index];
+            entry.node = Some(node);
+            entry.last_result = Some(res);
+            if ctx.pending_error.get_mut().is_some() {
+                error_path = Some(entry.path.clone());
+            }
+        }
+        if let Some(path) = error_path {
+            ctx.promote_error(&path);
+        }
+        if let Some(ref event) = event {
+            if !ctx.aborted {
+                debug_hook(event, None, ctx, DebugEvent::Exit(res));
+            }
+            ctx.notify_exit(event, res, start);
+        }
+        res
+    }
+}
+
+impl<E: Env> ArenaState<E> {
+    fn child_index(&self, idx: usize) -> Option<usize> {
+        let node = &self.nodes[self.current];
+        (idx < node.num_children as usize).then(|| node.first_child as usize + idx)
+    }
+
+    pub(crate) fn num_children(&self) -> usize {
+        self.nodes[self.current].num_children as usize
+    }
+
+    pub(crate) fn blackboard_map(&self) -> &BBMap {
+        &self.nodes[self.current].blackboard_map
+    }
+
+    pub(crate) fn halt_child(&mut self, idx: usize) {
+        if let Some(index) = self.child_index(idx) {
+            halt_node(&mut self.nodes, index);
+        }
+    }
+}
+
+fn halt_node<E: Env>(nodes: &mut [ArenaNode<E>], index: usize) {
+    let Some(entry) = nodes.get(index) else {
+        return;
+    };
+    if entry.last_result != Some(BehaviorResult::Running) {
+        return;
+    }
+    let children = entry.first_child as usize..(entry.first_child + entry.num_children) as usize;
+    for child in children {
+        halt_node(nodes, child);
+    }
+    let entry = &mut nodes[index];
+    if let Some(ref mut node) = entry.node {
+        node.halt();
+    }
+    entry.last_result = None;
+}
+
+#[cfg(test)]
+mod test;
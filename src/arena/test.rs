@@ -0,0 +1,244 @@
+use super::*;
+use crate::{boxify, load, parse_file, BehaviorCallback, PortSpec, Registry, TickObserver};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Returns `Running` until the given number of ticks, then `Success`
+struct Wait {
+    ticks: usize,
+    halted: Arc<AtomicUsize>,
+}
+
+impl BehaviorNode for Wait {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("ticks")]
+    }
+
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let Some(ticks) = ctx.require_parse::<usize>("ticks") else {
+            return BehaviorResult::Fail;
+        };
+        self.ticks += 1;
+        if self.ticks < ticks {
+            BehaviorResult::Running
+        } else {
+            self.ticks = 0;
+            BehaviorResult::Success
+        }
+    }
+
+    fn halt(&mut self) {
+        self.ticks = 0;
+        self.halted.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+const SOURCE: &str = r#"
+tree main = Sequence {
+    var flag = false
+    Repeat (n <- "2") {
+        Sub (input <- "2", output -> result)
+    }
+    ReactiveFallback {
+        flag
+        Wait (ticks <- "3")
+    }
+}
+
+tree Sub(in input, out output) = Sequence {
+    Wait (ticks <- input)
+    SetBool (value <- "true", output -> output)
+}
+"#;
+
+fn registry(halted: &Arc<AtomicUsize>) -> Registry {
+    let mut registry = Registry::default();
+    let halted = halted.clone();
+    registry.register(
+        "Wait",
+        boxify(move || Wait {
+            ticks: 0,
+            halted: halted.clone(),
+        }),
+    );
+    registry
+}
+
+#[test]
+fn test_same_as_container() {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let registry = registry(&Arc::default());
+    let mut container = load(&source, &registry, true).unwrap();
+    let mut arena = ArenaTree::from(load(&source, &registry, true).unwrap());
+    let mut container_ctx = Context::default();
+    let mut arena_ctx = Context::default();
+
+    let mut results = vec![];
+    loop {
+        let res = container.tick(&mut |_| None, &mut container_ctx);
+        assert_eq!(arena.tick(&mut |_| None, &mut arena_ctx), res);
+        assert_eq!(
+            arena.last_result(NodeId(2)),
+            container.find_by_id(NodeId(2)).unwrap().last_result()
+        );
+        results.push(res);
+        if res != BehaviorResult::Running {
+            break;
+        }
+    }
+    assert_eq!(results.len(), 7);
+    assert_eq!(results.last(), Some(&BehaviorResult::Success));
+    assert_eq!(
+        arena_ctx.get::<bool>("result"),
+        container_ctx.get::<bool>("result")
+    );
+    assert_eq!(arena_ctx.get::<bool>("result"), Some(&true));
+}
+
+#[test]
+fn test_halt() {
+    let (_, source) = parse_file(
+        r#"
+tree main = Sequence {
+    OnChange (keys <- "flag") {
+        Wait (ticks <- "3")
+    }
+}
+"#,
+    )
+    .unwrap();
+    let halted = Arc::new(AtomicUsize::new(0));
+    let blueprint = Blueprint::new(&source, &registry(&halted), true).unwrap();
+    let mut tree = blueprint.instantiate_arena().unwrap();
+    let mut ctx = Context::default();
+    ctx.set("flag", false);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(halted.load(Ordering::SeqCst), 0);
+
+    // OnChange halts the running child with `Context::halt_child`
+    ctx.set("flag", true);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(halted.load(Ordering::SeqCst), 1);
+
+    tree.halt();
+    assert_eq!(halted.load(Ordering::SeqCst), 2);
+    assert_eq!(tree.last_result(NodeId(0)), None);
+    assert_eq!(tree.last_result(NodeId(2)), None);
+}
+
+#[test]
+fn test_tick_error() {
+    let (_, source) = parse_file("tree main = Sequence { Wait }").unwrap();
+    let mut tree = ArenaTree::from(load(&source, &registry(&Arc::default()), true).unwrap());
+    let err = tree
+        .try_tick(&mut |_| None, &mut Context::default())
+        .unwrap_err();
+    assert_eq!(err.path, "main/Wait[0]");
+}
+
+#[derive(Default)]
+struct EventLog(Vec<String>);
+
+impl TickObserver for EventLog {
+    fn on_enter(&mut self, event: &TickEvent) {
+        self.0.push(format!("enter {} {}", event.id, event.path));
+    }
+
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, _duration: Duration) {
+        self.0.push(format!("exit {} {:?}", event.id, result));
+    }
+}
+
+#[test]
+fn test_observer() {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let registry = registry(&Arc::default());
+    let mut container = load(&source, &registry, true).unwrap();
+    let mut arena = ArenaTree::from(load(&source, &registry, true).unwrap());
+
+    let mut container_ctx = Context::default();
+    container_ctx.add_observer(EventLog::default());
+    let mut arena_ctx = Context::default();
+    arena_ctx.add_observer(EventLog::default());
+    for _ in 0..3 {
+        container.tick(&mut |_| None, &mut container_ctx);
+        arena.tick(&mut |_| None, &mut arena_ctx);
+    }
+    let events = &arena_ctx.observer::<EventLog>().unwrap().0;
+    assert_eq!(events, &container_ctx.observer::<EventLog>().unwrap().0);
+    assert_eq!(events[0], "enter #0 main");
+}
+
+/// Ticks a container of its own, like a node that embeds another tree
+struct Embed(BehaviorNodeContainer);
+
+impl BehaviorNode for Embed {
+    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        self.0.tick(arg, ctx)
+    }
+}
+
+#[test]
+fn test_container_in_arena() {
+    let (_, inner) =
+        parse_file(r#"tree main = Sequence { SetBool (value <- "true", output -> inner_done) }"#)
+            .unwrap();
+    let inner = load(&inner, &Registry::default(), true).unwrap();
+    let mut tree = BehaviorNodeContainer::new_node(crate::SequenceNode::default());
+    tree.add_child(BehaviorNodeContainer::new_node(Embed(inner)))
+        .unwrap();
+    let mut tree = ArenaTree::from(tree);
+    let mut ctx = Context::default();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+    // The inner Sequence ticks its own child, not the children of the arena node
+    assert_eq!(ctx.get::<bool>("inner_done"), Some(&true));
+}
+
+#[test]
+fn test_debugger() {
+    use crate::debugger::{Breakpoint, DebugAction, DebugView, Debugger};
+
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let mut tree = ArenaTree::from(load(&source, &registry(&Arc::default()), true).unwrap());
+    let paused = Arc::new(std::sync::Mutex::new(vec![]));
+    let paused2 = paused.clone();
+    let mut debugger = Debugger::new(move |view: &DebugView| {
+        assert!(view.node().is_none());
+        paused2.lock().unwrap().push(view.path().to_owned());
+        DebugAction::Abort
+    });
+    debugger.add_breakpoint(Breakpoint::path("main/SetBool[0]"));
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    for _ in 0..3 {
+        assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    }
+    // Aborting at the variable initializer keeps the tree from going further
+    assert_eq!(*paused.lock().unwrap(), ["main/SetBool[0]"; 3]);
+    assert_eq!(ctx.get::<bool>("flag"), None);
+}
+
+#[test]
+fn test_recording() {
+    use crate::{codec::CodecRegistry, record::Recorder};
+
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let registry = registry(&Arc::default());
+    let mut container = load(&source, &registry, true).unwrap();
+    let mut arena = ArenaTree::from(load(&source, &registry, true).unwrap());
+    let mut container_recorder = Recorder::new(CodecRegistry::with_primitives());
+    let mut arena_recorder = Recorder::new(CodecRegistry::with_primitives());
+    let (mut container_ctx, mut arena_ctx) = (Context::default(), Context::default());
+    for _ in 0..3 {
+        container_recorder.tick(&mut container, &mut |_| None, &mut container_ctx);
+        arena_recorder.tick_arena(&mut arena, &mut |_| None, &mut arena_ctx);
+    }
+    assert_eq!(arena_recorder.recording(), container_recorder.recording());
+    assert!(!arena_recorder.recording().ticks.is_empty());
+}
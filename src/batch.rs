@@ -0,0 +1,198 @@
+//! Ticking many agents at once, e.g. a crowd in a game, each with its own tree and [`Context`].
+//!
+//! The agents are given as a slice of `(tree, context)` pairs, and the tree can be any type,
+//! like [`crate::BehaviorNodeContainer`] or [`crate::ArenaTree`].
+//! Each agent is ticked by a callback, which is given the index of the agent, so that it can
+//! pass the environment of that agent to the tree, and returns the result of `try_tick`.
+//! The results and the errors are aggregated into [`BatchStats`].
+//!
+//! [`tick_serial`] ticks the agents one by one, and [`tick_parallel`] splits them among
+//! threads, either scoped threads of the standard library with [`ScopedThreads`] or your own
+//! thread pool implementing [`Pool`].
+//! Trees and contexts can be sent to other threads only with `sync` feature.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, batch::*};
+//! let (_, source) = parse_file(r#"tree main = Sequence { Repeat (n <- "1") { Sequence } }"#).unwrap();
+//! let blueprint = Blueprint::new(&source, &Registry::default(), true).unwrap();
+//! let mut agents = (0..100)
+//!     .map(|_| (blueprint.instantiate().unwrap(), Context::default()))
+//!     .collect::<Vec<_>>();
+//!
+//! let stats = tick_serial(&mut agents, |_index, tree, ctx| tree.try_tick(&mut |_| None, ctx));
+//! assert_eq!(stats.running, 100);
+//! # #[cfg(feature = "sync")]
+//! # {
+//! let stats = tick_parallel(&mut agents, &ScopedThreads::default(), |_index, tree, ctx| {
+//!     tree.try_tick(&mut |_| None, ctx)
+//! });
+//! assert_eq!(stats.success, 100);
+//! # }
+//! ```
+
+use crate::{error::TickError, BehaviorResult, Context, Env};
+use std::time::{Duration, Instant};
+
+/// Aggregated results of ticking a batch of agents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchStats {
+    pub success: usize,
+    pub fail: usize,
+    pub running: usize,
+    /// Agents whose tick returned an error, which are not counted in the results above
+    pub errors: usize,
+    /// Time from the start to the end of the batch
+    pub elapsed: Duration,
+    /// The longest time spent to tick an agent
+    pub max_agent_time: Duration,
+}
+
+impl BatchStats {
+    /// The number of agents ticked.
+    pub fn total(&self) -> usize {
+        self.success + self.fail + self.running + self.errors
+    }
+
+    fn add(&mut self, res: Result<BehaviorResult, TickError>) {
+        match res {
+            Ok(BehaviorResult::Success) => self.success += 1,
+            Ok(BehaviorResult::Fail) => self.fail += 1,
+            Ok(BehaviorResult::Running) => self.running += 1,
+            Err(_) => self.errors += 1,
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.success += other.success;
+        self.fail += other.fail;
+        self.running += other.running;
+        self.errors += other.errors;
+        self.max_agent_time = self.max_agent_time.max(other.max_agent_time);
+    }
+}
+
+/// A unit of work given to a [`Pool`].
+pub type Job<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// A thread pool to run the jobs of [`tick_parallel`].
+///
+/// You can implement it for the thread pool of your application, for example with rayon:
+///
+/// ```ignore
+/// struct Rayon;
+///
+/// impl Pool for Rayon {
+///     fn num_threads(&self) -> usize {
+///         rayon::current_num_threads()
+///     }
+///
+///     fn run<'a>(&self, jobs: Vec<Job<'a>>) {
+///         rayon::scope(|s| {
+///             for job in jobs {
+///                 s.spawn(|_| job());
+///             }
+///         });
+///     }
+/// }
+/// ```
+pub trait Pool {
+    /// The number of jobs to split the agents into.
+    fn num_threads(&self) -> usize;
+
+    /// Run all the jobs, possibly in parallel, and return when all of them have finished.
+    fn run<'a>(&self, jobs: Vec<Job<'a>>);
+}
+
+/// A [`Pool`] that spawns scoped threads of the standard library for each batch.
+#[derive(Debug, Clone, Copy)]
+pub struct ScopedThreads(pub usize);
+
+impl Default for ScopedThreads {
+    /// As many threads as [`std::thread::available_parallelism`].
+    fn default() -> Self {
+        Self(std::thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+impl Pool for ScopedThreads {
+    fn num_threads(&self) -> usize {
+        self.0
+    }
+
+    fn run<'a>(&self, jobs: Vec<Job<'a>>) {
+        std::thread::scope(|scope| {
+            for job in jobs {
+                scope.spawn(job);
+            }
+        });
+    }
+}
+
+/// Tick all the agents one by one on the current thread with the callback, which is given
+/// the index of the agent in the slice, and returns the result of the tree, e.g. from
+/// [`crate::BehaviorNodeContainer::try_tick`].
+pub fn tick_serial<T, E: Env>(
+    agents: &mut [(T, Context<E>)],
+    mut tick: impl FnMut(usize, &mut T, &mut Context<E>) -> Result<BehaviorResult, TickError>,
+) -> BatchStats {
+    let start = Instant::now();
+    let mut stats = tick_chunk(agents, 0, &mut tick);
+    stats.elapsed = start.elapsed();
+    stats
+}
+
+/// Tick the agents like [`tick_serial`], but split them into chunks that run in parallel
+/// in the `pool`.
+///
+/// The order of the agents within a chunk is kept, but the chunks run in any order.
+pub fn tick_parallel<T, E: Env>(
+    agents: &mut [(T, Context<E>)],
+    pool: &impl Pool,
+    tick: impl Fn(usize, &mut T, &mut Context<E>) -> Result<BehaviorResult, TickError> + Sync,
+) -> BatchStats
+where
+    (T, Context<E>): Send,
+{
+    let start = Instant::now();
+    let chunk_size = agents.len().div_ceil(pool.num_threads().max(1)).max(1);
+    let mut partials = vec![BatchStats::default(); agents.len().div_ceil(chunk_size)];
+    let tick = &tick;
+    let jobs = agents
+        .chunks_mut(chunk_size)
+        .zip(&mut partials)
+        .enumerate()
+        .map(|(i, (chunk, partial))| {
+            Box::new(move || {
+                *partial = tick_chunk(chunk, i * chunk_size, &mut |index, tree, ctx| {
+                    tick(index, tree, ctx)
+                });
+            }) as Job
+        })
+        .collect();
+    pool.run(jobs);
+
+    let mut stats = BatchStats::default();
+    for partial in &partials {
+        stats.merge(partial);
+    }
+    stats.elapsed = start.elapsed();
+    stats
+}
+
+fn tick_chunk<T, E: Env>(
+    agents: &mut [(T, Context<E>)],
+    offset: usize,
+    tick: &mut impl FnMut(usize, &mut T, &mut Context<E>) -> Result<BehaviorResult, TickError>,
+) -> BatchStats {
+    let mut stats = BatchStats::default();
+    for (i, (tree, ctx)) in agents.iter_mut().enumerate() {
+        let start = Instant::now();
+        let res = tick(offset + i, tree, ctx);
+        stats.max_agent_time = stats.max_agent_time.max(start.elapsed());
+        stats.add(res);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,131 @@
+use super::*;
+use crate::{load, parse_file, BehaviorNodeContainer, Registry};
+
+/// Agents with `even` set succeed immediately, and the others are running for a tick
+const SOURCE: &str = r#"
+tree main = Sequence {
+    Fallback {
+        IsTrue (input <- even)
+        ForceSuccess {
+            Repeat (n <- "1") { Sequence }
+        }
+    }
+}
+"#;
+
+fn agents(count: usize) -> Vec<(BehaviorNodeContainer, Context)> {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let registry = Registry::default();
+    (0..count)
+        .map(|i| {
+            let mut ctx = Context::default();
+            ctx.set("even", i % 2 == 0);
+            (load(&source, &registry, true).unwrap(), ctx)
+        })
+        .collect()
+}
+
+#[test]
+fn test_tick_serial() {
+    let mut agents = agents(10);
+    let mut ticked = vec![];
+    let stats = tick_serial(&mut agents, |index, tree, ctx| {
+        ticked.push(index);
+        tree.try_tick(&mut |_| None, ctx)
+    });
+    assert_eq!(ticked, (0..10).collect::<Vec<_>>());
+    assert_eq!(stats.success, 5);
+    assert_eq!(stats.running, 5);
+    assert_eq!(stats.total(), 10);
+    assert_eq!(stats.errors, 0);
+
+    let stats = tick_serial(&mut agents, |_, tree, ctx| {
+        tree.try_tick(&mut |_| None, ctx)
+    });
+    assert_eq!(stats.success, 10);
+}
+
+#[test]
+fn test_errors() {
+    let (_, source) =
+        parse_file(r#"tree main = Sequence { Repeat (n <- count) { Sequence } }"#).unwrap();
+    let registry = Registry::default();
+    let mut agents = (0..4)
+        .map(|i| {
+            let mut ctx = Context::default();
+            if i != 1 {
+                ctx.set("count", "2".to_string());
+            }
+            (load(&source, &registry, true).unwrap(), ctx)
+        })
+        .collect::<Vec<_>>();
+    let mut failed = vec![];
+    let stats = tick_serial(&mut agents, |index, tree, ctx| {
+        let res = tree.try_tick(&mut |_| None, ctx);
+        if res.is_err() {
+            failed.push(index);
+        }
+        res
+    });
+    assert_eq!(failed, [1]);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.running, 3);
+    assert_eq!(stats.total(), 4);
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_tick_parallel() {
+    use std::sync::Mutex;
+
+    for pool_threads in [1, 3, 4, 16] {
+        let mut agents = agents(10);
+        let ticked = Mutex::new(vec![]);
+        let stats = tick_parallel(
+            &mut agents,
+            &ScopedThreads(pool_threads),
+            |index, tree, ctx| {
+                ticked.lock().unwrap().push(index);
+                tree.try_tick(&mut |_| None, ctx)
+            },
+        );
+        let mut ticked = ticked.into_inner().unwrap();
+        ticked.sort();
+        assert_eq!(ticked, (0..10).collect::<Vec<_>>());
+        assert_eq!(stats.success, 5);
+        assert_eq!(stats.running, 5);
+    }
+}
+
+/// Runs the jobs on the current thread in reverse order
+#[cfg(feature = "sync")]
+struct Reverse;
+
+#[cfg(feature = "sync")]
+impl Pool for Reverse {
+    fn num_threads(&self) -> usize {
+        3
+    }
+
+    fn run<'a>(&self, jobs: Vec<Job<'a>>) {
+        for job in jobs.into_iter().rev() {
+            job();
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_custom_pool() {
+    use std::sync::Mutex;
+
+    let mut agents = agents(10);
+    let ticked = Mutex::new(vec![]);
+    let stats = tick_parallel(&mut agents, &Reverse, |index, tree, ctx| {
+        ticked.lock().unwrap().push(index);
+        tree.try_tick(&mut |_| None, ctx)
+    });
+    // 3 chunks of 4, 4 and 2 agents, in reverse order
+    assert_eq!(ticked.into_inner().unwrap(), [8, 9, 4, 5, 6, 7, 0, 1, 2, 3]);
+    assert_eq!(stats.total(), 10);
+}
@@ -0,0 +1,168 @@
+use std::cell::Cell;
+
+use crate::{
+    container::path_segments,
+    error::LoadError,
+    parser::{compile, NodeKind, TreeSource},
+    registry::SharedConstructor,
+    BBMap, BehaviorNode, BehaviorNodeContainer, CallbackEnv, Env, NodeId, Registry, Shared,
+};
+
+/// The structure of a tree compiled from a [`TreeSource`], to create many instances of
+/// the same tree cheaply, e.g. one for each agent in a game.
+///
+/// [`crate::load`] walks the source, looks up the node types and checks the ports every time.
+/// A blueprint does it once, and [`Self::instantiate`] only constructs the nodes.
+/// The names, paths and port maps of the nodes are shared by [`Shared`] pointers between the
+/// blueprint and all the instances, so each instance only holds the state of its nodes.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// let (_, source) = parse_file(r#"
+/// tree main = Sequence {
+///     SetBool (value <- "true", output -> done)
+/// }
+/// "#).unwrap();
+/// let blueprint = Blueprint::new(&source, &Registry::default(), true).unwrap();
+///
+/// let mut agents = (0..100)
+///     .map(|_| (blueprint.instantiate().unwrap(), Context::default()))
+///     .collect::<Vec<_>>();
+/// for (tree, ctx) in &mut agents {
+///     assert_eq!(tree.tick(&mut |_| None, ctx), BehaviorResult::Success);
+///     assert_eq!(ctx.get::<bool>("done"), Some(&true));
+/// }
+/// ```
+pub struct Blueprint<E: Env = CallbackEnv> {
+    root: BlueprintNode<E>,
+}
+
+impl<E: Env> Blueprint<E> {
+    /// Compile the tree, with the same arguments as [`crate::load`].
+    ///
+    /// The constructors of the nodes are shared with the `registry`, so the blueprint
+    /// doesn't borrow it.
+    pub fn new(
+        tree_source: &TreeSource,
+        registry: &Registry<E>,
+        check_ports: bool,
+    ) -> Result<Self, LoadError> {
+        let mut root = compile(tree_source, registry, check_ports)?;
+        root.drop_instances();
+        Ok(Self { root })
+    }
+
+    /// Create a new instance of the tree, with ids and paths already assigned.
+    ///
+    /// The nodes are constructed again for each instance, so a constructor registered with
+    /// [`Registry::register_with_config`] can still reject its configuration here.
+    pub fn instantiate(&self) -> Result<BehaviorNodeContainer<E>, LoadError> {
+        self.root.instantiate()
+    }
+}
+
+/// A node in a [`Blueprint`], which holds what doesn't change between the instances.
+pub(crate) struct BlueprintNode<E: Env> {
+    constructor: SharedConstructor<E>,
+    name: Shared<str>,
+    pub(crate) blackboard_map: Shared<BBMap>,
+    pub(crate) children: Vec<BlueprintNode<E>>,
+    pub(crate) is_subtree: bool,
+    pub(crate) kind: NodeKind,
+    id: NodeId,
+    pub(crate) label: Option<Shared<str>>,
+    path: Shared<str>,
+    /// The node created while compiling, which [`Self::into_container`] uses instead of
+    /// constructing another
+    pub(crate) instance: Option<Box<dyn BehaviorNode<E>>>,
+}
+
+impl<E: Env> BlueprintNode<E> {
+    pub(crate) fn new(constructor: SharedConstructor<E>, name: &str) -> Self {
+        Self {
+            constructor,
+            name: name.into(),
+            blackboard_map: Shared::default(),
+            children: vec![],
+            is_subtree: false,
+            kind: NodeKind::Node,
+            id: NodeId::default(),
+            label: None,
+            path: "".into(),
+            instance: None,
+        }
+    }
+
+    /// Assign ids and paths in the same way as [`BehaviorNodeContainer::assign_ids`].
+    pub(crate) fn assign_ids(&mut self, root_name: &str) {
+        let mut next_id = 0;
+        self.assign_ids_recurse(root_name.to_owned(), &mut next_id);
+    }
+
+    fn assign_ids_recurse(&mut self, path: String, next_id: &mut u32) {
+        self.id = NodeId(*next_id);
+        *next_id += 1;
+
+        let segments = path_segments(
+            self.children
+                .iter()
+                .map(|child| (&*child.name, child.label.as_deref())),
+        );
+        for (child, segment) in self.children.iter_mut().zip(segments) {
+            child.assign_ids_recurse(format!("{path}/{segment}"), next_id);
+        }
+        self.path = path.into();
+    }
+
+    fn drop_instances(&mut self) {
+        self.instance = None;
+        for child in &mut self.children {
+            child.drop_instances();
+        }
+    }
+
+    fn instantiate(&self) -> Result<BehaviorNodeContainer<E>, LoadError> {
+        let child_nodes = self
+            .children
+            .iter()
+            .map(|child| child.instantiate())
+            .collect::<Result<_, _>>()?;
+        Ok(self.container((self.constructor)()?, child_nodes))
+    }
+
+    /// Create the tree for [`crate::load`], reusing the nodes created while compiling.
+    pub(crate) fn into_container(mut self) -> Result<BehaviorNodeContainer<E>, LoadError> {
+        let node = match self.instance.take() {
+            Some(node) => node,
+            None => (self.constructor)()?,
+        };
+        let child_nodes = std::mem::take(&mut self.children)
+            .into_iter()
+            .map(Self::into_container)
+            .collect::<Result<_, _>>()?;
+        Ok(self.container(node, child_nodes))
+    }
+
+    fn container(
+        &self,
+        node: Box<dyn BehaviorNode<E>>,
+        child_nodes: Vec<BehaviorNodeContainer<E>>,
+    ) -> BehaviorNodeContainer<E> {
+        BehaviorNodeContainer {
+            name: self.name.clone(),
+            node,
+            blackboard_map: self.blackboard_map.clone(),
+            child_nodes,
+            last_result: None,
+            is_subtree: self.is_subtree,
+            kind: self.kind,
+            subtree_expanded: Cell::new(false),
+            id: self.id,
+            label: self.label.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,121 @@
+use super::*;
+use crate::{load, parse_file, BehaviorResult, Context};
+
+const SOURCE: &str = r#"
+tree main = Sequence {
+    Repeat (n <- "2") {
+        Sub (input <- "hello", output -> result)
+    }
+}
+
+tree Sub(in input, out output) = Sequence {
+    SetBool (value <- "true", output -> done)
+    Fallback:pass {
+        IsTrue (input <- done)
+    }
+}
+"#;
+
+fn paths(tree: &BehaviorNodeContainer, out: &mut Vec<(NodeId, String)>) {
+    out.push((tree.id(), tree.path().to_owned()));
+    for child in tree.children() {
+        paths(child, out);
+    }
+}
+
+#[test]
+fn test_same_as_load() {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let registry = Registry::default();
+    let blueprint = Blueprint::new(&source, &registry, true).unwrap();
+
+    let mut expected = vec![];
+    paths(&load(&source, &registry, true).unwrap(), &mut expected);
+    let mut actual = vec![];
+    paths(&blueprint.instantiate().unwrap(), &mut actual);
+    assert_eq!(actual, expected);
+    assert!(actual
+        .iter()
+        .any(|(_, path)| path == "main/Repeat[0]/Sub[0]"));
+    assert!(actual
+        .iter()
+        .any(|(_, path)| path.ends_with("/Sequence[0]/pass")));
+}
+
+#[test]
+fn test_independent_instances() {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let blueprint = Blueprint::new(&source, &Registry::default(), true).unwrap();
+    let mut tree1 = blueprint.instantiate().unwrap();
+    let mut tree2 = blueprint.instantiate().unwrap();
+
+    let repeat1 = &tree1.children()[0];
+    let repeat2 = &tree2.children()[0];
+    assert!(Shared::ptr_eq(
+        &repeat1.blackboard_map,
+        &repeat2.blackboard_map
+    ));
+    assert!(Shared::ptr_eq(&repeat1.path, &repeat2.path));
+
+    let mut ctx1 = Context::default();
+    let mut ctx2 = Context::default();
+    assert_eq!(
+        tree1.tick(&mut |_| None, &mut ctx1),
+        BehaviorResult::Running
+    );
+    assert_eq!(
+        tree1.tick(&mut |_| None, &mut ctx1),
+        BehaviorResult::Running
+    );
+    assert_eq!(
+        tree2.tick(&mut |_| None, &mut ctx2),
+        BehaviorResult::Running
+    );
+    assert_eq!(
+        tree1.tick(&mut |_| None, &mut ctx1),
+        BehaviorResult::Success
+    );
+    assert_eq!(
+        tree2.tick(&mut |_| None, &mut ctx2),
+        BehaviorResult::Running
+    );
+}
+
+#[test]
+fn test_error() {
+    let (_, source) = parse_file("tree main = Sequence { Unknown }").unwrap();
+    assert!(matches!(
+        Blueprint::new(&source, &Registry::default(), true),
+        Err(LoadError::MissingNode(name)) if name == "Unknown"
+    ));
+}
+
+#[test]
+fn test_constructor_error() {
+    use crate::{boxify_with_config, nodes::SequenceNode};
+    use std::cell::Cell;
+
+    // Accepts the configuration only the first time, when the blueprint is compiled
+    let built = Cell::new(false);
+    let mut registry = Registry::default();
+    registry.register_with_config(
+        "Once",
+        boxify_with_config(move |config| {
+            if built.replace(true) {
+                Err(LoadError::InvalidConfig {
+                    node: config.node_type().to_owned(),
+                    port: "n".to_owned(),
+                    message: "built twice".to_owned(),
+                })
+            } else {
+                Ok(SequenceNode::default())
+            }
+        }),
+    );
+    let (_, source) = parse_file("tree main = Sequence { Once }").unwrap();
+    let blueprint = Blueprint::new(&source, &registry, true).unwrap();
+    assert!(matches!(
+        blueprint.instantiate(),
+        Err(LoadError::InvalidConfig { node, .. }) if node == "Once"
+    ));
+}
@@ -0,0 +1,288 @@
+//! Conversion between blackboard values and a serialized form.
+//!
+//! Blackboard values are `dyn Any`, so we cannot serialize them without knowing their types.
+//! Types opt in by being registered to a [`CodecRegistry`] with a stable name, which is
+//! written along with the value so that it can be decoded later.
+//!
+//! Each [`crate::Context`] has its own registry with primitive types registered by default,
+//! which is used to serialize, print and diff its blackboard.
+//!
+//! ```
+//! # use behavior_tree_lite::Context;
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Body { x: f64, y: f64 }
+//!
+//! let mut ctx = Context::default();
+//! ctx.register_type::<Body>("Body");
+//! ctx.set("body", Body { x: 1., y: 2. });
+//! let before = ctx.blackboard_snapshot();
+//! ctx.set("hp", 10i32);
+//! let diff = before.diff(&ctx.blackboard_snapshot());
+//! assert_eq!(diff.to_string(), "+ hp: 10\n");
+//! assert_eq!(
+//!     ctx.blackboard_to_json().unwrap().to_string(),
+//!     r#"{"body":{"type":"Body","value":{"x":1.0,"y":2.0}},"hp":{"type":"i32","value":10}}"#
+//! );
+//! ```
+
+use crate::{AnyValue, Blackboard, MaybeSendSync, Shared};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+};
+
+struct Codec {
+    name: String,
+    encode: fn(&dyn Any) -> Option<serde_json::Value>,
+    decode: fn(serde_json::Value) -> Result<Box<AnyValue>, serde_json::Error>,
+}
+
+/// A set of types that can be serialized, keyed by [`TypeId`] and by name.
+///
+/// ```
+/// # use behavior_tree_lite::codec::CodecRegistry;
+/// let mut codecs = CodecRegistry::new();
+/// codecs.register::<f64>("f64");
+/// let (name, value) = codecs.encode(&1.5f64).unwrap();
+/// assert_eq!(name, "f64");
+/// let decoded = codecs.decode(name, value).unwrap().unwrap();
+/// assert_eq!(decoded.downcast_ref::<f64>(), Some(&1.5));
+/// ```
+#[derive(Default)]
+pub struct CodecRegistry {
+    codecs: HashMap<TypeId, Codec>,
+    names: HashMap<String, TypeId>,
+}
+
+impl std::fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_set().entries(self.names.keys()).finish()
+    }
+}
+
+impl CodecRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with `bool`, `String`, `i32`, `i64`, `u32`, `u64`, `usize`, `f32` and `f64`
+    /// registered under their Rust names.
+    pub fn with_primitives() -> Self {
+        let mut ret = Self::new();
+        ret.register::<bool>("bool");
+        ret.register::<String>("String");
+        ret.register::<i32>("i32");
+        ret.register::<i64>("i64");
+        ret.register::<u32>("u32");
+        ret.register::<u64>("u64");
+        ret.register::<usize>("usize");
+        ret.register::<f32>("f32");
+        ret.register::<f64>("f64");
+        ret
+    }
+
+    /// Register a type with a name that identifies it in the serialized data.
+    /// Registering the same type again replaces the name.
+    pub fn register<T: Serialize + DeserializeOwned + MaybeSendSync + 'static>(
+        &mut self,
+        name: impl Into<String>,
+    ) {
+        let name = name.into();
+        if let Some(old) = self.codecs.get(&TypeId::of::<T>()) {
+            self.names.remove(&old.name);
+        }
+        self.names.insert(name.clone(), TypeId::of::<T>());
+        self.codecs.insert(
+            TypeId::of::<T>(),
+            Codec {
+                name,
+                encode: encode::<T>,
+                decode: decode::<T>,
+            },
+        );
+    }
+
+    pub fn is_registered(&self, type_id: TypeId) -> bool {
+        self.codecs.contains_key(&type_id)
+    }
+
+    /// Registered name of the type of the value, if any.
+    pub fn name_of(&self, value: &dyn Any) -> Option<&str> {
+        self.codecs
+            .get(&value.type_id())
+            .map(|codec| codec.name.as_str())
+    }
+
+    /// Serialize a value with its registered name.
+    /// Returns `None` if the type is not registered or serialization failed.
+    pub fn encode(&self, value: &dyn Any) -> Option<(&str, serde_json::Value)> {
+        let codec = self.codecs.get(&value.type_id())?;
+        Some((&codec.name, (codec.encode)(value)?))
+    }
+
+    /// Deserialize a value of the type registered as `name`.
+    /// Returns `None` if the name is not registered.
+    pub fn decode(
+        &self,
+        name: &str,
+        value: serde_json::Value,
+    ) -> Option<Result<Box<AnyValue>, serde_json::Error>> {
+        let codec = &self.codecs[self.names.get(name)?];
+        Some((codec.decode)(value))
+    }
+}
+
+fn encode<T: Serialize + 'static>(value: &dyn Any) -> Option<serde_json::Value> {
+    serde_json::to_value(value.downcast_ref::<T>()?).ok()
+}
+
+fn decode<T: DeserializeOwned + MaybeSendSync + 'static>(
+    value: serde_json::Value,
+) -> Result<Box<AnyValue>, serde_json::Error> {
+    Ok(Box::new(serde_json::from_value::<T>(value)?))
+}
+
+/// A wrapper to print a [`Blackboard`] with the values decoded by a [`CodecRegistry`].
+/// Values of unregistered types are shown as `<unregistered>`.
+pub struct BlackboardDebug<'a> {
+    pub blackboard: &'a Blackboard,
+    pub codecs: &'a CodecRegistry,
+}
+
+impl<'a> std::fmt::Debug for BlackboardDebug<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        struct Value(Option<serde_json::Value>);
+
+        impl std::fmt::Debug for Value {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self.0 {
+                    Some(ref value) => write!(f, "{value}"),
+                    None => write!(f, "<unregistered>"),
+                }
+            }
+        }
+
+        let mut entries = self.blackboard.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| key.as_str());
+        f.debug_map()
+            .entries(entries.into_iter().map(|(key, value)| {
+                let value = self.codecs.encode(&**value).map(|(_, value)| value);
+                (key.as_str(), Value(value))
+            }))
+            .finish()
+    }
+}
+
+/// Encoded values of a blackboard at a point in time, to be compared later with
+/// [`BlackboardSnapshot::diff`].
+#[derive(Clone, Default)]
+pub struct BlackboardSnapshot {
+    values: BTreeMap<String, (Option<serde_json::Value>, Shared<AnyValue>)>,
+}
+
+impl std::fmt::Debug for BlackboardSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.values.iter().map(|(key, (value, _))| (key, value)))
+            .finish()
+    }
+}
+
+impl BlackboardSnapshot {
+    pub fn new(blackboard: &Blackboard, codecs: &CodecRegistry) -> Self {
+        Self {
+            values: blackboard
+                .iter()
+                .map(|(key, value)| {
+                    let encoded = codecs.encode(&**value).map(|(_, value)| value);
+                    (key.to_string(), (encoded, value.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    /// The encoded value, or `Some(None)` if the type is not registered.
+    pub fn get(&self, key: &str) -> Option<Option<&serde_json::Value>> {
+        self.values.get(key).map(|(value, _)| value.as_ref())
+    }
+
+    /// Keys of the variables whose types are not registered.
+    pub fn unregistered(&self) -> impl Iterator<Item = &str> {
+        self.values
+            .iter()
+            .filter(|(_, (value, _))| value.is_none())
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// Compare with a later snapshot.
+    ///
+    /// Values of unregistered types cannot be compared by contents, so they are considered
+    /// changed if a different value was set, even if it is equal.
+    pub fn diff(&self, after: &BlackboardSnapshot) -> BlackboardDiff {
+        let mut ret = BlackboardDiff::default();
+        for (key, (before_value, before_rc)) in &self.values {
+            match after.values.get(key) {
+                None => ret.removed.push(key.clone()),
+                Some((after_value, after_rc)) => {
+                    let changed = match (before_value, after_value) {
+                        (Some(before), Some(after)) => before != after,
+                        _ => !Shared::ptr_eq(before_rc, after_rc),
+                    };
+                    if changed {
+                        ret.changed
+                            .insert(key.clone(), (before_value.clone(), after_value.clone()));
+                    }
+                }
+            }
+        }
+        for (key, (value, _)) in &after.values {
+            if !self.values.contains_key(key) {
+                ret.added.insert(key.clone(), value.clone());
+            }
+        }
+        ret.unregistered = after.unregistered().map(str::to_owned).collect();
+        ret
+    }
+}
+
+/// Changes between two [`BlackboardSnapshot`]s. `None` values are of unregistered types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlackboardDiff {
+    pub added: BTreeMap<String, Option<serde_json::Value>>,
+    pub removed: Vec<String>,
+    /// Values before and after the change
+    pub changed: BTreeMap<String, (Option<serde_json::Value>, Option<serde_json::Value>)>,
+    /// Keys of the variables in the later snapshot whose types are not registered
+    pub unregistered: Vec<String>,
+}
+
+impl BlackboardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for BlackboardDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn value(value: &Option<serde_json::Value>) -> String {
+            match value {
+                Some(value) => value.to_string(),
+                None => "<unregistered>".to_owned(),
+            }
+        }
+        for (key, added) in &self.added {
+            writeln!(f, "+ {key}: {}", value(added))?;
+        }
+        for key in &self.removed {
+            writeln!(f, "- {key}")?;
+        }
+        for (key, (before, after)) in &self.changed {
+            writeln!(f, "~ {key}: {} -> {}", value(before), value(after))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,94 @@
+use super::*;
+use crate::{error::StateError, Context};
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Body {
+    x: f64,
+    y: f64,
+}
+
+struct Opaque;
+
+#[test]
+fn test_register() {
+    let mut codecs = CodecRegistry::new();
+    codecs.register::<Body>("Body");
+    assert!(codecs.is_registered(TypeId::of::<Body>()));
+    assert_eq!(codecs.name_of(&Body { x: 0., y: 0. }), Some("Body"));
+    assert_eq!(codecs.name_of(&Opaque), None);
+
+    // Registering again renames the type
+    codecs.register::<Body>("Vec2");
+    assert!(codecs.decode("Body", serde_json::Value::Null).is_none());
+    let decoded = codecs
+        .decode("Vec2", serde_json::json!({"x": 1., "y": 2.}))
+        .unwrap()
+        .unwrap();
+    assert_eq!(decoded.downcast_ref(), Some(&Body { x: 1., y: 2. }));
+}
+
+#[test]
+fn test_json() {
+    let mut ctx = Context::default();
+    ctx.register_type::<Body>("Body");
+    ctx.set("body", Body { x: 1., y: 2. });
+    ctx.set("flag", true);
+    let json = ctx.blackboard_to_json().unwrap();
+
+    let mut ctx2 = Context::default();
+    ctx2.register_type::<Body>("Body");
+    ctx2.blackboard_from_json(json).unwrap();
+    assert_eq!(ctx2.get::<Body>("body"), Some(&Body { x: 1., y: 2. }));
+    assert_eq!(ctx2.get::<bool>("flag"), Some(&true));
+
+    ctx.set("opaque", Opaque);
+    assert!(matches!(
+        ctx.blackboard_to_json(),
+        Err(StateError::UnregisteredType { key }) if key == "opaque"
+    ));
+}
+
+#[test]
+fn test_debug() {
+    let mut ctx = Context::default();
+    ctx.register_type::<Body>("Body");
+    ctx.set("body", Body { x: 1., y: 2. });
+    ctx.set("opaque", Opaque);
+    ctx.set("name", "Alice".to_owned());
+    assert_eq!(
+        format!("{:?}", ctx.debug_blackboard()),
+        r#"{"body": {"x":1.0,"y":2.0}, "name": "Alice", "opaque": <unregistered>}"#
+    );
+    assert!(format!("{ctx:?}").contains(r#""name": "Alice""#));
+}
+
+#[test]
+fn test_diff() {
+    let mut ctx = Context::default();
+    ctx.set("a", 1i32);
+    ctx.set("b", 2i32);
+    ctx.set("c", 3i32);
+    ctx.set("opaque", Opaque);
+    ctx.set("opaque2", Opaque);
+    let before = ctx.blackboard_snapshot();
+    assert_eq!(before.get("a"), Some(Some(&serde_json::json!(1))));
+    assert_eq!(before.get("opaque"), Some(None));
+
+    ctx.set("a", 10i32);
+    ctx.set("b", 2i32);
+    ctx.set("opaque", Opaque);
+    ctx.set("d", 4i32);
+    ctx.blackboard.remove(&"c".into());
+    let diff = before.diff(&ctx.blackboard_snapshot());
+
+    assert_eq!(
+        diff.to_string(),
+        "+ d: 4\n- c\n~ a: 1 -> 10\n~ opaque: <unregistered> -> <unregistered>\n"
+    );
+    assert_eq!(diff.unregistered, vec!["opaque", "opaque2"]);
+    assert!(!diff.is_empty());
+    assert!(ctx
+        .blackboard_snapshot()
+        .diff(&ctx.blackboard_snapshot())
+        .is_empty());
+}
@@ -1,84 +1,176 @@
-use std::{cell::Cell, collections::HashMap};
+use std::{cell::Cell, collections::HashMap, fmt};
 
 use crate::{
-    error::{AddChildError, AddChildResult},
-    parser::PortMapOwned,
-    BehaviorCallback, BehaviorNode, BehaviorResult, BlackboardValue, Context, NumChildren, Symbol,
+    debugger::{debug_hook, DebugEvent},
+    error::{AddChildError, AddChildResult, TickError},
+    observer::TickEvent,
+    parser::{NodeKind, PortMapOwned},
+    BBMap, BehaviorNode, BehaviorResult, BlackboardValue, CallbackEnv, Context, Env, NumChildren,
+    Shared, Symbol,
 };
 
-pub struct BehaviorNodeContainer {
+/// A numeric identifier of a node in a tree, unique within the tree.
+///
+/// Ids are assigned in depth-first pre-order by [`BehaviorNodeContainer::assign_ids`],
+/// so loading the same source always gives the same ids.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct NodeId(pub u32);
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A node in a tree, with its children and the data the tree needs to tick it.
+///
+/// The data that doesn't change while ticking, like the name, the path and the port map,
+/// is shared by [`Shared`] pointers, so that the instances created from the same
+/// [`crate::Blueprint`] don't copy it.
+pub struct BehaviorNodeContainer<E: Env = CallbackEnv> {
     /// Name of the type of the node
-    pub(crate) name: String,
-    pub(crate) node: Box<dyn BehaviorNode>,
-    pub(crate) blackboard_map: HashMap<Symbol, BlackboardValue>,
-    pub(crate) child_nodes: Vec<BehaviorNodeContainer>,
+    pub(crate) name: Shared<str>,
+    pub(crate) node: Box<dyn BehaviorNode<E>>,
+    pub(crate) blackboard_map: Shared<BBMap>,
+    pub(crate) child_nodes: Vec<BehaviorNodeContainer<E>>,
     pub(crate) last_result: Option<BehaviorResult>,
     pub(crate) is_subtree: bool,
+    pub(crate) kind: NodeKind,
     pub(crate) subtree_expanded: Cell<bool>,
+    pub(crate) id: NodeId,
+    /// An optional name given by the user, e.g. `approach` in `MoveTo:approach`
+    pub(crate) label: Option<Shared<str>>,
+    pub(crate) path: Shared<str>,
 }
 
 impl BehaviorNodeContainer {
-    pub fn new(
-        node: Box<dyn BehaviorNode>,
-        blackboard_map: HashMap<Symbol, BlackboardValue>,
-    ) -> Self {
-        Self {
-            name: "".to_owned(),
-            node,
-            blackboard_map,
-            child_nodes: vec![],
-            last_result: None,
-            is_subtree: false,
-            subtree_expanded: Cell::new(false),
-        }
+    pub fn new(node: Box<dyn BehaviorNode>, blackboard_map: BBMap) -> Self {
+        Self::new_raw(node).with_blackboard_map(blackboard_map)
     }
 
     pub fn new_raw(node: Box<dyn BehaviorNode>) -> Self {
-        Self {
-            name: "".to_owned(),
-            node,
-            blackboard_map: HashMap::new(),
-            child_nodes: vec![],
-            last_result: None,
-            is_subtree: false,
-            subtree_expanded: Cell::new(false),
-        }
+        Self::new_raw_with_name(node, "")
     }
 
     pub fn new_node(node: impl BehaviorNode + 'static) -> Self {
-        Self {
-            name: "".to_owned(),
-            node: Box::new(node),
-            blackboard_map: HashMap::new(),
-            child_nodes: vec![],
-            last_result: None,
-            is_subtree: false,
-            subtree_expanded: Cell::new(false),
-        }
+        Self::new_raw(Box::new(node))
+    }
+}
+
+impl<E: Env> BehaviorNodeContainer<E> {
+    /// Create a node with a custom environment type, see [`Env`].
+    ///
+    /// The other constructors are only defined for the default environment, so that the type
+    /// can be inferred without annotations.
+    pub fn new_generic(node: impl BehaviorNode<E> + 'static) -> Self {
+        Self::new_raw_with_name(Box::new(node), "")
     }
 
-    pub(crate) fn new_raw_with_name(node: Box<dyn BehaviorNode>, name: String) -> Self {
+    pub fn with_blackboard_map(mut self, blackboard_map: BBMap) -> Self {
+        self.blackboard_map = Shared::new(blackboard_map);
+        self
+    }
+
+    pub(crate) fn new_raw_with_name(node: Box<dyn BehaviorNode<E>>, name: &str) -> Self {
         Self {
-            name,
+            name: name.into(),
             node,
-            blackboard_map: HashMap::new(),
+            blackboard_map: Shared::default(),
             child_nodes: vec![],
             last_result: None,
             is_subtree: false,
+            kind: NodeKind::Node,
             subtree_expanded: Cell::new(false),
+            id: NodeId::default(),
+            label: None,
+            path: "".into(),
         }
     }
 
-    pub fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+    pub fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        ctx.without_arena(|ctx| self.tick_container(arg, ctx))
+    }
+
+    fn tick_container(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        ctx.begin_tick();
+        if ctx.aborted {
+            return BehaviorResult::Running;
+        }
+        let start = ctx.notify_enter(&self.event());
+        debug_hook(&self.event(), Some(self), ctx, DebugEvent::Enter);
+        if ctx.aborted {
+            ctx.notify_exit(&self.event(), BehaviorResult::Running, start);
+            return BehaviorResult::Running;
+        }
+
         std::mem::swap(&mut self.child_nodes, &mut ctx.child_nodes.0);
         std::mem::swap(&mut self.blackboard_map, &mut ctx.blackboard_map);
+        ctx.depth += 1;
+        let prev_node = ctx.tape.as_ref().map(|tape| tape.enter(self.id));
+        let waits = ctx.waits_declared();
         let res = self.node.tick(arg, ctx);
+        ctx.check_waits(waits, res);
+        if let (Some(tape), Some(prev_node)) = (ctx.tape.as_ref(), prev_node) {
+            tape.exit(prev_node, self.id, res);
+        }
+        ctx.depth -= 1;
         std::mem::swap(&mut self.blackboard_map, &mut ctx.blackboard_map);
         std::mem::swap(&mut self.child_nodes, &mut ctx.child_nodes.0);
+        self.last_result = Some(res);
+        ctx.promote_error(&self.path);
+
+        if !ctx.aborted {
+            debug_hook(&self.event(), Some(self), ctx, DebugEvent::Exit(res));
+        }
+        ctx.notify_exit(&self.event(), res, start);
         res
     }
 
-    pub fn add_child(&mut self, child: BehaviorNodeContainer) -> AddChildResult {
+    /// Tick the tree like [`Self::tick`], but return the error if a node reported one, e.g.
+    /// a port direction violation in strict mode or a missing required input.
+    ///
+    /// The rest of the tick after the error is aborted, in the same way as
+    /// [`crate::debugger::DebugAction::Abort`], so the unfinished nodes are left `Running`.
+    /// [`Self::tick`] doesn't return the error, but you can check it with [`Context::tick_error`].
+    pub fn try_tick(
+        &mut self,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Result<BehaviorResult, TickError> {
+        let res = self.tick(arg, ctx);
+        match ctx.error.take() {
+            Some(error) => Err(error),
+            None => Ok(res),
+        }
+    }
+
+    /// Halt this node and its descendants if they are `Running`, deepest first,
+    /// so that the next tick starts over. See [`BehaviorNode::halt`].
+    pub fn halt(&mut self) {
+        if self.last_result != Some(BehaviorResult::Running) {
+            return;
+        }
+        for child in &mut self.child_nodes {
+            child.halt();
+        }
+        self.node.halt();
+        self.last_result = None;
+    }
+
+    pub fn add_child(&mut self, child: Self) -> AddChildResult {
         if NumChildren::Finite(self.child_nodes.len()) < self.node.max_children() {
             self.child_nodes.push(child);
             Ok(())
@@ -87,7 +179,7 @@ impl BehaviorNodeContainer {
         }
     }
 
-    pub fn children(&self) -> &[BehaviorNodeContainer] {
+    pub fn children(&self) -> &[Self] {
         &self.child_nodes
     }
 
@@ -99,11 +191,109 @@ impl BehaviorNodeContainer {
         &self.name
     }
 
+    #[cfg(test)]
     pub(crate) fn with_name(mut self, name: String) -> Self {
-        self.name = name;
+        self.name = name.into();
         self
     }
 
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into().into());
+        self
+    }
+
+    /// A path to this node from the root, like `main/Sequence[1]/approach`.
+    ///
+    /// The first segment is the name of the root, usually the tree name.
+    /// Each following segment is the label of the node if it has one, otherwise
+    /// the name of the node type followed by the index among the siblings of
+    /// the same type.
+    /// It is empty until [`Self::assign_ids`] is called, which [`crate::load`] does for you.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Assign ids and paths to this node and all its descendants.
+    /// You need to call this method yourself if you have built the tree by hand
+    /// instead of [`crate::load`].
+    pub fn assign_ids(&mut self, root_name: &str) {
+        let mut next_id = 0;
+        self.assign_ids_recurse(root_name.to_owned(), &mut next_id);
+    }
+
+    fn assign_ids_recurse(&mut self, path: String, next_id: &mut u32) {
+        self.id = NodeId(*next_id);
+        *next_id += 1;
+
+        let segments = path_segments(
+            self.child_nodes
+                .iter()
+                .map(|child| (&*child.name, child.label.as_deref())),
+        );
+        for (child, segment) in self.child_nodes.iter_mut().zip(segments) {
+            child.assign_ids_recurse(format!("{path}/{segment}"), next_id);
+        }
+        self.path = path.into();
+    }
+
+    pub fn find_by_id(&self, id: NodeId) -> Option<&Self> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.child_nodes
+            .iter()
+            .find_map(|child| child.find_by_id(id))
+    }
+
+    pub fn find_by_id_mut(&mut self, id: NodeId) -> Option<&mut Self> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.child_nodes
+            .iter_mut()
+            .find_map(|child| child.find_by_id_mut(id))
+    }
+
+    pub fn find_by_path(&self, path: &str) -> Option<&Self> {
+        if &*self.path == path {
+            return Some(self);
+        }
+        if !is_ancestor_path(&self.path, path) {
+            return None;
+        }
+        self.child_nodes
+            .iter()
+            .find_map(|child| child.find_by_path(path))
+    }
+
+    pub fn find_by_path_mut(&mut self, path: &str) -> Option<&mut Self> {
+        if &*self.path == path {
+            return Some(self);
+        }
+        if !is_ancestor_path(&self.path, path) {
+            return None;
+        }
+        self.child_nodes
+            .iter_mut()
+            .find_map(|child| child.find_by_path_mut(path))
+    }
+
+    fn event(&self) -> TickEvent<'_> {
+        TickEvent {
+            id: self.id,
+            path: &self.path,
+            name: &self.name,
+        }
+    }
+
     pub fn blackboard_map(&self) -> &HashMap<Symbol, BlackboardValue> {
         &self.blackboard_map
     }
@@ -130,6 +320,11 @@ impl BehaviorNodeContainer {
         self.is_subtree
     }
 
+    /// How the node was written in the source, or [`NodeKind::Node`] if it was built by hand.
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
     pub fn is_subtree_expanded(&self) -> bool {
         self.subtree_expanded.get()
     }
@@ -138,3 +333,30 @@ impl BehaviorNodeContainer {
         self.subtree_expanded.set(b);
     }
 }
+
+/// Path segments of the children given by their names and labels, see
+/// [`BehaviorNodeContainer::path`].
+pub(crate) fn path_segments<'a>(
+    children: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+) -> Vec<String> {
+    let mut type_counts = HashMap::<&str, usize>::new();
+    children
+        .map(|(name, label)| {
+            if let Some(label) = label {
+                label.to_owned()
+            } else {
+                let count = type_counts.entry(name).or_default();
+                let segment = format!("{}[{}]", name, count);
+                *count += 1;
+                segment
+            }
+        })
+        .collect()
+}
+
+fn is_ancestor_path(ancestor: &str, path: &str) -> bool {
+    matches!(path.strip_prefix(ancestor), Some(rest) if rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,185 @@
+use super::*;
+use crate::{
+    boxify,
+    error::{TickError, TickErrorCause},
+    load, parse_file, BehaviorCallback, Blackboard, Registry,
+};
+
+struct Succeed;
+
+impl BehaviorNode for Succeed {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+}
+
+/// Reads `input` and writes `output`
+struct CopyString;
+
+impl BehaviorNode for CopyString {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let value = ctx.get::<String>("input").cloned().unwrap_or_default();
+        ctx.set("output", value);
+        BehaviorResult::Success
+    }
+}
+
+fn load_tree(source: &str) -> BehaviorNodeContainer {
+    let (_, tree_source) = parse_file(source).unwrap();
+    let mut registry = Registry::default();
+    registry.register("MoveTo", boxify(|| Succeed));
+    registry.register("Copy", boxify(|| CopyString));
+    load(&tree_source, &registry, false).unwrap()
+}
+
+#[test]
+fn test_paths() {
+    let tree = load_tree(
+        r#"
+tree main = Sequence {
+    MoveTo
+    Sequence {
+        MoveTo
+        Patrol
+    }
+    Sequence:guard {
+        MoveTo:approach
+    }
+}
+
+tree Patrol = Sequence {
+    MoveTo
+    MoveTo
+}
+"#,
+    );
+
+    let paths = collect(&tree);
+    assert_eq!(
+        paths,
+        vec![
+            (0, "main".to_owned()),
+            (1, "main/MoveTo[0]".to_owned()),
+            (2, "main/Sequence[0]".to_owned()),
+            (3, "main/Sequence[0]/MoveTo[0]".to_owned()),
+            (4, "main/Sequence[0]/Patrol[0]".to_owned()),
+            (5, "main/Sequence[0]/Patrol[0]/Sequence[0]".to_owned()),
+            (
+                6,
+                "main/Sequence[0]/Patrol[0]/Sequence[0]/MoveTo[0]".to_owned()
+            ),
+            (
+                7,
+                "main/Sequence[0]/Patrol[0]/Sequence[0]/MoveTo[1]".to_owned()
+            ),
+            (8, "main/guard".to_owned()),
+            (9, "main/guard/approach".to_owned()),
+        ]
+    );
+
+    fn collect(node: &BehaviorNodeContainer) -> Vec<(u32, String)> {
+        let mut ret = vec![(node.id().0, node.path().to_owned())];
+        for child in node.children() {
+            ret.extend(collect(child));
+        }
+        ret
+    }
+}
+
+#[test]
+fn test_find() {
+    let mut tree = load_tree(
+        r#"
+tree main = Sequence {
+    MoveTo
+    Sequence:guard {
+        MoveTo:approach
+    }
+}
+"#,
+    );
+
+    let approach = tree.find_by_path("main/guard/approach").unwrap();
+    assert_eq!(approach.name(), "MoveTo");
+    assert_eq!(approach.label(), Some("approach"));
+    assert_eq!(approach.id(), NodeId(3));
+
+    assert_eq!(tree.find_by_id(NodeId(2)).unwrap().path(), "main/guard");
+    assert!(tree.find_by_id(NodeId(4)).is_none());
+    assert!(tree.find_by_path("main/guard/retreat").is_none());
+    assert!(tree.find_by_path("main/gua").is_none());
+
+    let node = tree.find_by_path_mut("main/MoveTo[0]").unwrap();
+    assert_eq!(node.id(), NodeId(1));
+    assert!(tree.find_by_id_mut(NodeId(3)).is_some());
+}
+
+#[test]
+fn test_try_tick() {
+    let mut tree = load_tree(
+        r#"
+tree main = Sequence {
+    Copy (input <- "a", output -> b)
+    Sequence {
+        Copy:bad (input -> b, output -> c)
+        Copy (input <- "d", output -> e)
+    }
+}
+"#,
+    );
+
+    let mut ctx = Context::new(Blackboard::new());
+    assert_eq!(
+        tree.try_tick(&mut |_| None, &mut ctx),
+        Err(TickError {
+            path: "main/Sequence[0]/bad".to_owned(),
+            cause: TickErrorCause::NotInput {
+                port: "input".to_owned()
+            },
+        })
+    );
+    assert!(ctx.is_aborted());
+    assert_eq!(ctx.get::<String>("b"), Some(&"a".to_owned()));
+    assert_eq!(ctx.get::<String>("c"), Some(&"".to_owned()));
+    assert_eq!(ctx.get::<String>("e"), None);
+
+    // Not an error without strict mode
+    let mut ctx = Context::default();
+    assert_eq!(
+        tree.try_tick(&mut |_| None, &mut ctx),
+        Ok(BehaviorResult::Success)
+    );
+    assert_eq!(ctx.get::<String>("e"), Some(&"d".to_owned()));
+}
+
+#[test]
+fn test_tick_error() {
+    let mut tree = load_tree(
+        r#"
+tree main = Sequence {
+    Repeat {
+        MoveTo
+    }
+    Copy (input <- "a", output <- "b")
+}
+"#,
+    );
+
+    let mut ctx = Context::default();
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(
+        ctx.tick_error().unwrap().to_string(),
+        "Required input port \"n\" has no value in node \"main/Repeat[0]\""
+    );
+
+    ctx.set("n", "0".to_owned());
+    let err = tree.try_tick(&mut |_| None, &mut ctx).unwrap_err();
+    assert_eq!(err.path, "main/Copy[0]");
+    assert_eq!(
+        err.cause,
+        TickErrorCause::LiteralWrite {
+            port: "output".to_owned()
+        }
+    );
+    assert!(ctx.tick_error().is_none());
+}
@@ -1,8 +1,17 @@
 use crate::{
-    BBMap, BehaviorCallback, BehaviorNodeContainer, BehaviorResult, Blackboard, BlackboardValue,
-    PortType, Symbol,
+    arena::{ArenaState, ArenaTree},
+    codec::{BlackboardDebug, BlackboardSnapshot, CodecRegistry},
+    debugger::Debugger,
+    error::{StateError, TickError, TickErrorCause},
+    observer::{AnyObserver, TickEvent, TickObserver},
+    record::{ReadOverride, TapeHandle},
+    wait::WaitState,
+    AnyValue, BBMap, BehaviorNodeContainer, BehaviorResult, Blackboard, BlackboardValue,
+    CallbackEnv, Env, MaybeSend, MaybeSendSync, PortType, Shared, Symbol,
+};
+use std::{
+    any::Any, cell::RefCell, collections::HashMap, str::FromStr, task::Waker, time::Instant,
 };
-use std::{any::Any, rc::Rc, str::FromStr};
 
 /// Our custom wrapper struct to stop propagation of Debug trait macro.
 /// Borrowed the concept from `debug-ignore` crate, but grossly simplified, and without dependency.
@@ -25,28 +34,199 @@ impl<T: ?Sized> std::ops::Deref for DebugIgnore<T> {
     }
 }
 
-#[derive(Default, Debug)]
-pub struct Context {
+/// When a blackboard variable was last set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyVersion {
+    version: u64,
+    tick: u64,
+}
+
+pub(crate) type Versions = HashMap<Symbol, KeyVersion>;
+
+/// A blackboard of an outer scope, kept while a subtree is being ticked.
+struct Scope {
+    blackboard: Blackboard,
+    versions: Versions,
+}
+
+/// Where a port is mapped to.
+pub(crate) enum Mapped<'a> {
+    Local(Symbol),
+    Global(Symbol),
+    Literal(&'a String),
+}
+
+/// A handle to remove a watcher installed by [`Context::watch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+#[cfg(not(feature = "sync"))]
+type WatchCallback = Box<dyn FnMut(Symbol, &dyn Any)>;
+#[cfg(feature = "sync")]
+type WatchCallback = Box<dyn FnMut(Symbol, &dyn Any) + Send>;
+
+struct Watcher {
+    id: WatchId,
+    key: Symbol,
+    callback: WatchCallback,
+}
+
+/// The state shared by the nodes during a tick, including the blackboard.
+///
+/// The type parameter `E` is the environment type of the nodes, see [`Env`].
+pub struct Context<E: Env = CallbackEnv> {
     pub(crate) blackboard: Blackboard,
-    pub(crate) blackboard_map: BBMap,
-    pub(crate) child_nodes: DebugIgnore<Vec<BehaviorNodeContainer>>,
+    pub(crate) blackboard_map: Shared<BBMap>,
+    pub(crate) child_nodes: DebugIgnore<Vec<BehaviorNodeContainer<E>>>,
+    /// The nodes of an [`ArenaTree`] while it is ticked, which replace `blackboard_map`
+    /// and `child_nodes`
+    pub(crate) arena: DebugIgnore<Option<ArenaState<E>>>,
     strict: bool,
+    observers: DebugIgnore<Vec<Box<dyn AnyObserver>>>,
+    pub(crate) debugger: Option<Debugger<E>>,
+    /// Depth of the node currently being ticked, counting from the root as 0
+    pub(crate) depth: usize,
+    pub(crate) aborted: bool,
+    /// Installed by [`crate::record::Recorder`] or [`crate::record::Replayer`] during a tick
+    pub(crate) tape: DebugIgnore<Option<TapeHandle>>,
+    codecs: CodecRegistry,
+    /// Versions of the variables in the current blackboard
+    pub(crate) versions: Versions,
+    version_counter: u64,
+    pub(crate) tick_count: u64,
+    watchers: DebugIgnore<Vec<Watcher>>,
+    next_watch_id: u64,
+    /// Blackboards of the subtrees we are in, from the root. The first one is the global scope.
+    outer_scopes: Vec<Scope>,
+    /// An error reported by the node being ticked, which doesn't know its path yet
+    pub(crate) pending_error: RefCell<Option<TickErrorCause>>,
+    pub(crate) error: Option<TickError>,
+    waker: Waker,
+    pub(crate) waits: WaitState,
 }
 
-impl Context {
-    pub fn new(blackboard: Blackboard) -> Self {
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            ..Self::new_generic(Blackboard::default())
+        }
+    }
+}
+
+impl<E: Env> Context<E> {
+    /// Create a context with a custom environment type, which is strict like [`Context::new`].
+    ///
+    /// [`Context::new`] and [`Context::default`] are only defined for the default environment,
+    /// so that the type can be inferred without annotations.
+    pub fn new_generic(blackboard: Blackboard) -> Self {
         Self {
             blackboard,
-            blackboard_map: BBMap::new(),
+            blackboard_map: Shared::default(),
             child_nodes: DebugIgnore(vec![]),
+            arena: DebugIgnore(None),
             strict: true,
+            observers: DebugIgnore::default(),
+            debugger: None,
+            depth: 0,
+            aborted: false,
+            tape: DebugIgnore::default(),
+            codecs: CodecRegistry::with_primitives(),
+            versions: HashMap::new(),
+            version_counter: 0,
+            tick_count: 0,
+            watchers: DebugIgnore::default(),
+            next_watch_id: 0,
+            outer_scopes: vec![],
+            pending_error: RefCell::new(None),
+            error: None,
+            waker: Waker::noop().clone(),
+            waits: WaitState::default(),
         }
     }
+}
+
+impl<E: Env> std::fmt::Debug for Context<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("blackboard", &self.debug_blackboard())
+            .field("blackboard_map", &self.blackboard_map)
+            .field("child_nodes", &self.child_nodes)
+            .field("strict", &self.strict)
+            .field("debugger", &self.debugger)
+            .field("tick_count", &self.tick_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Context {
+    pub fn new(blackboard: Blackboard) -> Self {
+        Self::new_generic(blackboard)
+    }
+}
+
+impl<E: Env> Context<E> {
+    pub fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
 
     pub fn take_blackboard(self) -> Blackboard {
         self.blackboard
     }
 
+    /// The codec registry used to serialize, print and diff the blackboard.
+    /// Primitive types are registered by default, see [`CodecRegistry::with_primitives`].
+    pub fn codecs(&self) -> &CodecRegistry {
+        &self.codecs
+    }
+
+    pub fn codecs_mut(&mut self) -> &mut CodecRegistry {
+        &mut self.codecs
+    }
+
+    pub fn set_codecs(&mut self, codecs: CodecRegistry) {
+        self.codecs = codecs;
+    }
+
+    /// Shorthand for `ctx.codecs_mut().register::<T>(name)`.
+    pub fn register_type<
+        T: serde::Serialize + serde::de::DeserializeOwned + MaybeSendSync + 'static,
+    >(
+        &mut self,
+        name: impl Into<String>,
+    ) {
+        self.codecs.register::<T>(name);
+    }
+
+    /// Serialize the blackboard to a JSON object, keyed by variable names.
+    /// Fails with the name of the variable if its type is not registered.
+    pub fn blackboard_to_json(&self) -> Result<serde_json::Value, StateError> {
+        Ok(serde_json::to_value(self.save_blackboard(&self.codecs)?)?)
+    }
+
+    /// Replace the blackboard with the one serialized by [`Self::blackboard_to_json`].
+    pub fn blackboard_from_json(&mut self, value: serde_json::Value) -> Result<(), StateError> {
+        let saved = serde_json::from_value(value)?;
+        self.blackboard = crate::state::restore_blackboard(&saved, &self.codecs)?;
+        self.touch_all();
+        Ok(())
+    }
+
+    /// Take a snapshot of the blackboard to see what changed later, e.g. between ticks.
+    pub fn blackboard_snapshot(&self) -> BlackboardSnapshot {
+        BlackboardSnapshot::new(&self.blackboard, &self.codecs)
+    }
+
+    /// A wrapper to print the blackboard with the actual values in `Debug` format.
+    pub fn debug_blackboard(&self) -> BlackboardDebug<'_> {
+        BlackboardDebug {
+            blackboard: &self.blackboard,
+            codecs: &self.codecs,
+        }
+    }
+
+    /// In strict mode, reading an output port or writing an input port is reported as
+    /// a [`TickError`]. Otherwise, reads return `None` and writes are ignored.
     pub fn strict(&self) -> bool {
         self.strict
     }
@@ -55,74 +235,262 @@ impl Context {
         self.strict = b;
     }
 
-    pub fn tick_child(&mut self, idx: usize, arg: BehaviorCallback) -> Option<BehaviorResult> {
+    /// Halt the child if it is running. See [`BehaviorNodeContainer::halt`].
+    pub fn halt_child(&mut self, idx: usize) {
+        if let Some(ref mut arena) = self.arena.0 {
+            arena.halt_child(idx);
+        } else if let Some(child) = self.child_nodes.0.get_mut(idx) {
+            child.halt();
+        }
+    }
+
+    pub fn tick_child(&mut self, idx: usize, arg: E::Arg<'_>) -> Option<BehaviorResult> {
+        if self.arena.is_some() {
+            return ArenaTree::tick_child(self, idx, arg);
+        }
         // Take the children temporarily because the context's `child_nodes` will be used by the child node (for grandchildren)
         let mut children = std::mem::take(&mut self.child_nodes.0);
-        let res = children.get_mut(idx).map(|child| {
-            let res = child.tick(arg, self);
-            child.last_result = Some(res);
-            res
-        });
+        let res = children.get_mut(idx).map(|child| child.tick(arg, self));
         self.child_nodes.0 = children;
         res
     }
 
     pub fn num_children(&self) -> usize {
-        self.child_nodes.len()
+        match *self.arena {
+            Some(ref arena) => arena.num_children(),
+            None => self.child_nodes.len(),
+        }
+    }
+
+    /// The waker to poll futures with, e.g. in [`crate::FutureNode`].
+    /// It does nothing by default.
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+
+    /// Set a waker to get notified when a future in the tree can make progress.
+    pub fn set_waker(&mut self, waker: Waker) {
+        self.waker = waker;
     }
 }
 
-impl Context {
+impl<E: Env> Context<E> {
+    /// Install an observer that gets notified when each node is entered and exited.
+    /// You can install as many observers as you like.
+    pub fn add_observer(&mut self, observer: impl TickObserver + 'static) {
+        self.observers.0.push(Box::new(observer));
+    }
+
+    /// Get a reference to the first installed observer of type `T`.
+    pub fn observer<T: TickObserver + 'static>(&self) -> Option<&T> {
+        self.observers
+            .iter()
+            .find_map(|observer| observer.as_any().downcast_ref())
+    }
+
+    /// Get a mutable reference to the first installed observer of type `T`.
+    pub fn observer_mut<T: TickObserver + 'static>(&mut self) -> Option<&mut T> {
+        self.observers
+            .0
+            .iter_mut()
+            .find_map(|observer| observer.as_any_mut().downcast_mut())
+    }
+
+    /// Uninstall the first observer of type `T` and return it.
+    pub fn remove_observer<T: TickObserver + 'static>(&mut self) -> Option<T> {
+        let idx = self
+            .observers
+            .iter()
+            .position(|observer| observer.as_any().is::<T>())?;
+        self.observers
+            .0
+            .remove(idx)
+            .into_any()
+            .downcast()
+            .ok()
+            .map(|observer| *observer)
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    /// Install a step debugger. See [`crate::debugger`] for details.
+    pub fn set_debugger(&mut self, debugger: Debugger<E>) {
+        self.debugger = Some(debugger);
+    }
+
+    pub fn debugger_mut(&mut self) -> Option<&mut Debugger<E>> {
+        self.debugger.as_mut()
+    }
+
+    pub fn take_debugger(&mut self) -> Option<Debugger<E>> {
+        self.debugger.take()
+    }
+
+    /// Returns true if the last tick was aborted by the debugger or a [`TickError`].
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// The error that aborted the last tick, if any.
+    /// See [`BehaviorNodeContainer::try_tick`].
+    pub fn tick_error(&self) -> Option<&TickError> {
+        self.error.as_ref()
+    }
+
+    /// Report an error in the node being ticked. Only the first error in a tick is kept,
+    /// and the rest of the tick is aborted when the node returns.
+    pub fn report_error(&self, cause: TickErrorCause) {
+        let mut pending = self.pending_error.borrow_mut();
+        if pending.is_none() && self.error.is_none() {
+            *pending = Some(cause);
+        }
+    }
+
+    /// Reset the state of the previous tick if this is the root node.
+    pub(crate) fn begin_tick(&mut self) {
+        if self.depth == 0 {
+            self.aborted = false;
+            self.error = None;
+            *self.pending_error.get_mut() = None;
+            self.tick_count += 1;
+            self.begin_waits();
+        }
+    }
+
+    /// Turn an error reported by the node just ticked into a [`TickError`] with its path,
+    /// and abort the rest of the tick.
+    pub(crate) fn promote_error(&mut self, path: &str) {
+        if let Some(cause) = self.pending_error.get_mut().take() {
+            self.error = Some(TickError {
+                path: path.to_owned(),
+                cause,
+            });
+            self.aborted = true;
+        }
+    }
+
+    /// Run `f` with the state of an [`ArenaTree`] being ticked moved out, so that a container
+    /// ticked by a node of the arena resolves its own children and ports.
+    pub(crate) fn without_arena<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let arena = self.arena.0.take();
+        let ret = f(self);
+        self.arena.0 = arena;
+        ret
+    }
+
+    pub(crate) fn has_observers(&self) -> bool {
+        !self.observers.is_empty()
+    }
+
+    /// Notify the observers that a node is about to be ticked.
+    /// Returns the time the node was entered, only if anyone is interested.
+    pub(crate) fn notify_enter(&mut self, event: &TickEvent) -> Option<Instant> {
+        if self.observers.is_empty() {
+            return None;
+        }
+        for observer in self.observers.0.iter_mut() {
+            observer.on_enter(event);
+        }
+        Some(Instant::now())
+    }
+
+    pub(crate) fn notify_exit(
+        &mut self,
+        event: &TickEvent,
+        result: BehaviorResult,
+        start: Option<Instant>,
+    ) {
+        let Some(start) = start else {
+            return;
+        };
+        let duration = start.elapsed();
+        for observer in self.observers.0.iter_mut() {
+            observer.on_exit(event, result, duration);
+        }
+    }
+}
+
+impl<E: Env> Context<E> {
     /// Get a blackboard variable with downcasting to the type argument.
     /// Returns `None` if it fails to downcast.
     pub fn get<T: 'static>(&self, key: impl Into<Symbol>) -> Option<&T> {
         let key: Symbol = key.into();
-        let mapped = self.blackboard_map.get(&key);
-        let mapped = match mapped {
-            None => &key,
-            Some(BlackboardValue::Ref(mapped, ty)) => {
-                if matches!(*ty, PortType::Input | PortType::InOut) {
-                    mapped
-                } else {
-                    if self.strict {
-                        panic!("Port {:?} is not specified as input or inout port", key);
-                    }
-                    return None;
-                }
-            }
-            Some(BlackboardValue::Literal(mapped)) => {
-                return (mapped as &dyn Any).downcast_ref();
-            }
+        let live = self.get_live(key);
+        let val = match self.tape_read(key, live) {
+            ReadOverride::Live => live,
+            ReadOverride::Missing => None,
+            ReadOverride::Value(val) => Some(&**val as &dyn Any),
         };
+        val.and_then(|val| val.downcast_ref())
+    }
 
-        self.blackboard.get(mapped).and_then(|val| {
-            // println!("val: {:?}", val);
-            val.downcast_ref()
-        })
+    fn get_live(&self, key: Symbol) -> Option<&dyn Any> {
+        match self.map_key(key, false)? {
+            Mapped::Local(mapped) => self.blackboard.get(&mapped).map(|val| &**val as &dyn Any),
+            Mapped::Global(mapped) => self
+                .global_blackboard()
+                .get(&mapped)
+                .map(|val| &**val as &dyn Any),
+            Mapped::Literal(mapped) => Some(mapped as &dyn Any),
+        }
     }
 
     /// Get a blackboard variable without downcasting.
-    pub fn get_any(&self, key: impl Into<Symbol>) -> Option<Rc<dyn Any>> {
+    pub fn get_any(&self, key: impl Into<Symbol>) -> Option<Shared<AnyValue>> {
         let key: Symbol = key.into();
-        let mapped = self.blackboard_map.get(&key);
-        let mapped = match mapped {
-            None => &key,
-            Some(BlackboardValue::Ref(mapped, ty)) => {
-                if matches!(*ty, PortType::Input | PortType::InOut) {
-                    mapped
-                } else {
-                    if self.strict {
-                        panic!("Port {:?} is not specified as input or inout port", key);
-                    }
-                    return None;
-                }
-            }
-            Some(BlackboardValue::Literal(mapped)) => {
-                return Some(Rc::new(mapped.clone()));
-            }
+        let live = self.get_any_live(key);
+        match self.tape_read(key, live.as_deref().map(|val| val as &dyn Any)) {
+            ReadOverride::Live => live,
+            ReadOverride::Missing => None,
+            ReadOverride::Value(val) => Some(val.clone()),
+        }
+    }
+
+    fn get_any_live(&self, key: Symbol) -> Option<Shared<AnyValue>> {
+        match self.map_key(key, false)? {
+            Mapped::Local(mapped) => self.blackboard.get(&mapped).cloned(),
+            Mapped::Global(mapped) => self.global_blackboard().get(&mapped).cloned(),
+            Mapped::Literal(mapped) => Some(Shared::new(mapped.clone())),
+        }
+    }
+
+    /// Resolve a port name with the port mapping of the current node.
+    /// Returns `None` if the port is not mapped in the direction, which is reported as an error
+    /// in strict mode.
+    pub(crate) fn map_key(&self, key: Symbol, output: bool) -> Option<Mapped<'_>> {
+        let blackboard_map = match *self.arena {
+            Some(ref arena) => arena.blackboard_map(),
+            None => &self.blackboard_map,
         };
+        let (mapped, ty) = match blackboard_map.get(&key) {
+            None => return Some(Mapped::Local(key)),
+            Some(BlackboardValue::Ref(mapped, ty)) => (Mapped::Local(*mapped), *ty),
+            Some(BlackboardValue::Global(mapped, ty)) => (Mapped::Global(*mapped), *ty),
+            Some(BlackboardValue::Literal(mapped)) => return Some(Mapped::Literal(mapped)),
+        };
+        if output && matches!(ty, PortType::Output | PortType::InOut)
+            || !output && matches!(ty, PortType::Input | PortType::InOut)
+        {
+            return Some(mapped);
+        }
+        if self.strict {
+            let port = key.to_string();
+            self.report_error(if output {
+                TickErrorCause::NotOutput { port }
+            } else {
+                TickErrorCause::NotInput { port }
+            });
+        }
+        None
+    }
 
-        self.blackboard.get(mapped).cloned()
+    fn tape_read<'a>(&'a self, key: Symbol, live: Option<&dyn Any>) -> ReadOverride<'a> {
+        match self.tape.as_ref() {
+            Some(tape) => tape.read(key, live),
+            None => ReadOverride::Live,
+        }
     }
 
     /// Convenience method to get raw primitive types such as f64 or parse from string
@@ -136,38 +504,240 @@ impl Context {
         })
     }
 
-    pub fn set<T: 'static>(&mut self, key: impl Into<Symbol>, val: T) {
-        if let Some(key) = self.map_out_key(key) {
-            self.blackboard.insert(key, Rc::new(val));
+    /// Get an input that the node can't do without, like [`Self::get`], but report a
+    /// [`TickErrorCause::MissingInput`] error if it is missing or has a different type.
+    pub fn require<T: 'static>(&self, key: impl Into<Symbol>) -> Option<&T> {
+        let key: Symbol = key.into();
+        let ret = self.get(key);
+        if ret.is_none() {
+            self.report_error(TickErrorCause::MissingInput {
+                port: key.to_string(),
+            });
         }
+        ret
     }
 
-    pub fn set_any(&mut self, key: impl Into<Symbol>, val: Rc<dyn Any>) {
-        if let Some(key) = self.map_out_key(key) {
-            self.blackboard.insert(key, val);
+    /// [`Self::get_parse`] for a required input, see [`Self::require`].
+    pub fn require_parse<F>(&self, key: impl Into<Symbol> + Copy) -> Option<F>
+    where
+        F: FromStr + Copy + 'static,
+    {
+        let ret = self.get_parse(key);
+        if ret.is_none() {
+            self.report_error(TickErrorCause::MissingInput {
+                port: key.into().to_string(),
+            });
         }
+        ret
+    }
+
+    /// Set a blackboard variable. It counts as a change even if the value is equal to the
+    /// previous one, see [`Self::version`].
+    pub fn set<T: MaybeSendSync + 'static>(&mut self, key: impl Into<Symbol>, val: T) {
+        self.set_any(key, Shared::new(val));
     }
 
-    fn map_out_key(&self, key: impl Into<Symbol>) -> Option<Symbol> {
+    pub fn set_any(&mut self, key: impl Into<Symbol>, val: Shared<AnyValue>) {
         let key = key.into();
-        let mapped = self.blackboard_map.get(&key);
-        match mapped {
-            None => Some(key),
-            Some(BlackboardValue::Ref(mapped, ty)) => {
-                if matches!(ty, PortType::Output | PortType::InOut) {
-                    Some(*mapped)
-                } else {
-                    if self.strict {
-                        panic!("Port {:?} is not specified as output or inout port", key);
-                    }
-                    None
-                }
+        match self.map_key(key, true) {
+            Some(Mapped::Local(key)) => {
+                self.blackboard.insert(key, val);
+                self.touch(key, false);
+            }
+            Some(Mapped::Global(key)) => {
+                self.global_blackboard_mut().insert(key, val);
+                self.touch(key, true);
             }
-            Some(BlackboardValue::Literal(_)) => panic!("Cannot write to a literal!"),
+            Some(Mapped::Literal(_)) => self.report_error(TickErrorCause::LiteralWrite {
+                port: key.to_string(),
+            }),
+            None => (),
+        }
+    }
+
+    /// Mark all the variables changed after replacing the blackboard.
+    pub(crate) fn touch_all(&mut self) {
+        let keys = self.blackboard.keys().copied().collect::<Vec<_>>();
+        for key in keys {
+            self.touch(key, false);
         }
     }
 
-    // pub fn get_env(&mut self) -> Option<&mut E> {
-    //     self.env
-    // }
+    /// Bump the version of a variable in the current or global scope and notify the watchers.
+    pub(crate) fn touch(&mut self, key: Symbol, global: bool) {
+        self.version_counter += 1;
+        // Changes between ticks are attributed to the next tick
+        let tick = if self.depth == 0 {
+            self.tick_count + 1
+        } else {
+            self.tick_count
+        };
+        let version = KeyVersion {
+            version: self.version_counter,
+            tick,
+        };
+        // Local variables of subtrees are not watched
+        let watched = global || self.outer_scopes.is_empty();
+        let (blackboard, versions) = match self.outer_scopes.first_mut() {
+            Some(scope) if global => (&scope.blackboard, &mut scope.versions),
+            _ => (&self.blackboard, &mut self.versions),
+        };
+        versions.insert(key, version);
+        if !watched {
+            return;
+        }
+        if let Some(val) = blackboard.get(&key) {
+            for watcher in self.watchers.0.iter_mut().filter(|w| w.key == key) {
+                (watcher.callback)(key, &**val);
+            }
+        }
+    }
+
+    /// Enter a new blackboard scope, e.g. of a subtree. The current one becomes an outer scope.
+    pub(crate) fn push_scope(&mut self, blackboard: Blackboard, versions: Versions) {
+        let blackboard = std::mem::replace(&mut self.blackboard, blackboard);
+        let versions = std::mem::replace(&mut self.versions, versions);
+        self.outer_scopes.push(Scope {
+            blackboard,
+            versions,
+        });
+    }
+
+    /// Leave the scope entered by [`Self::push_scope`] and return its blackboard.
+    pub(crate) fn pop_scope(&mut self) -> (Blackboard, Versions) {
+        let scope = self
+            .outer_scopes
+            .pop()
+            .expect("pop_scope should be paired with push_scope");
+        (
+            std::mem::replace(&mut self.blackboard, scope.blackboard),
+            std::mem::replace(&mut self.versions, scope.versions),
+        )
+    }
+
+    pub(crate) fn in_subtree(&self) -> bool {
+        !self.outer_scopes.is_empty()
+    }
+
+    /// The blackboard of the root scope, which is the same as [`Self::blackboard`] unless
+    /// we are in a subtree.
+    pub fn global_blackboard(&self) -> &Blackboard {
+        self.outer_scopes
+            .first()
+            .map_or(&self.blackboard, |scope| &scope.blackboard)
+    }
+
+    fn global_blackboard_mut(&mut self) -> &mut Blackboard {
+        match self.outer_scopes.first_mut() {
+            Some(scope) => &mut scope.blackboard,
+            None => &mut self.blackboard,
+        }
+    }
+
+    /// Get a variable in the global blackboard, bypassing port mapping.
+    /// It is the same as mapping a port to `@key` in the source.
+    pub fn get_global<T: 'static>(&self, key: impl Into<Symbol>) -> Option<&T> {
+        let key: Symbol = key.into();
+        let live = self
+            .global_blackboard()
+            .get(&key)
+            .map(|val| &**val as &dyn Any);
+        let val = match self.tape_read(key, live) {
+            ReadOverride::Live => live,
+            ReadOverride::Missing => None,
+            ReadOverride::Value(val) => Some(&**val as &dyn Any),
+        };
+        val.and_then(|val| val.downcast_ref())
+    }
+
+    /// Set a variable in the global blackboard, bypassing port mapping.
+    pub fn set_global<T: MaybeSendSync + 'static>(&mut self, key: impl Into<Symbol>, val: T) {
+        let key = key.into();
+        self.global_blackboard_mut().insert(key, Shared::new(val));
+        self.touch(key, true);
+    }
+
+    /// [`Self::version`] of a variable in the global blackboard.
+    pub fn global_version(&self, key: impl Into<Symbol>) -> Option<u64> {
+        let versions = self
+            .outer_scopes
+            .first()
+            .map_or(&self.versions, |scope| &scope.versions);
+        versions.get(&key.into()).map(|v| v.version)
+    }
+
+    /// The number of ticks started from the root node so far.
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    /// A number that increases every time a variable is set, or `None` if it has never been set.
+    /// Compare it with a previously obtained value to see if the variable has changed.
+    ///
+    /// The key is the name of the variable in the current blackboard, not a port name.
+    pub fn version(&self, key: impl Into<Symbol>) -> Option<u64> {
+        self.versions.get(&key.into()).map(|v| v.version)
+    }
+
+    /// The [tick](Self::tick_count) in which the variable was last set.
+    /// Changes made between ticks count as the next tick.
+    pub fn last_changed(&self, key: impl Into<Symbol>) -> Option<u64> {
+        self.versions.get(&key.into()).map(|v| v.tick)
+    }
+
+    /// Returns true if the variable was set after the tick `tick` has finished.
+    ///
+    /// ```
+    /// # use behavior_tree_lite::Context;
+    /// let mut ctx = Context::default();
+    /// let tick = ctx.tick_count();
+    /// assert!(!ctx.changed_since("hp", tick));
+    /// ctx.set("hp", 10);
+    /// assert!(ctx.changed_since("hp", tick));
+    /// ```
+    pub fn changed_since(&self, key: impl Into<Symbol>, tick: u64) -> bool {
+        self.last_changed(key).is_some_and(|changed| tick < changed)
+    }
+
+    /// Install a callback that is called every time the variable is set, with the key and
+    /// the new value.
+    ///
+    /// Only the variables of the root (global) blackboard are watched, including the ones
+    /// written as `@name` from subtrees. The local variables of subtrees are not watched, but
+    /// the output ports of a subtree notify when they are written back.
+    ///
+    /// ```
+    /// # use behavior_tree_lite::Context;
+    /// let mut ctx = Context::default();
+    /// let (tx, rx) = std::sync::mpsc::channel();
+    /// let id = ctx.watch("hp", move |_, value| {
+    ///     tx.send(*value.downcast_ref::<i32>().unwrap()).unwrap();
+    /// });
+    /// ctx.set("hp", 10);
+    /// ctx.set("mp", 20);
+    /// ctx.unwatch(id);
+    /// ctx.set("hp", 30);
+    /// assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![10]);
+    /// ```
+    pub fn watch(
+        &mut self,
+        key: impl Into<Symbol>,
+        callback: impl FnMut(Symbol, &dyn Any) + MaybeSend + 'static,
+    ) -> WatchId {
+        let id = WatchId(self.next_watch_id);
+        self.next_watch_id += 1;
+        self.watchers.0.push(Watcher {
+            id,
+            key: key.into(),
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    /// Remove a watcher. Returns false if it was already removed.
+    pub fn unwatch(&mut self, id: WatchId) -> bool {
+        let len = self.watchers.len();
+        self.watchers.0.retain(|w| w.id != id);
+        len != self.watchers.len()
+    }
 }
@@ -0,0 +1,348 @@
+//! Coverage of a tree, to find out which nodes and branches are never exercised.
+//!
+//! Install a [`Coverage`] observer on the [`Context`](crate::Context), run your test scenarios,
+//! and then make a [`CoverageReport`] out of it.
+//! The report counts each node as covered if it was ticked at all, and each of its
+//! outcomes (`Success`, `Fail` and `Running`) as a branch.
+//! `if` statements have two more branches, `then` and `else`.
+//!
+//! If the tree was loaded from a source text, the nodes can be mapped back to the lines
+//! with [`Coverage::report_with_source`], which enables the lcov output and the
+//! annotated source.
+//! The locations come from the [`TreeSource`], so the text is only needed to annotate it.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, coverage::*};
+//! let text = "tree main = Sequence {\n    Fallback\n}\n";
+//! let (_, source) = parse_file(text).unwrap();
+//! let mut tree = load(&source, &Registry::default(), false).unwrap();
+//! let mut ctx = Context::default();
+//! ctx.add_observer(Coverage::new());
+//! tree.tick(&mut |_| None, &mut ctx);
+//!
+//! let report = ctx
+//!     .observer::<Coverage>()
+//!     .unwrap()
+//!     .report_with_source(&tree, &source);
+//! assert_eq!(report.nodes_hit(), (2, 2));
+//! println!("{}", report.annotate(text));
+//! ```
+
+use crate::{
+    observer::{TickEvent, TickObserver},
+    parser::{NodeKind, SourceLocation, TreeDef, TreeSource},
+    BehaviorNodeContainer, BehaviorResult, Env, NodeId,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    io::Write,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Hits {
+    success: u64,
+    fail: u64,
+    running: u64,
+}
+
+impl Hits {
+    fn total(&self) -> u64 {
+        self.success + self.fail + self.running
+    }
+}
+
+/// A [`TickObserver`] that counts the results of each node.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    hits: HashMap<NodeId, Hits>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.hits.clear();
+    }
+
+    /// Make a report for all the nodes in the tree, including the ones never ticked.
+    pub fn report<E: Env>(&self, tree: &BehaviorNodeContainer<E>) -> CoverageReport {
+        let mut nodes = vec![];
+        self.collect(tree, &HashMap::new(), &mut nodes);
+        CoverageReport { nodes }
+    }
+
+    /// Make a report with the nodes mapped to the source locations.
+    /// `source` should be the one the tree was loaded from.
+    pub fn report_with_source<E: Env>(
+        &self,
+        tree: &BehaviorNodeContainer<E>,
+        source: &TreeSource,
+    ) -> CoverageReport {
+        let mut locations = HashMap::new();
+        if let Some(main) = source.tree_defs.iter().find(|tree| tree.name == "main") {
+            map_locations(tree, &main.root, source, None, &mut locations);
+        }
+        let mut nodes = vec![];
+        self.collect(tree, &locations, &mut nodes);
+        CoverageReport { nodes }
+    }
+
+    fn collect<E: Env>(
+        &self,
+        node: &BehaviorNodeContainer<E>,
+        locations: &HashMap<NodeId, SourceLocation>,
+        out: &mut Vec<NodeCoverage>,
+    ) {
+        let hits = |node: &BehaviorNodeContainer<E>| self.hits.get(&node.id()).copied();
+        let node_hits = hits(node).unwrap_or_default();
+
+        let if_branches = if node.kind() == NodeKind::If {
+            let children = node.children();
+            let then_taken = children.get(1).and_then(hits).unwrap_or_default().total();
+            let else_taken = match children.get(2) {
+                Some(child) => hits(child).unwrap_or_default().total(),
+                // Without an else clause, the else branch is taken when the condition fails.
+                None => children.first().and_then(hits).unwrap_or_default().fail,
+            };
+            Some(IfBranches {
+                then_taken,
+                else_taken,
+            })
+        } else {
+            None
+        };
+
+        out.push(NodeCoverage {
+            id: node.id(),
+            path: node.path().to_owned(),
+            name: node.name().to_owned(),
+            location: locations.get(&node.id()).copied(),
+            hits: node_hits.total(),
+            success: node_hits.success,
+            fail: node_hits.fail,
+            running: node_hits.running,
+            if_branches,
+        });
+
+        for child in node.children() {
+            self.collect(child, locations, out);
+        }
+    }
+}
+
+impl TickObserver for Coverage {
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, _duration: Duration) {
+        let hits = self.hits.entry(event.id).or_default();
+        match result {
+            BehaviorResult::Success => hits.success += 1,
+            BehaviorResult::Fail => hits.fail += 1,
+            BehaviorResult::Running => hits.running += 1,
+        }
+    }
+}
+
+/// Walk the tree and its definition side by side, the same way as [`crate::load`] builds it.
+fn map_locations<E: Env>(
+    node: &BehaviorNodeContainer<E>,
+    def: &TreeDef,
+    source: &TreeSource,
+    parent_location: Option<SourceLocation>,
+    out: &mut HashMap<NodeId, SourceLocation>,
+) {
+    // Nodes without their own location, e.g. `SetBool` for `var a = true`,
+    // are attributed to the parent.
+    let location = def.location().or(parent_location);
+    if let Some(location) = location {
+        out.insert(node.id(), location);
+    }
+    if node.is_subtree() {
+        let subtree = source
+            .tree_defs
+            .iter()
+            .find(|tree| tree.name == def.get_type());
+        if let (Some(child), Some(subtree)) = (node.children().first(), subtree) {
+            map_locations(child, &subtree.root, source, location, out);
+        }
+    } else {
+        for (child, child_def) in node.children().iter().zip(def.children()) {
+            map_locations(child, child_def, source, location, out);
+        }
+    }
+}
+
+/// How many times each branch of an `if` node was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfBranches {
+    pub then_taken: u64,
+    pub else_taken: u64,
+}
+
+/// Coverage of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeCoverage {
+    pub id: NodeId,
+    /// See [`BehaviorNodeContainer::path`]
+    pub path: String,
+    /// Name of the type of the node
+    pub name: String,
+    /// Only available in [`Coverage::report_with_source`]
+    pub location: Option<SourceLocation>,
+    /// Number of times the node was ticked
+    pub hits: u64,
+    pub success: u64,
+    pub fail: u64,
+    pub running: u64,
+    /// Only for `if` statements, see [`NodeKind::If`]
+    pub if_branches: Option<IfBranches>,
+}
+
+impl NodeCoverage {
+    /// Hit counts of each branch with its name.
+    pub fn branches(&self) -> Vec<(&'static str, u64)> {
+        let mut ret = vec![
+            ("Success", self.success),
+            ("Fail", self.fail),
+            ("Running", self.running),
+        ];
+        if let Some(branches) = self.if_branches {
+            ret.push(("then", branches.then_taken));
+            ret.push(("else", branches.else_taken));
+        }
+        ret
+    }
+}
+
+/// Coverage of all the nodes in a tree, in depth-first pre-order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub nodes: Vec<NodeCoverage>,
+}
+
+impl CoverageReport {
+    /// Returns the number of nodes ticked at least once and the number of all nodes.
+    pub fn nodes_hit(&self) -> (usize, usize) {
+        let hit = self.nodes.iter().filter(|node| node.hits != 0).count();
+        (hit, self.nodes.len())
+    }
+
+    /// Returns the number of branches taken at least once and the number of all branches.
+    pub fn branches_hit(&self) -> (usize, usize) {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.branches())
+            .fold((0, 0), |(hit, total), (_, count)| {
+                (hit + (count != 0) as usize, total + 1)
+            })
+    }
+
+    /// Nodes that were never ticked.
+    pub fn missed_nodes(&self) -> impl Iterator<Item = &NodeCoverage> {
+        self.nodes.iter().filter(|node| node.hits == 0)
+    }
+
+    pub fn summary(&self) -> String {
+        fn percent(hit: usize, total: usize) -> f64 {
+            if total == 0 {
+                100.
+            } else {
+                hit as f64 * 100. / total as f64
+            }
+        }
+        let (nodes_hit, nodes) = self.nodes_hit();
+        let (branches_hit, branches) = self.branches_hit();
+        let mut ret = String::new();
+        writeln!(
+            ret,
+            "Nodes:    {nodes_hit}/{nodes} ({:.1}%)",
+            percent(nodes_hit, nodes)
+        )
+        .unwrap();
+        writeln!(
+            ret,
+            "Branches: {branches_hit}/{branches} ({:.1}%)",
+            percent(branches_hit, branches)
+        )
+        .unwrap();
+        for node in self.missed_nodes() {
+            match node.location {
+                Some(loc) => writeln!(ret, "Missed: {} at {}:{}", node.path, loc.line, loc.column),
+                None => writeln!(ret, "Missed: {}", node.path),
+            }
+            .unwrap();
+        }
+        ret
+    }
+
+    /// Nodes that have locations, grouped by line.
+    fn lines(&self) -> BTreeMap<usize, Vec<&NodeCoverage>> {
+        let mut ret = BTreeMap::<usize, Vec<_>>::new();
+        for node in &self.nodes {
+            if let Some(location) = node.location {
+                ret.entry(location.line).or_default().push(node);
+            }
+        }
+        ret
+    }
+
+    /// Write the report in the lcov tracefile format, which can be processed by tools like
+    /// `genhtml`. Only nodes with source locations are included.
+    ///
+    /// A subtree used at several places counts the hits of all of them in the lines of
+    /// its definition.
+    pub fn write_lcov(&self, mut writer: impl Write, source_file: &str) -> std::io::Result<()> {
+        writeln!(writer, "TN:")?;
+        writeln!(writer, "SF:{source_file}")?;
+        let lines = self.lines();
+        let (mut branches, mut branches_hit) = (0, 0);
+        for (line, nodes) in &lines {
+            for node in nodes {
+                for (i, (_, count)) in node.branches().into_iter().enumerate() {
+                    let taken = if node.hits == 0 {
+                        "-".to_owned()
+                    } else {
+                        count.to_string()
+                    };
+                    writeln!(writer, "BRDA:{line},{},{i},{taken}", node.id.0)?;
+                    branches += 1;
+                    branches_hit += (count != 0) as usize;
+                }
+            }
+        }
+        writeln!(writer, "BRF:{branches}")?;
+        writeln!(writer, "BRH:{branches_hit}")?;
+        let mut lines_hit = 0;
+        for (line, nodes) in &lines {
+            let hits = nodes.iter().map(|node| node.hits).sum::<u64>();
+            writeln!(writer, "DA:{line},{hits}")?;
+            lines_hit += (hits != 0) as usize;
+        }
+        writeln!(writer, "LF:{}", lines.len())?;
+        writeln!(writer, "LH:{lines_hit}")?;
+        writeln!(writer, "end_of_record")
+    }
+
+    /// The source text with the number of hits at the beginning of each line, like `gcov`.
+    /// Lines with nodes never ticked are marked with `#####`, and lines without nodes with `-`.
+    pub fn annotate(&self, text: &str) -> String {
+        let lines = self.lines();
+        let mut ret = String::new();
+        for (i, line) in text.lines().enumerate() {
+            let count = match lines.get(&(i + 1)) {
+                Some(nodes) => match nodes.iter().map(|node| node.hits).sum::<u64>() {
+                    0 => "#####".to_owned(),
+                    hits => hits.to_string(),
+                },
+                None => "-".to_owned(),
+            };
+            writeln!(ret, "{count:>9}:{:>5}:{line}", i + 1).unwrap();
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,114 @@
+use super::*;
+use crate::{load, parse_file, Context, Registry};
+
+const SOURCE: &str = r#"tree main = Sequence {
+    var flag = true
+    if (flag) {
+        sub
+    } else {
+        Fallback
+    }
+}
+
+tree sub = Inverter {
+    ForceSuccess
+}
+"#;
+
+fn run() -> (BehaviorNodeContainer, Coverage) {
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let mut tree = load(&source, &Registry::default(), false).unwrap();
+    let mut ctx = Context::default();
+    ctx.add_observer(Coverage::new());
+    tree.tick(&mut |_| None, &mut ctx);
+    (tree, ctx.remove_observer().unwrap())
+}
+
+#[test]
+fn test_report() {
+    let (tree, coverage) = run();
+    let report = coverage.report(&tree);
+    let summary = report
+        .nodes
+        .iter()
+        .map(|node| (node.path.as_str(), node.hits, node.location))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("main", 1, None),
+            ("main/SetBool[0]", 1, None),
+            ("main/if[0]", 1, None),
+            ("main/if[0]/IsTrue[0]", 1, None),
+            ("main/if[0]/Sequence[0]", 1, None),
+            ("main/if[0]/Sequence[0]/sub[0]", 1, None),
+            ("main/if[0]/Sequence[0]/sub[0]/Inverter[0]", 1, None),
+            (
+                "main/if[0]/Sequence[0]/sub[0]/Inverter[0]/ForceSuccess[0]",
+                1,
+                None
+            ),
+            ("main/if[0]/Sequence[1]", 0, None),
+            ("main/if[0]/Sequence[1]/Fallback[0]", 0, None),
+        ]
+    );
+    assert_eq!(
+        report.nodes[2].if_branches,
+        Some(IfBranches {
+            then_taken: 1,
+            else_taken: 0
+        })
+    );
+    assert_eq!(report.nodes_hit(), (8, 10));
+    // 10 nodes with 3 outcomes, and 2 more for the if node
+    assert_eq!(report.branches_hit().1, 32);
+    assert_eq!(report.missed_nodes().count(), 2);
+}
+
+#[test]
+fn test_source() {
+    let (tree, coverage) = run();
+    let (_, source) = parse_file(SOURCE).unwrap();
+    let report = coverage.report_with_source(&tree, &source);
+    let lines = report
+        .nodes
+        .iter()
+        .map(|node| node.location.map(|loc| (loc.line, loc.column)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            Some((1, 13)),
+            // SetBool is attributed to the parent
+            Some((1, 13)),
+            Some((3, 5)),
+            Some((3, 9)),
+            Some((4, 9)),
+            Some((4, 9)),
+            Some((10, 12)),
+            Some((11, 5)),
+            Some((6, 9)),
+            Some((6, 9)),
+        ]
+    );
+
+    let annotated = report.annotate(SOURCE);
+    let annotated = annotated.lines().collect::<Vec<_>>();
+    assert_eq!(annotated[0], "        2:    1:tree main = Sequence {");
+    assert_eq!(annotated[4], "        -:    5:    } else {");
+    assert_eq!(annotated[5], "    #####:    6:        Fallback");
+
+    let mut lcov = vec![];
+    report.write_lcov(&mut lcov, "main.btc").unwrap();
+    let lcov = String::from_utf8(lcov).unwrap();
+    assert!(lcov.starts_with("TN:\nSF:main.btc\n"));
+    assert!(lcov.contains("DA:6,0\n"));
+    assert!(lcov.contains("DA:3,2\n"));
+    assert!(lcov.contains("BRDA:6,9,0,-\n"));
+    assert!(lcov.contains("LF:6\nLH:5\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+
+    let summary = report.summary();
+    assert!(summary.contains("Nodes:    8/10 (80.0%)"));
+    assert!(summary.contains("Missed: main/if[0]/Sequence[1]/Fallback[0] at 6:9"));
+}
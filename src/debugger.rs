@@ -0,0 +1,272 @@
+//! An interactive step debugger that can pause in the middle of a tick.
+//!
+//! A [`Debugger`] is installed on a [`Context`] with [`Context::set_debugger`].
+//! When a [`Breakpoint`] is hit, or a step is requested, the tick is paused by calling
+//! the user callback with a [`DebugView`], which can inspect the node and the blackboard.
+//! The callback returns a [`DebugAction`] to tell how to resume.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, debugger::*};
+//! let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+//! tree.assign_ids("main");
+//!
+//! let mut debugger = Debugger::new(|view: &DebugView| {
+//!     println!("Paused at {}", view.path());
+//!     DebugAction::Continue
+//! });
+//! debugger.add_breakpoint(Breakpoint::path("main"));
+//!
+//! let mut ctx = Context::default();
+//! ctx.set_debugger(debugger);
+//! tree.tick(&mut |_| None, &mut ctx);
+//! ```
+
+use crate::{
+    BehaviorNodeContainer, BehaviorResult, Blackboard, CallbackEnv, Context, Env, MaybeSend,
+    NodeId, TickEvent,
+};
+
+/// Which node a [`Breakpoint`] is set on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakTarget {
+    Id(NodeId),
+    /// See [`BehaviorNodeContainer::path`]
+    Path(String),
+    /// Name of the type of the node, which matches all nodes of the type
+    Type(String),
+}
+
+/// When a [`Breakpoint`] pauses the tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakCondition {
+    /// Before the node is ticked
+    Enter,
+    /// After the node is ticked, if the result matches. `None` matches any result.
+    Exit(Option<BehaviorResult>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub target: BreakTarget,
+    pub condition: BreakCondition,
+}
+
+impl Breakpoint {
+    pub fn id(id: NodeId) -> Self {
+        Self::new(BreakTarget::Id(id))
+    }
+
+    pub fn path(path: impl Into<String>) -> Self {
+        Self::new(BreakTarget::Path(path.into()))
+    }
+
+    pub fn node_type(name: impl Into<String>) -> Self {
+        Self::new(BreakTarget::Type(name.into()))
+    }
+
+    fn new(target: BreakTarget) -> Self {
+        Self {
+            target,
+            condition: BreakCondition::Enter,
+        }
+    }
+
+    /// Break after the node is ticked, regardless of the result.
+    pub fn on_exit(mut self) -> Self {
+        self.condition = BreakCondition::Exit(None);
+        self
+    }
+
+    /// Break after the node is ticked, only if it returned `result`.
+    pub fn on_result(mut self, result: BehaviorResult) -> Self {
+        self.condition = BreakCondition::Exit(Some(result));
+        self
+    }
+
+    fn matches(&self, node: &TickEvent, event: DebugEvent) -> bool {
+        let target = match &self.target {
+            BreakTarget::Id(id) => node.id == *id,
+            BreakTarget::Path(path) => node.path == path,
+            BreakTarget::Type(name) => node.name == name,
+        };
+        target
+            && match (self.condition, event) {
+                (BreakCondition::Enter, DebugEvent::Enter) => true,
+                (BreakCondition::Exit(None), DebugEvent::Exit(_)) => true,
+                (BreakCondition::Exit(Some(expected)), DebugEvent::Exit(res)) => expected == res,
+                _ => false,
+            }
+    }
+}
+
+/// The reason the tick was paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    /// The node is about to be ticked
+    Enter,
+    /// The node has been ticked with the result
+    Exit(BehaviorResult),
+}
+
+/// What to do after the debugger callback returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Pause at the very next node entered, which is the first child if it is paused on entering a node.
+    StepInto,
+    /// Pause at the next node entered at the same or shallower depth, skipping the children.
+    StepOver,
+    /// Abort the rest of the tick. Every node that has not finished returns `Running`,
+    /// so that the tree can resume in the next tick. See [`Context::is_aborted`].
+    Abort,
+}
+
+/// The state of the tick at the moment it is paused.
+pub struct DebugView<'a, E: Env = CallbackEnv> {
+    event: DebugEvent,
+    depth: usize,
+    target: &'a TickEvent<'a>,
+    node: Option<&'a BehaviorNodeContainer<E>>,
+    ctx: &'a Context<E>,
+}
+
+impl<'a, E: Env> DebugView<'a, E> {
+    pub fn event(&self) -> DebugEvent {
+        self.event
+    }
+
+    /// Depth of the node from the root, which is 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.target.id
+    }
+
+    /// See [`BehaviorNodeContainer::path`].
+    pub fn path(&self) -> &'a str {
+        self.target.path
+    }
+
+    /// The type name of the node.
+    pub fn name(&self) -> &'a str {
+        self.target.name
+    }
+
+    /// The paused node, or `None` if it is a node of an [`crate::ArenaTree`], which has no
+    /// container.
+    pub fn node(&self) -> Option<&'a BehaviorNodeContainer<E>> {
+        self.node
+    }
+
+    /// The blackboard the node reads and writes.
+    pub fn blackboard(&self) -> &'a Blackboard {
+        self.ctx.blackboard()
+    }
+
+    pub fn context(&self) -> &'a Context<E> {
+        self.ctx
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Run,
+    StepInto,
+    StepOver(usize),
+}
+
+#[cfg(not(feature = "sync"))]
+type DebugCallback<E> = Box<dyn FnMut(&DebugView<E>) -> DebugAction>;
+#[cfg(feature = "sync")]
+type DebugCallback<E> = Box<dyn FnMut(&DebugView<E>) -> DebugAction + Send>;
+
+pub struct Debugger<E: Env = CallbackEnv> {
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    callback: DebugCallback<E>,
+}
+
+impl<E: Env> std::fmt::Debug for Debugger<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl<E: Env> Debugger<E> {
+    pub fn new(callback: impl FnMut(&DebugView<E>) -> DebugAction + MaybeSend + 'static) -> Self {
+        Self {
+            breakpoints: vec![],
+            mode: Mode::Run,
+            callback: Box::new(callback),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|b| b != breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Pause at the first node entered in the next tick.
+    pub fn break_on_next(&mut self) {
+        self.mode = Mode::StepInto;
+    }
+
+    fn should_break(&self, node: &TickEvent, event: DebugEvent, depth: usize) -> bool {
+        let stepping = match (event, self.mode) {
+            (DebugEvent::Enter, Mode::StepInto) => true,
+            (DebugEvent::Enter, Mode::StepOver(step_depth)) => depth <= step_depth,
+            _ => false,
+        };
+        stepping || self.breakpoints.iter().any(|b| b.matches(node, event))
+    }
+}
+
+/// Called by [`BehaviorNodeContainer::tick`] and [`crate::ArenaTree`] on entering and
+/// exiting a node, which is given as a container if any.
+/// It may set the abort flag of the context.
+pub(crate) fn debug_hook<E: Env>(
+    target: &TickEvent,
+    node: Option<&BehaviorNodeContainer<E>>,
+    ctx: &mut Context<E>,
+    event: DebugEvent,
+) {
+    let Some(mut debugger) = ctx.debugger.take() else {
+        return;
+    };
+    let depth = ctx.depth;
+    if debugger.should_break(target, event, depth) {
+        let view = DebugView {
+            event,
+            depth,
+            target,
+            node,
+            ctx,
+        };
+        match (debugger.callback)(&view) {
+            DebugAction::Continue => debugger.mode = Mode::Run,
+            DebugAction::StepInto => debugger.mode = Mode::StepInto,
+            DebugAction::StepOver => debugger.mode = Mode::StepOver(depth),
+            DebugAction::Abort => {
+                debugger.mode = Mode::Run;
+                ctx.aborted = true;
+            }
+        }
+    }
+    ctx.debugger = Some(debugger);
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,175 @@
+use super::*;
+use crate::{BehaviorCallback, BehaviorNode, SequenceNode};
+use std::sync::{Arc, Mutex};
+
+struct Succeed;
+
+impl BehaviorNode for Succeed {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        ctx.set("ticked", true);
+        BehaviorResult::Success
+    }
+}
+
+struct Fail;
+
+impl BehaviorNode for Fail {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Fail
+    }
+}
+
+fn named(node: impl BehaviorNode + 'static, name: &str) -> BehaviorNodeContainer {
+    BehaviorNodeContainer::new_node(node).with_name(name.to_owned())
+}
+
+/// main
+/// |-- Succeed[0]
+/// `-- Sequence[0]
+///     |-- Succeed[0]
+///     `-- Fail[0]
+fn build_tree() -> BehaviorNodeContainer {
+    let mut tree = named(SequenceNode::default(), "Sequence");
+    tree.add_child(named(Succeed, "Succeed")).unwrap();
+    let mut sub = named(SequenceNode::default(), "Sequence");
+    sub.add_child(named(Succeed, "Succeed")).unwrap();
+    sub.add_child(named(Fail, "Fail")).unwrap();
+    tree.add_child(sub).unwrap();
+    tree.assign_ids("main");
+    tree
+}
+
+/// Make a debugger that records the paused paths and replies with the given actions in order,
+/// then `Continue`.
+fn recording_debugger(actions: Vec<DebugAction>) -> (Debugger, Arc<Mutex<Vec<String>>>) {
+    let log = Arc::new(Mutex::new(vec![]));
+    let log2 = log.clone();
+    let mut actions = actions.into_iter();
+    let debugger = Debugger::new(move |view: &DebugView| {
+        let event = match view.event() {
+            DebugEvent::Enter => "enter".to_owned(),
+            DebugEvent::Exit(res) => format!("exit {:?}", res),
+        };
+        log2.lock()
+            .unwrap()
+            .push(format!("{} {} {}", event, view.depth(), view.path()));
+        actions.next().unwrap_or(DebugAction::Continue)
+    });
+    (debugger, log)
+}
+
+#[test]
+fn test_breakpoint_path() {
+    let mut tree = build_tree();
+    let (mut debugger, log) = recording_debugger(vec![]);
+    debugger.add_breakpoint(Breakpoint::path("main/Sequence[0]/Fail[0]"));
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Fail);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["enter 2 main/Sequence[0]/Fail[0]"]
+    );
+}
+
+#[test]
+fn test_breakpoint_type_and_id() {
+    let mut tree = build_tree();
+    let (mut debugger, log) = recording_debugger(vec![]);
+    debugger.add_breakpoint(Breakpoint::node_type("Succeed"));
+    debugger.add_breakpoint(Breakpoint::id(NodeId(2)));
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "enter 1 main/Succeed[0]",
+            "enter 1 main/Sequence[0]",
+            "enter 2 main/Sequence[0]/Succeed[0]",
+        ]
+    );
+}
+
+#[test]
+fn test_breakpoint_on_result() {
+    let mut tree = build_tree();
+    let (mut debugger, log) = recording_debugger(vec![]);
+    debugger.add_breakpoint(Breakpoint::node_type("Sequence").on_result(BehaviorResult::Fail));
+    debugger.add_breakpoint(Breakpoint::path("main/Succeed[0]").on_result(BehaviorResult::Fail));
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["exit Fail 1 main/Sequence[0]", "exit Fail 0 main"]
+    );
+}
+
+#[test]
+fn test_step() {
+    let mut tree = build_tree();
+    let (mut debugger, log) = recording_debugger(vec![
+        DebugAction::StepInto,
+        DebugAction::StepOver,
+        DebugAction::StepOver,
+        DebugAction::Continue,
+    ]);
+    debugger.break_on_next();
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "enter 0 main",
+            "enter 1 main/Succeed[0]",
+            "enter 1 main/Sequence[0]",
+        ]
+    );
+
+    // Stepping over the last node in the tick pauses at the root in the next tick
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(log.lock().unwrap().last().unwrap(), "enter 0 main");
+}
+
+#[test]
+fn test_inspect_blackboard() {
+    let mut tree = build_tree();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let seen2 = seen.clone();
+    let mut debugger = Debugger::new(move |view: &DebugView| {
+        seen2
+            .lock()
+            .unwrap()
+            .push(view.blackboard().contains_key(&"ticked".into()));
+        DebugAction::Continue
+    });
+    debugger.add_breakpoint(Breakpoint::path("main/Succeed[0]"));
+    debugger.add_breakpoint(Breakpoint::path("main/Succeed[0]").on_exit());
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(*seen.lock().unwrap(), vec![false, true]);
+}
+
+#[test]
+fn test_abort() {
+    let mut tree = build_tree();
+    let (mut debugger, log) = recording_debugger(vec![DebugAction::Abort]);
+    debugger.add_breakpoint(Breakpoint::path("main/Sequence[0]/Fail[0]"));
+    let mut ctx = Context::default();
+    ctx.set_debugger(debugger);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert!(ctx.is_aborted());
+    assert_eq!(
+        tree.children()[0].last_result(),
+        Some(BehaviorResult::Success)
+    );
+    assert_eq!(tree.children()[1].children()[1].last_result(), None);
+    assert_eq!(log.lock().unwrap().len(), 1);
+
+    ctx.take_debugger();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Fail);
+    assert!(!ctx.is_aborted());
+}
@@ -0,0 +1,131 @@
+//! Environment types that the nodes communicate with.
+//!
+//! The first argument of [`crate::BehaviorNode::tick`] is the environment, whose type is
+//! determined by the type parameter `E` of the node, the [`crate::Context`] and the tree.
+//! The default is [`CallbackEnv`], a callback taking and returning `dyn Any`, which you can
+//! replace with your own type to avoid downcasting.
+//!
+//! The environment type is a "family" of types parameterized by a lifetime, so that
+//! it can contain references that only live during a tick, while the tree itself doesn't
+//! borrow anything.
+
+use crate::{record::ArgTape, BehaviorCallback};
+
+/// A family of environment types passed to the nodes on each tick.
+///
+/// [`Self::Arg`] is the type that the nodes actually receive, which usually borrows the
+/// environment only during the tick.
+/// Since it is passed by value, a node that ticks its children multiple times needs to
+/// [reborrow](Self::reborrow) it for each child.
+///
+/// If your environment doesn't have lifetimes, [`MutEnv`] is the easiest way to use it.
+/// Otherwise, you can define a handle with a lifetime like below.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// struct Game<'a> {
+///     log: &'a mut Vec<String>,
+///     player: &'a str,
+/// }
+///
+/// struct GameEnv;
+///
+/// impl Env for GameEnv {
+///     type Arg<'a> = Game<'a>;
+///
+///     fn reborrow<'b>(arg: &'b mut Game<'_>) -> Game<'b> {
+///         Game {
+///             log: &mut *arg.log,
+///             player: arg.player,
+///         }
+///     }
+/// }
+///
+/// struct Greet;
+///
+/// impl BehaviorNode<GameEnv> for Greet {
+///     fn tick(&mut self, game: Game, _ctx: &mut Context<GameEnv>) -> BehaviorResult {
+///         game.log.push(format!("Hello, {}!", game.player));
+///         BehaviorResult::Success
+///     }
+/// }
+///
+/// let mut tree = BehaviorNodeContainer::new_generic(SequenceNode::default());
+/// tree.add_child(BehaviorNodeContainer::new_generic(Greet)).unwrap();
+/// tree.add_child(BehaviorNodeContainer::new_generic(Greet)).unwrap();
+///
+/// let mut log = vec![];
+/// let player = "Alice".to_string();
+/// let game = Game { log: &mut log, player: &player };
+/// tree.tick(game, &mut Context::new_generic(Blackboard::new()));
+/// assert_eq!(log, ["Hello, Alice!", "Hello, Alice!"]);
+/// ```
+pub trait Env: 'static {
+    type Arg<'a>;
+
+    /// Borrow the argument for a shorter lifetime, e.g. to pass it to a child node.
+    fn reborrow<'b>(arg: &'b mut Self::Arg<'_>) -> Self::Arg<'b>;
+
+    /// Give `f` the argument to tick the tree with while a [`crate::record::Recorder`] or
+    /// a [`crate::record::Replayer`] is ticking it.
+    ///
+    /// [`CallbackEnv`] wraps the callback to record its responses, and feed them back in replay.
+    /// Other environments are opaque to the recorder, so the default passes the argument as is
+    /// and only the blackboard reads and the results are recorded.
+    fn record_arg<R>(arg: Self::Arg<'_>, _tape: &ArgTape, f: impl FnOnce(Self::Arg<'_>) -> R) -> R {
+        f(arg)
+    }
+}
+
+/// The default environment, a callback that takes and returns any type.
+/// See [`BehaviorCallback`].
+pub struct CallbackEnv;
+
+impl Env for CallbackEnv {
+    type Arg<'a> = BehaviorCallback<'a>;
+
+    fn reborrow<'b>(arg: &'b mut Self::Arg<'_>) -> Self::Arg<'b> {
+        &mut **arg
+    }
+
+    fn record_arg<R>(arg: Self::Arg<'_>, tape: &ArgTape, f: impl FnOnce(Self::Arg<'_>) -> R) -> R {
+        f(&mut |req| tape.call(&mut *arg, req))
+    }
+}
+
+/// An environment that gives the nodes `&mut T`.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// struct Counter(usize);
+///
+/// struct Increment;
+///
+/// impl BehaviorNode<MutEnv<Counter>> for Increment {
+///     fn tick(&mut self, counter: &mut Counter, _ctx: &mut Context<MutEnv<Counter>>) -> BehaviorResult {
+///         counter.0 += 1;
+///         BehaviorResult::Success
+///     }
+/// }
+///
+/// let mut registry = Registry::new_generic();
+/// registry.register("Increment", boxify(|| Increment));
+/// let (_, source) = parse_file("tree main = Sequence { Increment Increment }").unwrap();
+/// let mut tree = load(&source, &registry, true).unwrap();
+///
+/// let mut counter = Counter(0);
+/// tree.tick(&mut counter, &mut Context::new_generic(Blackboard::new()));
+/// assert_eq!(counter.0, 2);
+/// ```
+pub struct MutEnv<T: ?Sized>(std::marker::PhantomData<fn(&mut T)>);
+
+impl<T: ?Sized + 'static> Env for MutEnv<T> {
+    type Arg<'a> = &'a mut T;
+
+    fn reborrow<'b>(arg: &'b mut Self::Arg<'_>) -> Self::Arg<'b> {
+        &mut **arg
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,127 @@
+use super::*;
+use crate::{
+    boxify, load, nodes::InverterNode, parse_file, BehaviorNode, BehaviorNodeContainer,
+    BehaviorResult, Blackboard, Context, FallbackNode, PortSpec, Registry,
+};
+
+struct Game {
+    hp: i32,
+    log: Vec<String>,
+}
+
+type GameEnv = MutEnv<Game>;
+
+struct Damage;
+
+impl BehaviorNode<GameEnv> for Damage {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("amount")]
+    }
+
+    fn tick(&mut self, game: &mut Game, ctx: &mut Context<GameEnv>) -> BehaviorResult {
+        let amount = ctx.get_parse::<i32>("amount").unwrap_or(1);
+        game.hp -= amount;
+        game.log.push(format!("damage {amount}"));
+        BehaviorResult::Success
+    }
+}
+
+struct IsAlive;
+
+impl BehaviorNode<GameEnv> for IsAlive {
+    fn tick(&mut self, game: &mut Game, _ctx: &mut Context<GameEnv>) -> BehaviorResult {
+        if 0 < game.hp {
+            BehaviorResult::Success
+        } else {
+            BehaviorResult::Fail
+        }
+    }
+}
+
+#[test]
+fn test_mut_env() {
+    let (_, tree_source) = parse_file(
+        r#"
+tree main = Sequence {
+    Repeat (n <- "3") {
+        Hit
+    }
+    if (IsAlive) {
+        Damage (amount <- "10")
+    }
+}
+
+tree Hit = Sequence {
+    Damage (amount <- "2")
+}
+"#,
+    )
+    .unwrap();
+    let mut registry = Registry::new_generic();
+    registry.register("Damage", boxify(|| Damage));
+    registry.register("IsAlive", boxify(|| IsAlive));
+    let mut tree = load(&tree_source, &registry, true).unwrap();
+
+    let mut game = Game {
+        hp: 15,
+        log: vec![],
+    };
+    let mut ctx = Context::new_generic(Blackboard::new());
+    while tree.tick(&mut game, &mut ctx) == BehaviorResult::Running {}
+
+    assert_eq!(game.hp, -1);
+    assert_eq!(game.log, ["damage 2", "damage 2", "damage 2", "damage 10"]);
+}
+
+/// An environment that borrows data living only during a tick.
+struct View<'a> {
+    names: &'a [&'a str],
+    greeted: &'a mut Vec<String>,
+}
+
+struct ViewEnv;
+
+impl Env for ViewEnv {
+    type Arg<'a> = View<'a>;
+
+    fn reborrow<'b>(arg: &'b mut View<'_>) -> View<'b> {
+        View {
+            names: arg.names,
+            greeted: &mut *arg.greeted,
+        }
+    }
+}
+
+struct GreetAll;
+
+impl BehaviorNode<ViewEnv> for GreetAll {
+    fn tick(&mut self, view: View, _ctx: &mut Context<ViewEnv>) -> BehaviorResult {
+        view.greeted
+            .extend(view.names.iter().map(|name| name.to_string()));
+        BehaviorResult::Success
+    }
+}
+
+#[test]
+fn test_borrowed_env() {
+    let mut inverter = BehaviorNodeContainer::new_generic(InverterNode);
+    inverter
+        .add_child(BehaviorNodeContainer::new_generic(GreetAll))
+        .unwrap();
+    let mut tree = BehaviorNodeContainer::new_generic(FallbackNode::default());
+    tree.add_child(inverter).unwrap();
+    tree.add_child(BehaviorNodeContainer::new_generic(GreetAll))
+        .unwrap();
+
+    let names = ["Alice".to_string(), "Bob".to_string()];
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut greeted = vec![];
+    let view = View {
+        names: &names,
+        greeted: &mut greeted,
+    };
+    let res = tree.tick(view, &mut Context::new_generic(Blackboard::new()));
+
+    assert_eq!(res, BehaviorResult::Success);
+    assert_eq!(greeted, ["Alice", "Bob", "Alice", "Bob"]);
+}
@@ -49,9 +49,28 @@ pub enum LoadError {
     MissingTree,
     MissingNode(String),
     AddChildError(AddChildError, String),
-    PortUnmatch { node: String, port: String },
-    PortIOUnmatch { node: String, port: String },
-    InfiniteRecursion { node: String },
+    PortUnmatch {
+        node: String,
+        port: String,
+    },
+    PortIOUnmatch {
+        node: String,
+        port: String,
+    },
+    InfiniteRecursion {
+        node: String,
+    },
+    /// Children of the same node have the same label, which would give them the same path
+    DuplicateLabel {
+        node: String,
+        label: String,
+    },
+    /// A node constructor rejected the static configuration, see [`crate::NodeConfig`]
+    InvalidConfig {
+        node: String,
+        port: String,
+        message: String,
+    },
 }
 
 impl Display for LoadError {
@@ -76,8 +95,186 @@ impl Display for LoadError {
                 fmt,
                 "Inifinite recusion detected; the same subtree {node:?} was used in itself"
             ),
+            Self::DuplicateLabel { node, label } => write!(
+                fmt,
+                "Label {label:?} is used by more than one child of the node {node:?}"
+            ),
+            Self::InvalidConfig {
+                node,
+                port,
+                message,
+            } => write!(
+                fmt,
+                "Invalid value for port {port:?} of node {node:?}: {message}"
+            ),
         }
     }
 }
 
 impl std::error::Error for LoadError {}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LogReadError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Format(String),
+}
+
+impl Display for LogReadError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(fmt),
+            Self::Json(e) => e.fmt(fmt),
+            Self::Format(e) => write!(fmt, "Malformed log: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LogReadError {}
+
+impl From<std::io::Error> for LogReadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LogReadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// An error in saving or restoring the runtime state of a tree.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StateError {
+    /// A blackboard variable has a type that is not registered in the [`crate::codec::CodecRegistry`]
+    UnregisteredType {
+        key: String,
+    },
+    /// The saved state has a type name that is not registered in the [`crate::codec::CodecRegistry`]
+    UnknownTypeName {
+        key: String,
+        name: String,
+    },
+    /// The saved state has a node that does not exist in the tree
+    MissingNode {
+        path: String,
+    },
+    /// The tree has a node that does not exist in the saved state
+    UnexpectedNode {
+        path: String,
+    },
+    /// The node at the same path has a different type
+    NodeTypeMismatch {
+        path: String,
+        saved: String,
+        actual: String,
+    },
+    /// The node failed to save or restore its state
+    InvalidState {
+        path: String,
+        message: String,
+    },
+    Json(serde_json::Error),
+}
+
+impl Display for StateError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnregisteredType { key } => write!(
+                fmt,
+                "Blackboard variable {key:?} has a type that is not registered"
+            ),
+            Self::UnknownTypeName { key, name } => {
+                write!(
+                    fmt,
+                    "Blackboard variable {key:?} has an unknown type {name:?}"
+                )
+            }
+            Self::MissingNode { path } => {
+                write!(fmt, "Saved node {path:?} does not exist in the tree")
+            }
+            Self::UnexpectedNode { path } => {
+                write!(fmt, "Node {path:?} does not exist in the saved state")
+            }
+            Self::NodeTypeMismatch {
+                path,
+                saved,
+                actual,
+            } => write!(
+                fmt,
+                "Node {path:?} was saved as {saved:?}, but it is {actual:?} in the tree"
+            ),
+            Self::InvalidState { path, message } => {
+                write!(fmt, "Invalid state of node {path:?}: {message}")
+            }
+            Self::Json(e) => e.fmt(fmt),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<serde_json::Error> for StateError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// What went wrong in a [`TickError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TickErrorCause {
+    /// A port was read, but it is mapped as an output port. Only reported in strict mode.
+    NotInput { port: String },
+    /// A port was written, but it is mapped as an input port. Only reported in strict mode.
+    NotOutput { port: String },
+    /// A port was written, but it is mapped to a literal
+    LiteralWrite { port: String },
+    /// A required input port has no value of the expected type, see [`crate::Context::require`]
+    MissingInput { port: String },
+}
+
+impl Display for TickErrorCause {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::NotInput { port } => {
+                write!(fmt, "Port {port:?} is not specified as input or inout port")
+            }
+            Self::NotOutput { port } => {
+                write!(
+                    fmt,
+                    "Port {port:?} is not specified as output or inout port"
+                )
+            }
+            Self::LiteralWrite { port } => {
+                write!(
+                    fmt,
+                    "Port {port:?} is mapped to a literal and cannot be written"
+                )
+            }
+            Self::MissingInput { port } => {
+                write!(fmt, "Required input port {port:?} has no value")
+            }
+        }
+    }
+}
+
+/// An error reported by a node during a tick, which aborts the rest of the tick.
+/// See [`crate::BehaviorNodeContainer::try_tick`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickError {
+    /// See [`crate::BehaviorNodeContainer::path`]
+    pub path: String,
+    pub cause: TickErrorCause,
+}
+
+impl Display for TickError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{} in node {:?}", self.cause, self.path)
+    }
+}
+
+impl std::error::Error for TickError {}
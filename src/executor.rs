@@ -0,0 +1,303 @@
+//! A loop around [`BehaviorNodeContainer::tick`] that most applications need.
+//!
+//! An [`Executor`] owns a tree and its [`Context`], and ticks it once, until it finishes,
+//! or at a fixed rate.
+//! Between the ticks of the fixed-rate loop, it sleeps until the next period, but wakes up
+//! early if a node signals that it has new results through [`Context::waker`], so that
+//! the tree can react immediately.
+//!
+//! [`Executor::run_event_driven`] goes further and skips the ticks in which no node would
+//! make progress, according to the waits declared by the running nodes, like
+//! [`Context::wait_for_key`]. See [`Context::needs_tick`].
+//!
+//! ```
+//! # use behavior_tree_lite::{*, executor::Executor};
+//! # use std::time::Duration;
+//! let (_, source) = parse_file(r#"tree main = Sequence { Repeat (n <- "3") { Sequence } }"#).unwrap();
+//! let tree = load(&source, &Registry::default(), false).unwrap();
+//!
+//! let mut executor = Executor::new(tree, Context::default());
+//! let result = executor.run(&mut |_| None, Duration::from_millis(1));
+//! assert_eq!(result, BehaviorResult::Success);
+//! assert_eq!(executor.context().tick_count(), 4);
+//! ```
+
+use crate::{BehaviorNodeContainer, BehaviorResult, CallbackEnv, Context, Env, Symbol};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    task::{Wake, Waker},
+    time::{Duration, Instant},
+};
+
+#[derive(Default)]
+struct Signal {
+    state: Mutex<SignalState>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct SignalState {
+    woken: bool,
+    /// Events posted from outside of the tree, not yet passed to the context
+    events: Vec<Symbol>,
+}
+
+impl SignalState {
+    fn is_set(&self) -> bool {
+        self.woken || !self.events.is_empty()
+    }
+}
+
+impl Wake for Signal {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.state.lock().unwrap().woken = true;
+        self.cond.notify_all();
+    }
+}
+
+/// A handle to wake up an [`Executor`] sleeping between ticks, which can be sent to
+/// other threads.
+///
+/// Nodes can do the same with [`Context::waker`], which the executor sets to this handle.
+#[derive(Clone, Default)]
+pub struct WakeHandle(Arc<Signal>);
+
+impl std::fmt::Debug for WakeHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("WakeHandle").field(&self.is_woken()).finish()
+    }
+}
+
+impl WakeHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wake(&self) {
+        self.0.wake_by_ref();
+    }
+
+    /// Post an event to the tree, which is passed to [`Context::post_event`] before the next
+    /// tick, and wake up the executor.
+    pub fn post_event(&self, event: impl Into<Symbol>) {
+        self.0.state.lock().unwrap().events.push(event.into());
+        self.0.cond.notify_all();
+    }
+
+    /// Returns true if it has been woken since the last wait.
+    pub fn is_woken(&self) -> bool {
+        self.0.state.lock().unwrap().woken
+    }
+
+    /// Block until woken, an event is posted or the timeout elapses, and clear the flag.
+    /// Returns true if woken or an event is pending, which can be before the call.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let state = self.0.state.lock().unwrap();
+        let (mut state, _) = self
+            .0
+            .cond
+            .wait_timeout_while(state, timeout, |state| !state.is_set())
+            .unwrap();
+        let set = state.is_set();
+        state.woken = false;
+        set
+    }
+
+    /// Block until woken or an event is posted, without a timeout if `None`.
+    /// The flag and the events are kept for [`Self::take`].
+    fn wait(&self, timeout: Option<Duration>) {
+        let state = self.0.state.lock().unwrap();
+        let _state = match timeout {
+            Some(timeout) => {
+                self.0
+                    .cond
+                    .wait_timeout_while(state, timeout, |state| !state.is_set())
+                    .unwrap()
+                    .0
+            }
+            None => self
+                .0
+                .cond
+                .wait_while(state, |state| !state.is_set())
+                .unwrap(),
+        };
+    }
+
+    /// Clear the flag and take the posted events. Returns whether it was woken.
+    fn take(&self) -> (bool, Vec<Symbol>) {
+        let mut state = self.0.state.lock().unwrap();
+        (
+            std::mem::replace(&mut state.woken, false),
+            std::mem::take(&mut state.events),
+        )
+    }
+
+    pub fn waker(&self) -> Waker {
+        Waker::from(self.0.clone())
+    }
+}
+
+/// Owns a tree and a context to tick the tree repeatedly. See the [module](self) documentation.
+pub struct Executor<E: Env = CallbackEnv> {
+    tree: BehaviorNodeContainer<E>,
+    ctx: Context<E>,
+    handle: WakeHandle,
+    last_result: Option<BehaviorResult>,
+}
+
+impl<E: Env> Executor<E> {
+    /// Create an executor, replacing the waker of the context with the [`WakeHandle`].
+    pub fn new(tree: BehaviorNodeContainer<E>, mut ctx: Context<E>) -> Self {
+        let handle = WakeHandle::new();
+        ctx.set_waker(handle.waker());
+        Self {
+            tree,
+            ctx,
+            handle,
+            last_result: None,
+        }
+    }
+
+    pub fn tree(&self) -> &BehaviorNodeContainer<E> {
+        &self.tree
+    }
+
+    pub fn tree_mut(&mut self) -> &mut BehaviorNodeContainer<E> {
+        &mut self.tree
+    }
+
+    pub fn context(&self) -> &Context<E> {
+        &self.ctx
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context<E> {
+        &mut self.ctx
+    }
+
+    pub fn into_parts(self) -> (BehaviorNodeContainer<E>, Context<E>) {
+        (self.tree, self.ctx)
+    }
+
+    /// A handle to wake up the executor from outside of the tree, e.g. another thread.
+    pub fn wake_handle(&self) -> WakeHandle {
+        self.handle.clone()
+    }
+
+    /// Post an event to the tree before the next tick, see [`Context::post_event`].
+    pub fn post_event(&mut self, event: impl Into<Symbol>) {
+        self.ctx.post_event(event);
+    }
+
+    /// Tick the tree once. Errors can be checked with [`Context::tick_error`].
+    pub fn tick_once(&mut self, arg: E::Arg<'_>) -> BehaviorResult {
+        // Wake-ups before this tick are handled by it
+        self.take_signal();
+        self.tick_tree(arg)
+    }
+
+    /// Tick the tree once if it has not returned `Running` last time, the executor was woken,
+    /// or any of the waits declared by the running nodes fired. Returns `None` otherwise.
+    ///
+    /// The nodes that are not on the path to a running node are not ticked either way,
+    /// since [`crate::SequenceNode`] and the like resume the running child.
+    /// Reactive nodes check their conditions every tick, so they request the tick with
+    /// [`Context::wait_for_next_tick`].
+    pub fn tick_if_needed(&mut self, arg: E::Arg<'_>) -> Option<BehaviorResult> {
+        let woken = self.take_signal();
+        if self.last_result == Some(BehaviorResult::Running) && !woken && !self.ctx.needs_tick() {
+            return None;
+        }
+        Some(self.tick_tree(arg))
+    }
+
+    fn tick_tree(&mut self, arg: E::Arg<'_>) -> BehaviorResult {
+        let res = self.tree.tick(arg, &mut self.ctx);
+        self.last_result = Some(res);
+        res
+    }
+
+    /// Pass the events posted to the handle to the context, and return whether it was woken.
+    fn take_signal(&mut self) -> bool {
+        let (woken, events) = self.handle.take();
+        for event in events {
+            self.ctx.post_event(event);
+        }
+        woken
+    }
+
+    /// Tick the tree until it returns other than `Running`, without sleeping, up to
+    /// `max_ticks` times. Returns `Running` if it didn't finish in time.
+    pub fn tick_while_running(&mut self, mut arg: E::Arg<'_>, max_ticks: usize) -> BehaviorResult {
+        let mut res = BehaviorResult::Running;
+        for _ in 0..max_ticks {
+            res = self.tick_once(E::reborrow(&mut arg));
+            if res != BehaviorResult::Running {
+                break;
+            }
+        }
+        res
+    }
+
+    /// Tick the tree every `period` until it returns other than `Running`.
+    ///
+    /// It sleeps the rest of the period after each tick, but a wake-up, during the tick or
+    /// the sleep, starts the next tick immediately.
+    pub fn run(&mut self, arg: E::Arg<'_>, period: Duration) -> BehaviorResult {
+        self.run_with_sleep(arg, period, |handle, duration| {
+            handle.wait_timeout(duration);
+        })
+    }
+
+    /// [`Self::run`] with a custom sleep function, which is given the wake handle and the time
+    /// until the next tick. It is supposed to return when the time elapses or the handle is
+    /// woken, but it can return at any time, e.g. to simulate the time in tests.
+    pub fn run_with_sleep(
+        &mut self,
+        mut arg: E::Arg<'_>,
+        period: Duration,
+        mut sleep: impl FnMut(&WakeHandle, Duration),
+    ) -> BehaviorResult {
+        loop {
+            let start = Instant::now();
+            let res = self.tick_once(E::reborrow(&mut arg));
+            if res != BehaviorResult::Running {
+                return res;
+            }
+            if let Some(rest) = period.checked_sub(start.elapsed()) {
+                sleep(&self.handle, rest);
+            }
+        }
+    }
+
+    /// Tick the tree like [`Self::run`], but only when [`Self::tick_if_needed`] would.
+    ///
+    /// The ticks are at least `period` apart unless woken. If no node needs the next tick,
+    /// it sleeps until the earliest [deadline](Context::wait_until), a wake-up or an event
+    /// posted through the [`WakeHandle`], which can be forever if none of them comes.
+    pub fn run_event_driven(&mut self, mut arg: E::Arg<'_>, period: Duration) -> BehaviorResult {
+        loop {
+            let start = Instant::now();
+            if let Some(res) = self.tick_if_needed(E::reborrow(&mut arg)) {
+                if res != BehaviorResult::Running {
+                    return res;
+                }
+            }
+            let mut until = Some(start + period);
+            if !self.ctx.needs_tick() {
+                until = self
+                    .ctx
+                    .next_deadline()
+                    .map(|deadline| deadline.max(start + period));
+            }
+            self.handle
+                .wait(until.map(|until| until.saturating_duration_since(Instant::now())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,110 @@
+use super::*;
+use crate::{BehaviorNode, SequenceNode};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Returns `Running` for the given number of ticks, then `Success`
+struct Countdown(usize);
+
+impl BehaviorNode for Countdown {
+    fn tick(&mut self, _arg: crate::BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        if self.0 == 0 {
+            return BehaviorResult::Success;
+        }
+        self.0 -= 1;
+        BehaviorResult::Running
+    }
+}
+
+fn new_executor<T: BehaviorNode + 'static>(node: T) -> Executor {
+    let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+    tree.add_child(BehaviorNodeContainer::new_node(node))
+        .unwrap();
+    Executor::new(tree, Context::default())
+}
+
+#[test]
+fn test_tick_while_running() {
+    let mut executor = new_executor(Countdown(3));
+    assert_eq!(
+        executor.tick_while_running(&mut |_| None, 2),
+        BehaviorResult::Running
+    );
+    assert_eq!(executor.context().tick_count(), 2);
+    assert_eq!(
+        executor.tick_while_running(&mut |_| None, 10),
+        BehaviorResult::Success
+    );
+    assert_eq!(executor.context().tick_count(), 4);
+}
+
+#[test]
+fn test_run_with_sleep() {
+    let period = Duration::from_secs(10);
+    let mut sleeps = vec![];
+    let mut executor = new_executor(Countdown(3));
+    let res = executor.run_with_sleep(&mut |_| None, period, |_, duration| sleeps.push(duration));
+    assert_eq!(res, BehaviorResult::Success);
+    assert_eq!(sleeps.len(), 3);
+    assert!(sleeps
+        .iter()
+        .all(|duration| Duration::ZERO < *duration && *duration <= period));
+}
+
+/// Wakes the executor on the first tick, as if it had a result ready
+struct WakeSelf(bool);
+
+impl BehaviorNode for WakeSelf {
+    fn tick(&mut self, _arg: crate::BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        if self.0 {
+            return BehaviorResult::Success;
+        }
+        self.0 = true;
+        ctx.waker().wake_by_ref();
+        BehaviorResult::Running
+    }
+}
+
+#[test]
+fn test_wake_from_node() {
+    let mut executor = new_executor(WakeSelf(false));
+    let start = Instant::now();
+    let res = executor.run(&mut |_| None, Duration::from_secs(60));
+    assert_eq!(res, BehaviorResult::Success);
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert_eq!(executor.context().tick_count(), 2);
+}
+
+/// Succeeds when the flag is set from outside
+struct WaitFlag(Arc<AtomicBool>);
+
+impl BehaviorNode for WaitFlag {
+    fn tick(&mut self, _arg: crate::BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        if self.0.load(Ordering::SeqCst) {
+            BehaviorResult::Success
+        } else {
+            BehaviorResult::Running
+        }
+    }
+}
+
+#[test]
+fn test_wake_handle() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut executor = new_executor(WaitFlag(flag.clone()));
+    let handle = executor.wake_handle();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(10));
+        flag.store(true, Ordering::SeqCst);
+        handle.wake();
+    });
+
+    let start = Instant::now();
+    let res = executor.run(&mut |_| None, Duration::from_secs(60));
+    thread.join().unwrap();
+    assert_eq!(res, BehaviorResult::Success);
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert!(!executor.wake_handle().is_woken());
+}
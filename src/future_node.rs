@@ -0,0 +1,141 @@
+use crate::{BehaviorNode, BehaviorResult, CallbackEnv, Context, Env, MaybeSend, PortSpec};
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{self, Poll},
+};
+
+/// A node that runs a future, so that a long-running action can be written as an `async` block
+/// instead of a hand-rolled state machine.
+///
+/// When the node is ticked for the first time, or after the previous run has finished,
+/// the function given to [`Self::new`] is called to create the future.
+/// It can read the inputs from the [`Context`], but the future itself can't borrow the context
+/// or the environment, because it lives across ticks.
+///
+/// The future is polled once per tick with [`Context::waker`], and the node returns `Running`
+/// while it is pending. If the node is [halted](BehaviorNode::halt), the future is dropped,
+/// which cancels it.
+///
+/// No async runtime is required. If the future waits for something outside of the tree, e.g.
+/// a channel, it is simply polled again in the next tick.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// let mut registry = Registry::default();
+/// registry.register(
+///     "Wait",
+///     boxify(|| {
+///         FutureNode::new(|ctx: &mut Context| {
+///             let ticks = ctx.get_parse::<usize>("ticks").unwrap_or(1);
+///             async move {
+///                 for _ in 0..ticks {
+///                     yield_now().await;
+///                 }
+///                 BehaviorResult::Success
+///             }
+///         })
+///         .with_ports(vec![PortSpec::new_in("ticks")])
+///     }),
+/// );
+///
+/// let (_, source) = parse_file(r#"tree main = Sequence { Wait (ticks <- "2") }"#).unwrap();
+/// let mut tree = load(&source, &registry, true).unwrap();
+/// let mut ctx = Context::default();
+/// assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+/// assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+/// assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+/// ```
+pub struct FutureNode<F, Fut, E = CallbackEnv> {
+    start: F,
+    future: Option<Pin<Box<Fut>>>,
+    ports: Vec<PortSpec>,
+    _env: PhantomData<fn() -> E>,
+}
+
+impl<F, Fut, E> FutureNode<F, Fut, E>
+where
+    E: Env,
+    F: FnMut(&mut Context<E>) -> Fut,
+{
+    pub fn new(start: F) -> Self {
+        Self {
+            start,
+            future: None,
+            ports: vec![],
+            _env: PhantomData,
+        }
+    }
+
+    /// Declare the ports the function reads, see [`BehaviorNode::provided_ports`].
+    pub fn with_ports(mut self, ports: Vec<PortSpec>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    /// Returns true if the future has been started and not finished yet.
+    pub fn is_running(&self) -> bool {
+        self.future.is_some()
+    }
+}
+
+impl<E, F, Fut> BehaviorNode<E> for FutureNode<F, Fut, E>
+where
+    E: Env,
+    F: FnMut(&mut Context<E>) -> Fut + MaybeSend,
+    Fut: Future<Output = BehaviorResult> + MaybeSend + 'static,
+{
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        self.ports.clone()
+    }
+
+    fn tick(&mut self, _arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        let future = match self.future {
+            Some(ref mut future) => future,
+            None => self.future.insert(Box::pin((self.start)(ctx))),
+        };
+        match future
+            .as_mut()
+            .poll(&mut task::Context::from_waker(ctx.waker()))
+        {
+            Poll::Ready(res) => {
+                self.future = None;
+                res
+            }
+            Poll::Pending => BehaviorResult::Running,
+        }
+    }
+
+    fn halt(&mut self) {
+        self.future = None;
+    }
+}
+
+/// Returns a future that is pending once, to give control back to the tree until the next tick.
+///
+/// It doesn't wake the waker, since nothing new has happened, so an
+/// [`Executor`](crate::executor::Executor) resumes it in the next regular tick.
+pub fn yield_now() -> YieldNow {
+    YieldNow(false)
+}
+
+/// The future returned by [`yield_now`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut task::Context) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test;
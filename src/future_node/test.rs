@@ -0,0 +1,149 @@
+use super::*;
+use crate::{boxify, load, parse_file, BehaviorNodeContainer, Registry};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Wake, Waker},
+};
+
+fn load_tree(registry: &Registry, source: &str) -> BehaviorNodeContainer {
+    let (_, tree_source) = parse_file(source).unwrap();
+    load(&tree_source, registry, true).unwrap()
+}
+
+#[test]
+fn test_future_node() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let mut registry = Registry::default();
+    let started2 = started.clone();
+    registry.register(
+        "Wait",
+        boxify(move || {
+            let started = started2.clone();
+            FutureNode::new(move |ctx: &mut Context| {
+                started.fetch_add(1, Ordering::SeqCst);
+                let ticks = ctx.get_parse::<usize>("ticks").unwrap();
+                async move {
+                    for _ in 0..ticks {
+                        yield_now().await;
+                    }
+                    BehaviorResult::Success
+                }
+            })
+            .with_ports(vec![PortSpec::new_in("ticks")])
+        }),
+    );
+    let mut tree = load_tree(
+        &registry,
+        r#"
+tree main = Sequence {
+    Wait (ticks <- "2")
+    Wait (ticks <- "0")
+    SetBool (value <- "true", output -> done)
+}
+"#,
+    );
+
+    let mut ctx = Context::default();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(ctx.get::<bool>("done"), None);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+    assert_eq!(ctx.get::<bool>("done"), Some(&true));
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+
+    // Starts over
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(started.load(Ordering::SeqCst), 3);
+}
+
+/// Sets the flag when dropped
+struct DropGuard(Arc<AtomicBool>);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_halt() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let dropped2 = dropped.clone();
+    let mut tree = BehaviorNodeContainer::new_node(FutureNode::new(move |_: &mut Context| {
+        let guard = DropGuard(dropped2.clone());
+        async move {
+            let _guard = guard;
+            loop {
+                yield_now().await;
+            }
+        }
+    }));
+
+    let mut ctx = Context::default();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    tree.halt();
+    assert!(dropped.load(Ordering::SeqCst));
+    assert_eq!(tree.last_result(), None);
+}
+
+#[derive(Default)]
+struct Slot {
+    result: Option<BehaviorResult>,
+    waker: Option<Waker>,
+}
+
+/// A future that resolves when a result is sent from outside of the tree
+struct Receiver(Arc<Mutex<Slot>>);
+
+impl Future for Receiver {
+    type Output = BehaviorResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<BehaviorResult> {
+        let mut slot = self.0.lock().unwrap();
+        match slot.result.take() {
+            Some(res) => Poll::Ready(res),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct CountWakes(AtomicUsize);
+
+impl Wake for CountWakes {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_waker() {
+    let slot = Arc::new(Mutex::new(Slot::default()));
+    let slot2 = slot.clone();
+    let mut tree = BehaviorNodeContainer::new_node(FutureNode::new(move |_: &mut Context| {
+        Receiver(slot2.clone())
+    }));
+
+    let wakes = Arc::new(CountWakes::default());
+    let mut ctx = Context::default();
+    ctx.set_waker(Waker::from(wakes.clone()));
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+
+    let mut locked = slot.lock().unwrap();
+    locked.result = Some(BehaviorResult::Fail);
+    locked.waker.take().unwrap().wake();
+    drop(locked);
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Fail);
+}
@@ -69,7 +69,7 @@
 //!
 //! ```rust
 //! # use behavior_tree_lite::*;
-//! # let mut root = SequenceNode::default();
+//! # let mut root = BehaviorNodeContainer::new_node(SequenceNode::default());
 //! # let mut ctx = Context::default();
 //! let result = root.tick(&mut |_| None, &mut ctx);
 //! ```
@@ -83,7 +83,7 @@
 //!
 //! ```rust
 //! # use behavior_tree_lite::*;
-//! # let mut tree = SequenceNode::default();
+//! # let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
 //! tree.tick(
 //!     &mut |v: &dyn std::any::Any| {
 //!         println!("{}", *v.downcast_ref::<bool>().unwrap());
@@ -109,6 +109,43 @@
 //! A callback is much more direct (and doesn't require indirection of port names)
 //! way to communicate with the environment.
 //!
+//! ### Typed environment
+//!
+//! If you don't want to downcast the messages, you can give the tree your own environment type instead of the callback.
+//! The `BehaviorNode`, `BehaviorNodeContainer`, `Context` and `Registry` types take an optional type parameter `E` implementing the `Env` trait, which defaults to `CallbackEnv`, the callback above.
+//! `Env::Arg<'a>` is the type that the nodes actually receive, which can borrow the environment only for the duration of a tick, so the lifetime issue above doesn't apply.
+//! The simplest one is `MutEnv<T>`, which gives the nodes `&mut T`.
+//!
+//! ```rust
+//! # use behavior_tree_lite::*;
+//! struct Game {
+//!     hp: i32,
+//! }
+//!
+//! struct Damage;
+//!
+//! impl BehaviorNode<MutEnv<Game>> for Damage {
+//!     fn tick(&mut self, game: &mut Game, _ctx: &mut Context<MutEnv<Game>>) -> BehaviorResult {
+//!         game.hp -= 1;
+//!         BehaviorResult::Success
+//!     }
+//! }
+//!
+//! let mut registry = Registry::new_generic();
+//! registry.register("Damage", boxify(|| Damage));
+//! let (_, source) = parse_file("tree main = Sequence { Damage Damage }").unwrap();
+//! let mut tree = load(&source, &registry, true).unwrap();
+//!
+//! let mut game = Game { hp: 10 };
+//! tree.tick(&mut game, &mut Context::new_generic(Blackboard::new()));
+//! assert_eq!(game.hp, 8);
+//! ```
+//!
+//! The built-in nodes work with any environment type.
+//! If your environment has a lifetime, you can define a handle type implementing `Env` yourself, see the documentation of `Env`.
+//! Note that `Context::new`, `Context::default`, `Registry::default` and `BehaviorNodeContainer::new_node` are only defined for the default environment, so that the types can be inferred without annotations.
+//! Use `Context::new_generic`, `Registry::new_generic` and `BehaviorNodeContainer::new_generic` for the other environments.
+//!
 //!
 //! ## How to define your own node
 //!
@@ -226,6 +263,130 @@
 //!
 //! See [example code](examples/main.rs) for the full code.
 //!
+//! `Registry::metadata` returns the `NodeMetadata` of a registered type: the provided ports, the maximum number of children, the category (action, condition, control or decorator) and a description, and `Registry::node_types` lists the registered types, e.g. for an editor.
+//! A node type can declare it with `BehaviorNode::type_metadata`, which `boxify` and `boxify_with_config` pass to the registry, or you can give it with `Registry::set_metadata`.
+//! Otherwise, it is derived from the `provided_ports`, `max_children`, `category` and `description` methods of a node created once.
+//! `load` checks the ports with the metadata instead of creating the nodes, and `PortSpec::with_type` records the value type of a port for tools.
+//!
+//! ### Declaring ports with derive macros
+//!
+//! With `derive` feature, which pulls in the `behavior-tree-lite-derive` crate, you can declare the typed ports of a node with attributes instead of repeating the port names in `provided_ports` and the symbols.
+//! `#[derive(Ports)]` reads `#[input(...)]`, `#[output(...)]` and `#[inout(...)]` attributes on the struct, or `#[input]`, `#[output]` and `#[inout]` on its fields, and generates `input_<port>` and `output_<port>` methods that cache the port names as symbols.
+//! `#[provided_ports]` on the `BehaviorNode` implementation fills in `provided_ports` with the declared ports and their value types.
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! # mod example {
+//! use ::behavior_tree_lite::{
+//!     provided_ports, BehaviorCallback, BehaviorNode, BehaviorResult, Context, Ports
+//! };
+//! # #[derive(Clone)] struct Arm;
+//! # struct Body { left_arm: Arm, right_arm: Arm }
+//!
+//! #[derive(Ports)]
+//! #[input(body: Body)]
+//! #[output(left_arm: Arm, right_arm: Arm)]
+//! struct PrintBodyNode;
+//!
+//! #[provided_ports]
+//! impl BehaviorNode for PrintBodyNode {
+//!     fn tick(&mut self, _: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+//!         let Some(body) = self.input_body(ctx) else {
+//!             return BehaviorResult::Fail;
+//!         };
+//!         let (left_arm, right_arm) = (body.left_arm.clone(), body.right_arm.clone());
+//!         self.output_left_arm(ctx, left_arm);
+//!         self.output_right_arm(ctx, right_arm);
+//!         BehaviorResult::Success
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! ### Configuring nodes at load time
+//!
+//! Ports are read every tick, since they can be mapped to variables that change.
+//! If a parameter is usually a literal in the source file, you can register the node with `register_with_config`, whose constructor receives the `NodeConfig` of each node in the tree.
+//! It can parse and validate the literal values once, and reject them with a `LoadError`, which `load` returns.
+//! The built-in `Repeat` and `Retry` nodes parse `n` this way if it is a literal.
+//!
+//! ```rust
+//! # use behavior_tree_lite::{*, error::LoadError};
+//! struct Wait(usize);
+//!
+//! # impl BehaviorNode for Wait {
+//! #     fn tick(&mut self, _: BehaviorCallback, _: &mut Context) -> BehaviorResult { BehaviorResult::Success }
+//! # }
+//! let mut registry = Registry::default();
+//! registry.register_with_config(
+//!     "Wait",
+//!     boxify_with_config(|config| Ok(Wait(config.parse("ticks")?.unwrap_or(1)))),
+//! );
+//! # let (_, source) = parse_file(r#"tree main = Sequence { Wait (ticks <- "many") }"#).unwrap();
+//! # assert!(matches!(load(&source, &registry, false), Err(LoadError::InvalidConfig { .. })));
+//! ```
+//!
+//! ### Asynchronous nodes
+//!
+//! A long-running action, like waiting for a path finding result or an animation, needs to return `Running` until it finishes, which usually means writing a state machine by hand.
+//! Instead, you can write it as an `async` block with `FutureNode`.
+//! The function given to `FutureNode::new` reads the inputs and creates a future when the node starts, and the future is polled once per tick until it finishes.
+//! If the node is halted, the future is dropped.
+//! No async runtime is needed.
+//!
+//! ```rust
+//! # use behavior_tree_lite::*;
+//! let node = FutureNode::new(|ctx: &mut Context| {
+//!     let ticks = ctx.get_parse::<usize>("ticks").unwrap_or(1);
+//!     async move {
+//!         for _ in 0..ticks {
+//!             yield_now().await;
+//!         }
+//!         BehaviorResult::Success
+//!     }
+//! });
+//! ```
+//!
+//! The future is polled with the waker set by `Context::set_waker`, which does nothing by default.
+//!
+//! ### Running the tree
+//!
+//! `executor::Executor` owns a tree and a `Context`, and runs the loop that most applications need.
+//! `tick_once` ticks the tree once, `tick_while_running` ticks it until it finishes up to a given number of ticks, and `run` ticks it at a fixed rate, sleeping between the ticks.
+//! The executor sets the waker of the context, so a node can wake it up early with `ctx.waker()` when it has new results, and other threads can do the same with `Executor::wake_handle`.
+//! `run_with_sleep` lets you replace the sleep, e.g. to simulate the time in tests.
+//!
+//! ```rust
+//! # use behavior_tree_lite::{*, executor::Executor};
+//! # use std::time::Duration;
+//! # let tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+//! let mut executor = Executor::new(tree, Context::default());
+//! let result = executor.run(&mut |_| None, Duration::from_millis(10));
+//! # assert_eq!(result, BehaviorResult::Success);
+//! ```
+//!
+//! A node returning `Running` can declare what it waits for: a blackboard variable with `ctx.wait_for_key`, a time with `ctx.wait_until`, or an event with `ctx.wait_for_event`, which is posted by `Executor::post_event` or `WakeHandle::post_event` and seen by the nodes with `ctx.has_event` in the next tick.
+//! `run_event_driven` and `tick_if_needed` skip the ticks in which none of them has fired.
+//! Since `Sequence` and `Fallback` resume the running child, a tick only goes down the path to the waiting nodes anyway.
+//! Reactive nodes re-evaluate the children before the running one every tick, so they fall back to full ticks, and so does a running node that declares nothing.
+//!
+//! ### Using trees from multiple threads
+//!
+//! By default, the blackboard values are shared by `Rc` and the nodes don't need to be `Send`,
+//! so you can't move a tree or a `Context` to another thread.
+//! Enable `sync` feature to make them `Send`:
+//!
+//! ```toml
+//! behavior-tree-lite = { version = "0.4", features = ["sync"] }
+//! ```
+//!
+//! With this feature, the blackboard stores `Arc<dyn Any + Send + Sync>`, and nodes, observers and callbacks stored in the `Context` are required to be `Send`.
+//! The type aliases `Shared` and `AnyValue` switch between the two modes, so the code using them compiles with or without the feature.
+//!
+//! The `batch` module ticks many agents, each with its own tree and `Context`, and aggregates the results.
+//! `batch::tick_parallel` splits them among scoped threads or your own thread pool, which requires this feature.
+//!
+//!
 //! ### Loading the tree structure from a yaml file
 //!
 //! Deprecated in favor of <a href="#The custom config file format">the custom config file format</a>.
@@ -318,6 +479,23 @@
 //! # })();
 //! ```
 //!
+//! If you create the same tree many times, e.g. for each agent in a game, compile it into a `Blueprint` once instead.
+//! It resolves the node types, port maps and paths only once and shares them between the instances, so each instance only allocates the nodes themselves.
+//!
+//! ```rust
+//! # use ::behavior_tree_lite::*;
+//! # let (_, tree_source) = parse_file("tree main = Sequence").unwrap();
+//! # let registry = Registry::default();
+//! # let check_ports = true;
+//! # (|| -> Result<(), error::LoadError> {
+//! let blueprint = Blueprint::new(&tree_source, &registry, check_ports)?;
+//! let trees = (0..100)
+//!     .map(|_| blueprint.instantiate())
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! # Ok(())
+//! # })();
+//! ```
+//!
 //! ### Line comments
 //!
 //! You can put a line comment starting with a hash (`#`).
@@ -367,6 +545,35 @@
 //!
 //! It is an error to try to read from an output port or write to an input port,
 //! but inout port can do both.
+//! In a strict context, created by `Context::new`, doing so is reported as a `TickError` with the path of the node.
+//! Writing to a literal and missing inputs required by `Context::require` are reported in any context.
+//! An error aborts the rest of the tick, and `BehaviorNodeContainer::try_tick` returns it instead of the result,
+//! so a mistake in a tree file doesn't crash your application.
+//!
+//! ```rust
+//! # use behavior_tree_lite::*;
+//! # let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+//! let mut ctx = Context::new(Blackboard::new());
+//! match tree.try_tick(&mut |_| None, &mut ctx) {
+//!     Ok(result) => println!("Result: {result:?}"),
+//!     Err(e) => eprintln!("Error: {e}"),
+//! }
+//! ```
+//!
+//!
+//! ### Node labels
+//!
+//! You can give a node a label by putting a colon (`:`) and a name after the node name.
+//! The labels of the children of a node need to be unique.
+//!
+//! ```raw
+//! MoveTo:approach (target <- enemy)
+//! ```
+//!
+//! Each node in a loaded tree gets a numeric id and a path like `main/Sequence[1]/approach`, which you can
+//! use to find the node with `BehaviorNodeContainer::find_by_id` or `BehaviorNodeContainer::find_by_path`.
+//! The path consists of the tree name, followed by labels of nodes, or the node type names and
+//! indices among the siblings of the same type if they don't have labels.
 //!
 //!
 //! ### Child nodes
@@ -428,6 +635,29 @@
 //! ```
 //!
 //!
+//! ### Global variables
+//!
+//! Sometimes you want to share data with every subtree, e.g. the state of the world,
+//! without threading it through the ports of every subtree on the way.
+//! A variable name prefixed with `@` refers to the variable in the global blackboard,
+//! which is the blackboard of the root tree, from anywhere in the tree.
+//!
+//! ```raw
+//! tree main = Sequence {
+//!     UpdateWorld (output -> @world)
+//!     SubTree
+//! }
+//!
+//! tree SubTree = Sequence {
+//!     FindEnemy (world <- @world, enemy -> enemy)
+//! }
+//! ```
+//!
+//! The names without `@` are still resolved in the subtree's own namespace,
+//! so the data flow through ports stays explicit for everything else.
+//! In Rust code, `Context::get_global` and `Context::set_global` access the global blackboard directly.
+//!
+//!
 //! ### Conditional syntax
 //!
 //! Like a programming language, the format supports conditional syntax.
@@ -586,6 +816,23 @@
 //! This design is a step towards statically checked source code.
 //!
 //!
+//! ### Reacting to variable changes
+//!
+//! `OnChange` node ticks its child only when any of the variables listed in `keys` port
+//! has been set since the last evaluation, and returns the cached result otherwise.
+//! It is useful to avoid re-running expensive nodes, e.g. path finding, every tick.
+//!
+//! ```raw
+//! OnChange (keys <- "target, goal") {
+//!     FindPath (start <- target, goal <- goal, path -> path)
+//! }
+//! ```
+//!
+//! If the variables change while the child is `Running`, the child is halted and started over.
+//!
+//! From the Rust side, `Context::watch` installs a callback on a variable, and
+//! `Context::changed_since` tells if it has been set after a given tick.
+//!
 //!
 //! ### Syntax specification
 //!
@@ -614,7 +861,9 @@
 //!
 //! conditional =  conditional-and | conditional "||" conditional-and
 //!
-//! node-syntax = node-name [ "(" port-list ")" ] [ "{" node* "}" ]
+//! node-syntax = node-name [ ":" label ] [ "(" port-list ")" ] [ "{" node* "}" ]
+//!
+//! label = identifier
 //!
 //! port-list = port [ "," port-list ]
 //!
@@ -622,7 +871,7 @@
 //!
 //! node-port-name = identifier
 //!
-//! blackboard-port-name = identifier
+//! blackboard-port-name = ["@"] identifier
 //!
 //! var-def-syntax = "var" identifier "=" initializer
 //!
@@ -673,32 +922,65 @@
 //! Although there is sub-blackboards in subtrees, it is difficult to keep track of similar to scripting language's stack frame without proper debugging tools.
 //!
 //! I might experiment with non-string keys to make it more efficient, but the nature of the variables need to be handled dynamically in uniformly typeds nodes.
+//!
+//! `BehaviorNodeContainer` moves the children and the port map of each node into the `Context` while ticking it.
+//! If it shows up in your profile, convert the tree into an `ArenaTree`, which stores the nodes in a contiguous array and runs the same nodes.
+//! `cargo bench` compares the two, as well as `load` against instantiating a `Blueprint`.
 
+mod arena;
+pub mod batch;
+mod blueprint;
+pub mod codec;
 mod container;
 mod context;
+pub mod coverage;
+pub mod debugger;
+mod env;
 pub mod error;
+pub mod executor;
+mod future_node;
+pub mod logger;
 mod nodes;
+mod observer;
 pub mod parser;
 mod port;
+pub mod profiler;
+pub mod record;
 mod registry;
+pub mod reload;
+mod render;
+mod shared;
+pub mod state;
 mod symbol;
+mod wait;
 
 use std::any::Any;
 use std::collections::HashMap;
-use std::rc::Rc;
 
-pub use crate::container::BehaviorNodeContainer;
-pub use crate::context::Context;
+pub use crate::arena::ArenaTree;
+pub use crate::blueprint::Blueprint;
+pub use crate::container::{BehaviorNodeContainer, NodeId};
+pub use crate::context::{Context, WatchId};
+pub use crate::env::{CallbackEnv, Env, MutEnv};
+pub use crate::future_node::{yield_now, FutureNode, YieldNow};
 pub use crate::nodes::{tick_child_node, FallbackNode, SequenceNode};
+pub use crate::observer::{TickEvent, TickObserver};
+pub use crate::shared::{AnyValue, MaybeSend, MaybeSendSync, Shared};
 pub use crate::symbol::Symbol;
 pub use crate::{
     parser::{load, load_yaml, node_def, parse_file, parse_nodes, NodeDef},
     port::{AbstractPortMap, BlackboardValueOwned, PortSpec, PortType},
-    registry::{boxify, Constructor, Registry},
+    registry::{
+        boxify, boxify_with_config, ConfigConstructor, Constructor, NodeCategory, NodeConfig,
+        NodeConstructor, NodeMetadata, Registry,
+    },
+    render::TreeRenderer,
 };
 pub use ::once_cell::sync::*;
+#[cfg(feature = "derive")]
+pub use behavior_tree_lite_derive::{provided_ports, Ports};
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub enum BehaviorResult {
     Success,
     Fail,
@@ -706,9 +988,13 @@ pub enum BehaviorResult {
     Running,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BlackboardValue {
     Ref(Symbol, PortType),
+    /// A reference to a variable in the global (root) blackboard, written as `@name` in the
+    /// source, which is visible from any subtree.
+    Global(Symbol, PortType),
     Literal(String),
 }
 
@@ -751,11 +1037,14 @@ impl From<&str> for BlackboardValue {
 /// a function call in your own programming language, i.e. pass-by-value v.s. pass-by-reference.
 /// In essence, a subtree in behavior tree is a function in a programming language.
 /// The third sect in the society is copy-on-write reference, which is what `Rc` does.
-pub type Blackboard = HashMap<Symbol, Rc<dyn Any>>;
+///
+/// With `sync` feature, `Rc` is replaced by `Arc` and the values need to be `Send + Sync`,
+/// see [`Shared`] and [`AnyValue`].
+pub type Blackboard = HashMap<Symbol, Shared<AnyValue>>;
 pub type BBMap = HashMap<Symbol, BlackboardValue>;
 pub type BehaviorCallback<'a> = &'a mut dyn FnMut(&dyn Any) -> Option<Box<dyn Any>>;
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NumChildren {
     Finite(usize),
     Infinite,
@@ -772,16 +1061,75 @@ impl PartialOrd for NumChildren {
     }
 }
 
-pub trait BehaviorNode {
+/// A behavior node that communicates with the environment of type `E`, see [`Env`].
+pub trait BehaviorNode<E: Env = CallbackEnv>: MaybeSend {
     fn provided_ports(&self) -> Vec<PortSpec> {
         vec![]
     }
 
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult;
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult;
 
     fn max_children(&self) -> NumChildren {
         NumChildren::Finite(0)
     }
+
+    /// The category in the [`NodeMetadata`] of the node type.
+    ///
+    /// The default implementation tells it from [`Self::max_children`]: an action for no
+    /// children, a decorator for one and a control node otherwise.
+    /// Conditions need to override it.
+    fn category(&self) -> NodeCategory {
+        match self.max_children() {
+            NumChildren::Finite(0) => NodeCategory::Action,
+            NumChildren::Finite(1) => NodeCategory::Decorator,
+            _ => NodeCategory::Control,
+        }
+    }
+
+    /// A human readable description in the [`NodeMetadata`] of the node type.
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// The [`NodeMetadata`] of the node type, which [`boxify`] and [`boxify_with_config`] give
+    /// to the [`Registry`], so that it doesn't need to create a node to know it.
+    ///
+    /// The default implementation returns `None`, in which case the registry derives it from
+    /// a node, see [`Registry::metadata`].
+    /// Override it if the node can't be created without a [`NodeConfig`].
+    fn type_metadata() -> Option<NodeMetadata>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Called when the node is discarded while it is `Running`, e.g. removed by [`reload::reload`],
+    /// to give it a chance to clean up or cancel an ongoing action.
+    ///
+    /// It is also called by [`BehaviorNodeContainer::halt`] to interrupt the node, in which case
+    /// the node should reset its state so that the next tick starts over.
+    fn halt(&mut self) {}
+
+    /// Serialize the internal state of the node, which persists between ticks.
+    /// See [`BehaviorNodeContainer::save_state`].
+    ///
+    /// The default implementation returns `None`, which means the node is stateless.
+    fn save_state(
+        &self,
+        _codecs: &codec::CodecRegistry,
+    ) -> Result<Option<serde_json::Value>, error::StateError> {
+        Ok(None)
+    }
+
+    /// Restore the state returned by [`Self::save_state`].
+    fn restore_state(
+        &mut self,
+        _state: &serde_json::Value,
+        _codecs: &codec::CodecRegistry,
+    ) -> Result<(), error::StateError> {
+        Ok(())
+    }
 }
 
 #[macro_export]
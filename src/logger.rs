@@ -0,0 +1,475 @@
+//! A ready-made [`TickObserver`] that records status transitions of nodes.
+//!
+//! [`TransitionLogger`] only remembers the moments when a node's status changes,
+//! e.g. `Idle -> Running` or `Running -> Success`, so the log stays small even if
+//! the tree is ticked every frame.
+//! The recorded [`TransitionLog`] can be written in two formats:
+//!
+//! * JSON lines, one object per line, which is easy to inspect with text tools.
+//! * A compact binary form similar to BehaviorTreeCPP's `.btlog`.
+//!
+//! Both can be read back later for offline analysis, e.g. to find out which node
+//! an NPC was stuck at.
+//!
+//! [`JsonLinesLogger`] writes the JSON lines to a file while the tree is ticked, so the
+//! log survives even if the program crashes or never stops.
+//!
+//! The loggers tell the nodes apart by their [`NodeId`]s, so the tree needs
+//! [`crate::BehaviorNodeContainer::assign_ids`] if it was built by hand instead of
+//! [`crate::load`]. Otherwise every node is logged as `NodeId(0)`.
+
+use crate::{
+    error::LogReadError,
+    observer::{TickEvent, TickObserver},
+    BehaviorResult, NodeId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    io::{self, BufRead, Read, Write},
+    time::{Duration, Instant},
+};
+
+const MAGIC: &[u8; 4] = b"BTLG";
+const VERSION: u8 = 1;
+
+/// Status of a node as seen by the logger.
+/// A node is `Idle` until it is ticked for the first time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeStatus {
+    Idle,
+    Running,
+    Success,
+    Fail,
+}
+
+impl From<BehaviorResult> for NodeStatus {
+    fn from(res: BehaviorResult) -> Self {
+        match res {
+            BehaviorResult::Success => Self::Success,
+            BehaviorResult::Fail => Self::Fail,
+            BehaviorResult::Running => Self::Running,
+        }
+    }
+}
+
+impl NodeStatus {
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Idle => 0,
+            Self::Running => 1,
+            Self::Success => 2,
+            Self::Fail => 3,
+        }
+    }
+
+    fn from_u8(v: u8) -> Option<Self> {
+        Some(match v {
+            0 => Self::Idle,
+            1 => Self::Running,
+            2 => Self::Success,
+            3 => Self::Fail,
+            _ => return None,
+        })
+    }
+}
+
+/// A node that appeared in the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggedNode {
+    pub id: NodeId,
+    /// See [`crate::BehaviorNodeContainer::path`]
+    pub path: String,
+    /// Name of the type of the node
+    pub name: String,
+}
+
+/// A change of the status of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    /// Time since the logger was created, in microseconds
+    pub timestamp_us: u64,
+    pub id: NodeId,
+    pub from: NodeStatus,
+    pub to: NodeStatus,
+}
+
+impl Transition {
+    pub fn timestamp(&self) -> Duration {
+        Duration::from_micros(self.timestamp_us)
+    }
+}
+
+/// A line in the JSON lines format.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonLine {
+    Node(LoggedNode),
+    Transition(Transition),
+}
+
+/// Recorded transitions, either collected by a [`TransitionLogger`] or read back from a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransitionLog {
+    pub nodes: Vec<LoggedNode>,
+    pub transitions: Vec<Transition>,
+}
+
+impl TransitionLog {
+    pub fn node(&self, id: NodeId) -> Option<&LoggedNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Iterate transitions of a particular node.
+    pub fn transitions_of(&self, id: NodeId) -> impl Iterator<Item = &Transition> {
+        self.transitions.iter().filter(move |t| t.id == id)
+    }
+
+    /// Returns the status of each node at the given time since the start of the log.
+    pub fn status_at(&self, time: Duration) -> HashMap<NodeId, NodeStatus> {
+        let mut ret = HashMap::new();
+        for t in self
+            .transitions
+            .iter()
+            .take_while(|t| t.timestamp() <= time)
+        {
+            ret.insert(t.id, t.to);
+        }
+        ret
+    }
+
+    /// Nodes which were `Running` at the end of the log, which are usually the first
+    /// suspects when an agent got stuck.
+    pub fn running_at_end(&self) -> Vec<&LoggedNode> {
+        let statuses = self.status_at(Duration::MAX);
+        self.nodes
+            .iter()
+            .filter(|node| statuses.get(&node.id) == Some(&NodeStatus::Running))
+            .collect()
+    }
+
+    /// Write the log in JSON lines format. Node definitions come first, followed by
+    /// transitions in chronological order.
+    pub fn write_json_lines(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for node in &self.nodes {
+            serde_json::to_writer(&mut writer, &JsonLine::Node(node.clone()))?;
+            writeln!(writer)?;
+        }
+        for transition in &self.transitions {
+            serde_json::to_writer(&mut writer, &JsonLine::Transition(*transition))?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_json_lines(reader: impl BufRead) -> Result<Self, LogReadError> {
+        let mut ret = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line)? {
+                JsonLine::Node(node) => ret.nodes.push(node),
+                JsonLine::Transition(transition) => ret.transitions.push(transition),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Write the log in the compact binary format.
+    ///
+    /// The layout is, in little endian:
+    ///
+    /// ```raw
+    /// "BTLG" version:u8
+    /// num_nodes:u32 { id:u32 path_len:u16 path:[u8] name_len:u16 name:[u8] }*
+    /// { timestamp_us:u64 id:u32 (from << 4 | to):u8 }*
+    /// ```
+    ///
+    /// Transitions continue until the end of the stream, so that a log can be appended
+    /// without rewriting the header.
+    ///
+    /// Returns an error of [`io::ErrorKind::InvalidInput`] if a path or a name is longer
+    /// than the length field can hold.
+    pub fn write_binary(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let num_nodes: u32 = self
+            .nodes
+            .len()
+            .try_into()
+            .map_err(|_| invalid_input(format!("Too many nodes: {}", self.nodes.len())))?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&num_nodes.to_le_bytes())?;
+        for node in &self.nodes {
+            writer.write_all(&node.id.0.to_le_bytes())?;
+            write_str(&mut writer, &node.path)?;
+            write_str(&mut writer, &node.name)?;
+        }
+        for t in &self.transitions {
+            writer.write_all(&t.timestamp_us.to_le_bytes())?;
+            writer.write_all(&t.id.0.to_le_bytes())?;
+            writer.write_all(&[t.from.to_u8() << 4 | t.to.to_u8()])?;
+        }
+        Ok(())
+    }
+
+    pub fn read_binary(mut reader: impl Read) -> Result<Self, LogReadError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(LogReadError::Format("Bad magic number".to_owned()));
+        }
+        let version = read_u8(&mut reader)?;
+        if version != VERSION {
+            return Err(LogReadError::Format(format!(
+                "Unsupported version {version}"
+            )));
+        }
+
+        let mut ret = Self::default();
+        let num_nodes = read_u32(&mut reader)?;
+        for _ in 0..num_nodes {
+            let id = NodeId(read_u32(&mut reader)?);
+            let path = read_str(&mut reader)?;
+            let name = read_str(&mut reader)?;
+            ret.nodes.push(LoggedNode { id, path, name });
+        }
+
+        let mut buf = [0u8; 13];
+        loop {
+            // The log may only end between records
+            match reader.read(&mut buf[..1]) {
+                Ok(0) => break,
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+            match reader.read_exact(&mut buf[1..]) {
+                Ok(()) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    return Err(LogReadError::Format("Truncated record".to_owned()))
+                }
+                Err(e) => return Err(e.into()),
+            }
+            let status = |v: u8| {
+                NodeStatus::from_u8(v)
+                    .ok_or_else(|| LogReadError::Format(format!("Bad node status {v}")))
+            };
+            ret.transitions.push(Transition {
+                timestamp_us: u64::from_le_bytes(buf[0..8].try_into().unwrap()),
+                id: NodeId(u32::from_le_bytes(buf[8..12].try_into().unwrap())),
+                from: status(buf[12] >> 4)?,
+                to: status(buf[12] & 0xf)?,
+            });
+        }
+        Ok(ret)
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> std::io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(reader: &mut impl Read) -> std::io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_str(reader: &mut impl Read) -> Result<String, LogReadError> {
+    let len = read_u16(reader)?;
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| LogReadError::Format("String is not UTF-8".to_owned()))
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> std::io::Result<()> {
+    let len: u16 = s
+        .len()
+        .try_into()
+        .map_err(|_| invalid_input(format!("String is too long: {} bytes", s.len())))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Remembers the status of each node to find out the transitions, for the loggers.
+struct StatusTracker {
+    start: Instant,
+    statuses: HashMap<NodeId, NodeStatus>,
+}
+
+impl StatusTracker {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            statuses: HashMap::new(),
+        }
+    }
+
+    /// Returns the node if it is seen for the first time, and the transition if its status
+    /// has changed.
+    fn update(
+        &mut self,
+        event: &TickEvent,
+        result: BehaviorResult,
+    ) -> (Option<LoggedNode>, Option<Transition>) {
+        let timestamp_us = self.start.elapsed().as_micros() as u64;
+        let status = NodeStatus::from(result);
+        let mut new_node = None;
+        let entry = self.statuses.entry(event.id).or_insert_with(|| {
+            new_node = Some(LoggedNode {
+                id: event.id,
+                path: event.path.to_owned(),
+                name: event.name.to_owned(),
+            });
+            NodeStatus::Idle
+        });
+        let prev = std::mem::replace(entry, status);
+        let transition = (prev != status).then_some(Transition {
+            timestamp_us,
+            id: event.id,
+            from: prev,
+            to: status,
+        });
+        (new_node, transition)
+    }
+}
+
+/// A [`TickObserver`] that records status transitions in memory.
+///
+/// The nodes need ids, see the [module documentation](self).
+///
+/// ```
+/// # use behavior_tree_lite::{*, logger::TransitionLogger};
+/// let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+/// tree.assign_ids("main");
+/// let mut ctx = Context::default();
+/// ctx.add_observer(TransitionLogger::new());
+/// tree.tick(&mut |_| None, &mut ctx);
+/// let log = ctx.remove_observer::<TransitionLogger>().unwrap().into_log();
+/// assert_eq!(log.transitions.len(), 1);
+/// ```
+pub struct TransitionLogger {
+    tracker: StatusTracker,
+    log: TransitionLog,
+}
+
+impl Default for TransitionLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransitionLogger {
+    pub fn new() -> Self {
+        Self {
+            tracker: StatusTracker::new(),
+            log: TransitionLog::default(),
+        }
+    }
+
+    pub fn log(&self) -> &TransitionLog {
+        &self.log
+    }
+
+    pub fn into_log(self) -> TransitionLog {
+        self.log
+    }
+}
+
+impl TickObserver for TransitionLogger {
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, _duration: Duration) {
+        let (node, transition) = self.tracker.update(event, result);
+        self.log.nodes.extend(node);
+        self.log.transitions.extend(transition);
+    }
+}
+
+/// A [`TickObserver`] that writes status transitions in the JSON lines format as they happen.
+///
+/// Each node is written right before its first transition, which
+/// [`TransitionLog::read_json_lines`] reads back like a log written at once.
+/// The binary format needs all the nodes in the header, so it can only be written from a
+/// [`TransitionLog`].
+///
+/// Observers can't return errors while the tree is ticked, so the logger stops writing at
+/// the first error and returns it from [`Self::finish`].
+/// The nodes need ids, see the [module documentation](self).
+///
+/// ```
+/// # use behavior_tree_lite::{*, logger::{JsonLinesLogger, TransitionLog}};
+/// let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+/// tree.assign_ids("main");
+/// let mut ctx = Context::default();
+/// ctx.add_observer(JsonLinesLogger::new(Vec::<u8>::new()));
+/// tree.tick(&mut |_| None, &mut ctx);
+/// let buf = ctx.remove_observer::<JsonLinesLogger<Vec<u8>>>().unwrap().finish().unwrap();
+/// let log = TransitionLog::read_json_lines(&buf[..]).unwrap();
+/// assert_eq!(log.transitions.len(), 1);
+/// ```
+pub struct JsonLinesLogger<W> {
+    tracker: StatusTracker,
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonLinesLogger<W> {
+    /// Create a logger writing to `writer`, which is usually a buffered file.
+    pub fn new(writer: W) -> Self {
+        Self {
+            tracker: StatusTracker::new(),
+            writer,
+            error: None,
+        }
+    }
+
+    /// Flush and return the writer, or the first error that happened while writing.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_line(&mut self, line: &JsonLine) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, line)?;
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write + crate::MaybeSend> TickObserver for JsonLinesLogger<W> {
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, _duration: Duration) {
+        let (node, transition) = self.tracker.update(event, result);
+        if self.error.is_some() {
+            return;
+        }
+        let lines = node
+            .map(JsonLine::Node)
+            .into_iter()
+            .chain(transition.map(JsonLine::Transition));
+        for line in lines {
+            if let Err(error) = self.write_line(&line) {
+                self.error = Some(error);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
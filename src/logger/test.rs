@@ -0,0 +1,153 @@
+use super::*;
+use crate::{BehaviorCallback, BehaviorNode, BehaviorNodeContainer, Context, SequenceNode};
+
+/// Returns Running for the given number of ticks, then Success.
+struct Wait(usize);
+
+impl BehaviorNode for Wait {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        if self.0 == 0 {
+            BehaviorResult::Success
+        } else {
+            self.0 -= 1;
+            BehaviorResult::Running
+        }
+    }
+}
+
+fn tree() -> BehaviorNodeContainer {
+    let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+    tree.add_child(BehaviorNodeContainer::new_node(Wait(0)).with_name("Wait".to_owned()))
+        .unwrap();
+    tree.add_child(BehaviorNodeContainer::new_node(Wait(2)).with_name("Wait".to_owned()))
+        .unwrap();
+    tree.assign_ids("main");
+    tree
+}
+
+fn record(ticks: usize) -> TransitionLog {
+    let mut tree = tree();
+    let mut ctx = Context::default();
+    ctx.add_observer(TransitionLogger::new());
+    for _ in 0..ticks {
+        tree.tick(&mut |_| None, &mut ctx);
+    }
+    ctx.remove_observer::<TransitionLogger>()
+        .unwrap()
+        .into_log()
+}
+
+fn summary(log: &TransitionLog) -> Vec<(&str, NodeStatus, NodeStatus)> {
+    log.transitions
+        .iter()
+        .map(|t| (log.node(t.id).unwrap().path.as_str(), t.from, t.to))
+        .collect()
+}
+
+#[test]
+fn test_transitions() {
+    let log = record(4);
+    use NodeStatus::*;
+    assert_eq!(
+        summary(&log),
+        vec![
+            ("main/Wait[0]", Idle, Success),
+            ("main/Wait[1]", Idle, Running),
+            ("main", Idle, Running),
+            ("main/Wait[1]", Running, Success),
+            ("main", Running, Success),
+        ]
+    );
+}
+
+#[test]
+fn test_running_at_end() {
+    let log = record(2);
+    let running = log
+        .running_at_end()
+        .into_iter()
+        .map(|node| node.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(running, vec!["main/Wait[1]", "main"]);
+}
+
+#[test]
+fn test_json_lines_roundtrip() {
+    let log = record(4);
+    let mut buf = vec![];
+    log.write_json_lines(&mut buf).unwrap();
+    let text = String::from_utf8(buf.clone()).unwrap();
+    assert_eq!(
+        text.lines().count(),
+        log.nodes.len() + log.transitions.len()
+    );
+    assert_eq!(TransitionLog::read_json_lines(&buf[..]).unwrap(), log);
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let log = record(4);
+    let mut buf = vec![];
+    log.write_binary(&mut buf).unwrap();
+    assert_eq!(TransitionLog::read_binary(&buf[..]).unwrap(), log);
+
+    buf[0] = b'X';
+    assert!(matches!(
+        TransitionLog::read_binary(&buf[..]),
+        Err(LogReadError::Format(_))
+    ));
+}
+
+#[test]
+fn test_binary_truncated() {
+    let log = record(4);
+    assert!(!log.transitions.is_empty());
+    let mut buf = vec![];
+    log.write_binary(&mut buf).unwrap();
+    for cut in [1, 5, 12] {
+        assert!(matches!(
+            TransitionLog::read_binary(&buf[..buf.len() - cut]),
+            Err(LogReadError::Format(e)) if e == "Truncated record"
+        ));
+    }
+    // Without the last record
+    let read = TransitionLog::read_binary(&buf[..buf.len() - 13]).unwrap();
+    assert_eq!(
+        read.transitions,
+        log.transitions[..log.transitions.len() - 1]
+    );
+}
+
+#[test]
+fn test_json_lines_logger() {
+    let mut tree = tree();
+    let mut ctx = Context::default();
+    ctx.add_observer(JsonLinesLogger::new(vec![]));
+    for _ in 0..4 {
+        tree.tick(&mut |_| None, &mut ctx);
+    }
+    let buf = ctx
+        .remove_observer::<JsonLinesLogger<Vec<u8>>>()
+        .unwrap()
+        .finish()
+        .unwrap();
+    let log = TransitionLog::read_json_lines(&buf[..]).unwrap();
+    let expected = record(4);
+    // Only the timestamps differ from the log collected in memory
+    assert_eq!(log.nodes, expected.nodes);
+    assert_eq!(summary(&log), summary(&expected));
+}
+
+#[test]
+fn test_binary_too_long() {
+    let log = TransitionLog {
+        nodes: vec![LoggedNode {
+            id: NodeId(0),
+            path: "a".repeat(u16::MAX as usize + 1),
+            name: "Sequence".to_owned(),
+        }],
+        transitions: vec![],
+    };
+    let err = log.write_binary(vec![]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -1,6 +1,11 @@
 use crate::{
-    BehaviorCallback, BehaviorNode, BehaviorNodeContainer, BehaviorResult, Blackboard, Context,
-    Lazy, NumChildren, PortSpec, PortType, Symbol,
+    codec::CodecRegistry,
+    context::Versions,
+    error::{LoadError, StateError},
+    state::{restore_blackboard, save_blackboard},
+    AnyValue, BehaviorCallback, BehaviorNode, BehaviorNodeContainer, BehaviorResult, Blackboard,
+    CallbackEnv, Context, Env, Lazy, NodeCategory, NodeConfig, NodeMetadata, NumChildren, PortSpec,
+    PortType, Shared, Symbol,
 };
 
 pub fn tick_child_node<T>(
@@ -8,44 +13,71 @@ pub fn tick_child_node<T>(
     ctx: &mut Context,
     node: &mut BehaviorNodeContainer,
 ) -> BehaviorResult {
-    std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
-    let res = node.node.tick(arg, ctx);
-    std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
-    res
+    ctx.without_arena(|ctx| {
+        std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
+        let res = node.node.tick(arg, ctx);
+        std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
+        res
+    })
 }
 
 /// SubtreeNode is a container for a subtree, introducing a local namescope of blackboard variables.
 pub struct SubtreeNode {
     /// Blackboard variables needs to be a part of the node payload
     blackboard: Blackboard,
+    versions: Versions,
     params: Vec<PortSpec>,
 }
 
 impl SubtreeNode {
     pub fn new(blackboard: Blackboard, params: Vec<PortSpec>) -> Self {
-        Self { blackboard, params }
+        Self {
+            blackboard,
+            versions: Versions::new(),
+            params,
+        }
     }
 }
 
-impl BehaviorNode for SubtreeNode {
+/// Returns true if the new value of an input parameter is the same as the old one, to avoid
+/// bumping the version every tick. Literals are copied as new `String`s, so we compare them by value.
+fn same_value(old: &Shared<AnyValue>, new: &Shared<AnyValue>) -> bool {
+    Shared::ptr_eq(old, new)
+        || matches!(
+            (old.downcast_ref::<String>(), new.downcast_ref::<String>()),
+            (Some(old), Some(new)) if old == new
+        )
+}
+
+impl<E: Env> BehaviorNode<E> for SubtreeNode {
     fn provided_ports(&self) -> Vec<PortSpec> {
         self.params.clone()
     }
 
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
-        for param in self
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        let inputs = self
             .params
             .iter()
             .filter(|param| matches!(param.ty, PortType::Input | PortType::InOut))
-        {
-            if let Some(value) = ctx.get_any(param.key) {
-                self.blackboard.insert(param.key, value.clone());
+            .filter_map(|param| Some((param.key, ctx.get_any(param.key)?)))
+            .collect::<Vec<_>>();
+
+        ctx.push_scope(
+            std::mem::take(&mut self.blackboard),
+            std::mem::take(&mut self.versions),
+        );
+        for (key, value) in inputs {
+            if !ctx
+                .blackboard
+                .get(&key)
+                .is_some_and(|old| same_value(old, &value))
+            {
+                ctx.blackboard.insert(key, value);
+                ctx.touch(key, false);
             }
         }
-
-        std::mem::swap(&mut self.blackboard, &mut ctx.blackboard);
         let res = ctx.tick_child(0, arg);
-        std::mem::swap(&mut ctx.blackboard, &mut self.blackboard);
+        (self.blackboard, self.versions) = ctx.pop_scope();
 
         // It is debatable if we should assign the output value back to the parent blackboard
         // when the result was Fail or Running. We chose to assign them, which seems less counterintuitive.
@@ -65,6 +97,22 @@ impl BehaviorNode for SubtreeNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Finite(1)
     }
+
+    fn save_state(&self, codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(save_blackboard(
+            &self.blackboard,
+            codecs,
+        )?)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.blackboard = restore_blackboard(&serde_json::from_value(state.clone())?, codecs)?;
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -72,11 +120,19 @@ pub struct SequenceNode {
     current_child: Option<usize>,
 }
 
-impl BehaviorNode for SequenceNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for SequenceNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the children in order until one fails, resuming the running one"
+    }
+
+    fn tick(&mut self, mut arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         let from = self.current_child.unwrap_or(0);
         for i in from..ctx.num_children() {
-            match ctx.tick_child(i, arg) {
+            match ctx.tick_child(i, E::reborrow(&mut arg)) {
                 Some(BehaviorResult::Fail) => {
                     self.current_child = None;
                     return BehaviorResult::Fail;
@@ -95,19 +151,46 @@ impl BehaviorNode for SequenceNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Infinite
     }
+
+    fn halt(&mut self) {
+        self.current_child = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.current_child)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.current_child = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
 }
 
 #[derive(Default)]
 pub struct ReactiveSequenceNode;
 
-impl BehaviorNode for ReactiveSequenceNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for ReactiveSequenceNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the children in order until one fails, from the first one every tick"
+    }
+
+    fn tick(&mut self, mut arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         for i in 0..ctx.num_children() {
-            match ctx.tick_child(i, arg) {
+            match ctx.tick_child(i, E::reborrow(&mut arg)) {
                 Some(BehaviorResult::Fail) => {
                     return BehaviorResult::Fail;
                 }
                 Some(BehaviorResult::Running) => {
+                    // The conditions before the running child need to be checked every tick
+                    ctx.wait_for_next_tick();
                     return BehaviorResult::Running;
                 }
                 _ => (),
@@ -126,11 +209,19 @@ pub struct FallbackNode {
     current_child: Option<usize>,
 }
 
-impl BehaviorNode for FallbackNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for FallbackNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the children in order until one succeeds, resuming the running one"
+    }
+
+    fn tick(&mut self, mut arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         let from = self.current_child.unwrap_or(0);
         for i in from..ctx.num_children() {
-            match ctx.tick_child(i, arg) {
+            match ctx.tick_child(i, E::reborrow(&mut arg)) {
                 Some(BehaviorResult::Success) => {
                     self.current_child = None;
                     return BehaviorResult::Success;
@@ -149,19 +240,46 @@ impl BehaviorNode for FallbackNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Infinite
     }
+
+    fn halt(&mut self) {
+        self.current_child = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.current_child)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.current_child = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
 }
 
 #[derive(Default)]
 pub struct ReactiveFallbackNode;
 
-impl BehaviorNode for ReactiveFallbackNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for ReactiveFallbackNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the children in order until one succeeds, from the first one every tick"
+    }
+
+    fn tick(&mut self, mut arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         for i in 0..ctx.num_children() {
-            match ctx.tick_child(i, arg) {
+            match ctx.tick_child(i, E::reborrow(&mut arg)) {
                 Some(BehaviorResult::Success) => {
                     return BehaviorResult::Success;
                 }
                 Some(BehaviorResult::Running) => {
+                    // The conditions before the running child need to be checked every tick
+                    ctx.wait_for_next_tick();
                     return BehaviorResult::Running;
                 }
                 _ => (),
@@ -178,8 +296,16 @@ impl BehaviorNode for ReactiveFallbackNode {
 #[derive(Default)]
 pub struct ForceSuccessNode;
 
-impl BehaviorNode for ForceSuccessNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for ForceSuccessNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn description(&self) -> &str {
+        "Succeeds unless the child is running"
+    }
+
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         match ctx.tick_child(0, arg) {
             Some(BehaviorResult::Running) => BehaviorResult::Running,
             Some(_) => BehaviorResult::Success,
@@ -192,18 +318,34 @@ impl BehaviorNode for ForceSuccessNode {
     }
 }
 
-#[derive(Default)]
-pub struct ForceFailureNode(Option<BehaviorNodeContainer>);
+pub struct ForceFailureNode<E: Env = CallbackEnv>(Option<BehaviorNodeContainer<E>>);
+
+impl<E: Env> Default for ForceFailureNode<E> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<E: Env> BehaviorNode<E> for ForceFailureNode<E> {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Fails unless the child is running"
+    }
 
-impl BehaviorNode for ForceFailureNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         if let Some(ref mut node) = self.0 {
-            std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
-            if let BehaviorResult::Running = node.node.tick(arg, ctx) {
+            let res = ctx.without_arena(|ctx| {
                 std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
+                let res = node.node.tick(arg, ctx);
+                std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
+                res
+            });
+            if let BehaviorResult::Running = res {
                 return BehaviorResult::Running;
             }
-            std::mem::swap(&mut ctx.blackboard_map, &mut node.blackboard_map);
             BehaviorResult::Fail
         } else {
             BehaviorResult::Fail
@@ -218,8 +360,16 @@ impl BehaviorNode for ForceFailureNode {
 #[derive(Default)]
 pub struct InverterNode;
 
-impl BehaviorNode for InverterNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for InverterNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn description(&self) -> &str {
+        "Inverts the result of the child"
+    }
+
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         match ctx.tick_child(0, arg) {
             Some(BehaviorResult::Running) => BehaviorResult::Running,
             Some(BehaviorResult::Success) => BehaviorResult::Fail,
@@ -235,18 +385,40 @@ impl BehaviorNode for InverterNode {
 
 static N: Lazy<Symbol> = Lazy::new(|| "n".into());
 
+/// The remaining count of a [`RepeatNode`] or a [`RetryNode`] is given to the `n` port, which
+/// is parsed at load time if it is a literal, or every time it starts over otherwise.
 #[derive(Default)]
 pub(super) struct RepeatNode {
     n: Option<usize>,
+    /// The literal value of `n`
+    count: Option<usize>,
 }
 
-impl BehaviorNode for RepeatNode {
+impl RepeatNode {
+    pub(super) fn new(config: &NodeConfig) -> Result<Self, LoadError> {
+        Ok(Self {
+            n: None,
+            count: config.parse(*N)?,
+        })
+    }
+}
+
+impl<E: Env> BehaviorNode<E> for RepeatNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the child n times as long as it succeeds"
+    }
+
     fn provided_ports(&self) -> Vec<PortSpec> {
         vec![PortSpec::new_in(*N)]
     }
 
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
-        if let Some(current) = self.n.or_else(|| ctx.get_parse::<usize>("n")) {
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        let count = self.n.or(self.count);
+        if let Some(current) = count.or_else(|| ctx.require_parse::<usize>(*N)) {
             if current == 0 {
                 self.n = None;
                 return BehaviorResult::Success;
@@ -270,20 +442,57 @@ impl BehaviorNode for RepeatNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Finite(1)
     }
+
+    fn halt(&mut self) {
+        self.n = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.n)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.n = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
 }
 
 #[derive(Default)]
 pub(super) struct RetryNode {
     n: Option<usize>,
+    /// The literal value of `n`
+    count: Option<usize>,
 }
 
-impl BehaviorNode for RetryNode {
+impl RetryNode {
+    pub(super) fn new(config: &NodeConfig) -> Result<Self, LoadError> {
+        Ok(Self {
+            n: None,
+            count: config.parse(*N)?,
+        })
+    }
+}
+
+impl<E: Env> BehaviorNode<E> for RetryNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the child again when it fails, up to n times"
+    }
+
     fn provided_ports(&self) -> Vec<PortSpec> {
         vec![PortSpec::new_in(*N)]
     }
 
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
-        if let Some(current) = self.n.or_else(|| ctx.get_parse::<usize>("n")) {
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        let count = self.n.or(self.count);
+        if let Some(current) = count.or_else(|| ctx.require_parse::<usize>(*N)) {
             if current == 0 {
                 self.n = None;
                 return BehaviorResult::Success;
@@ -307,6 +516,23 @@ impl BehaviorNode for RetryNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Finite(1)
     }
+
+    fn halt(&mut self) {
+        self.n = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.n)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.n = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
 }
 
 pub(crate) static VALUE: Lazy<Symbol> = Lazy::new(|| "value".into());
@@ -314,12 +540,20 @@ pub(crate) static OUTPUT: Lazy<Symbol> = Lazy::new(|| "output".into());
 
 pub(crate) struct SetBoolNode;
 
-impl BehaviorNode for SetBoolNode {
+impl<E: Env> BehaviorNode<E> for SetBoolNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn description(&self) -> &str {
+        "Sets the output to the boolean value"
+    }
+
     fn provided_ports(&self) -> Vec<PortSpec> {
         vec![PortSpec::new_in(*VALUE), PortSpec::new_out(*OUTPUT)]
     }
 
-    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+    fn tick(&mut self, _arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         let result = ctx.get_parse::<bool>(*VALUE);
         if let Some(value) = result {
             ctx.set(*OUTPUT, value);
@@ -334,12 +568,24 @@ pub(crate) static INPUT: Lazy<Symbol> = Lazy::new(|| "input".into());
 
 pub struct IsTrueNode;
 
-impl BehaviorNode for IsTrueNode {
+impl<E: Env> BehaviorNode<E> for IsTrueNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self))
+    }
+
+    fn category(&self) -> NodeCategory {
+        NodeCategory::Condition
+    }
+
+    fn description(&self) -> &str {
+        "Succeeds if the input is true"
+    }
+
     fn provided_ports(&self) -> Vec<PortSpec> {
         vec![PortSpec::new_in(*INPUT)]
     }
 
-    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+    fn tick(&mut self, _arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         if let Some(input) = ctx.get_parse::<bool>(*INPUT) {
             if input {
                 BehaviorResult::Success
@@ -357,12 +603,24 @@ pub struct IfNode {
     condition_result: Option<BehaviorResult>,
 }
 
-impl BehaviorNode for IfNode {
-    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+impl<E: Env> BehaviorNode<E> for IfNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the second child if the first one succeeds, or the third one otherwise"
+    }
+
+    fn tick(&mut self, mut arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
         let condition_result = match self.condition_result {
-            Some(BehaviorResult::Running) => ctx.tick_child(0, arg).unwrap_or(BehaviorResult::Fail),
+            Some(BehaviorResult::Running) => ctx
+                .tick_child(0, E::reborrow(&mut arg))
+                .unwrap_or(BehaviorResult::Fail),
             Some(res) => res,
-            None => ctx.tick_child(0, arg).unwrap_or(BehaviorResult::Fail),
+            None => ctx
+                .tick_child(0, E::reborrow(&mut arg))
+                .unwrap_or(BehaviorResult::Fail),
         };
 
         // Remember the last conditional result in case the child node returns Running
@@ -373,10 +631,13 @@ impl BehaviorNode for IfNode {
         }
 
         let branch_result = match condition_result {
-            BehaviorResult::Success => ctx.tick_child(1, arg).unwrap_or(BehaviorResult::Fail),
+            BehaviorResult::Success => ctx
+                .tick_child(1, E::reborrow(&mut arg))
+                .unwrap_or(BehaviorResult::Fail),
             BehaviorResult::Fail => {
                 // Be aware that lack of else clause is not an error, so the result is Success.
-                ctx.tick_child(2, arg).unwrap_or(BehaviorResult::Success)
+                ctx.tick_child(2, E::reborrow(&mut arg))
+                    .unwrap_or(BehaviorResult::Success)
             }
             BehaviorResult::Running => BehaviorResult::Running,
         };
@@ -393,6 +654,135 @@ impl BehaviorNode for IfNode {
     fn max_children(&self) -> NumChildren {
         NumChildren::Finite(3)
     }
+
+    fn halt(&mut self) {
+        self.condition_result = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.condition_result)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.condition_result = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
+}
+
+pub(crate) static KEYS: Lazy<Symbol> = Lazy::new(|| "keys".into());
+
+/// A decorator that ticks its child only when any of the watched blackboard variables
+/// have changed, and returns the last result of the child otherwise.
+///
+/// The variables are given as a comma separated list of names to the `keys` port, like
+/// `OnChange (keys <- "target, enemy")`. The names refer to the variables in the current
+/// blackboard, not to ports, or in the global blackboard if prefixed with `@`.
+///
+/// The child keeps being ticked while it is `Running`, but if the variables change in the
+/// meantime, it is [halted](BehaviorNodeContainer::halt) and started over.
+#[derive(Default)]
+pub struct OnChangeNode {
+    versions: Option<Vec<Option<u64>>>,
+    last_result: Option<BehaviorResult>,
+}
+
+impl<E: Env> BehaviorNode<E> for OnChangeNode {
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata::of::<E>(&Self::default()))
+    }
+
+    fn description(&self) -> &str {
+        "Ticks the child only when the variables in keys change"
+    }
+
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in(*KEYS)]
+    }
+
+    fn tick(&mut self, arg: E::Arg<'_>, ctx: &mut Context<E>) -> BehaviorResult {
+        let Some(versions) = watched_versions(ctx) else {
+            return BehaviorResult::Fail;
+        };
+        let changed = match &self.versions {
+            Some(old) => *old != versions,
+            // Restored by `restore_state`. The versions don't survive restoring the blackboard,
+            // so the variables are watched from here.
+            None => self.last_result.is_none(),
+        };
+        self.versions.get_or_insert(versions);
+        match self.last_result {
+            Some(BehaviorResult::Running) if changed => ctx.halt_child(0),
+            Some(BehaviorResult::Running) | None => (),
+            Some(res) if !changed => return res,
+            Some(_) => (),
+        }
+
+        let res = ctx.tick_child(0, arg).unwrap_or(BehaviorResult::Fail);
+
+        // Take the versions after ticking, so that the child's own writes do not trigger
+        // another evaluation.
+        self.versions = watched_versions(ctx);
+        self.last_result = Some(res);
+        if res == BehaviorResult::Running {
+            wait_for_watched(ctx);
+        }
+        res
+    }
+
+    fn max_children(&self) -> NumChildren {
+        NumChildren::Finite(1)
+    }
+
+    fn halt(&mut self) {
+        self.versions = None;
+        self.last_result = None;
+    }
+
+    fn save_state(&self, _codecs: &CodecRegistry) -> Result<Option<serde_json::Value>, StateError> {
+        Ok(Some(serde_json::to_value(self.last_result)?))
+    }
+
+    fn restore_state(
+        &mut self,
+        state: &serde_json::Value,
+        _codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.versions = None;
+        self.last_result = serde_json::from_value(state.clone())?;
+        Ok(())
+    }
+}
+
+fn watched_versions<E: Env>(ctx: &Context<E>) -> Option<Vec<Option<u64>>> {
+    let keys = ctx.require::<String>(*KEYS)?;
+    Some(
+        keys.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| match key.strip_prefix('@') {
+                Some(key) => ctx.global_version(key),
+                None => ctx.version(key),
+            })
+            .collect(),
+    )
+}
+
+/// Declare waits on the watched variables, so that the running child is halted as soon as
+/// they change.
+fn wait_for_watched<E: Env>(ctx: &mut Context<E>) {
+    let Some(keys) = ctx.get::<String>(*KEYS).cloned() else {
+        return;
+    };
+    for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+        match key.strip_prefix('@') {
+            Some(key) => ctx.wait_for_variable(key.into(), true),
+            None => ctx.wait_for_variable(key.into(), false),
+        }
+    }
 }
 
 #[cfg(test)]
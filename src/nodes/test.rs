@@ -1,4 +1,11 @@
+// The unit struct nodes are created with `default()` like the other nodes
+#![allow(clippy::default_constructed_unit_structs)]
+
 use super::*;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 type BNContainer = BehaviorNodeContainer;
 
 struct Append<const V: bool = true>;
@@ -631,7 +638,7 @@ fn test_if_node_suspend() {
         ),
         BehaviorResult::Running
     );
-    assert_eq!(res, vec![]);
+    assert_eq!(res, Vec::<bool>::new());
 }
 
 #[test]
@@ -657,7 +664,7 @@ fn test_if_node_true_suspend() {
         ),
         BehaviorResult::Running
     );
-    assert_eq!(res, vec![]);
+    assert_eq!(res, Vec::<bool>::new());
 }
 
 #[test]
@@ -682,5 +689,137 @@ fn test_if_node_false_suspend() {
         ),
         BehaviorResult::Running
     );
-    assert_eq!(res, vec![]);
+    assert_eq!(res, Vec::<bool>::new());
+}
+
+#[test]
+fn test_on_change() {
+    let mut tree = BNContainer::new_node(OnChangeNode::default());
+    tree.add_child(BNContainer::new_node(Append::<true>))
+        .unwrap();
+
+    let mut ctx = Context::default();
+    ctx.set("keys", "target, goal".to_string());
+
+    let mut res = vec![];
+    let mut tick = |ctx: &mut Context| {
+        tree.tick(
+            &mut |v| {
+                res.push(*v.downcast_ref::<bool>().unwrap());
+                None
+            },
+            ctx,
+        )
+    };
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    ctx.set("target", 1);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    ctx.set("other", 1);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    ctx.set("goal", 1);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    assert_eq!(res, vec![true; 3]);
+}
+
+#[derive(Default)]
+struct CountRunning {
+    ticks: usize,
+}
+
+impl BehaviorNode for CountRunning {
+    fn tick(&mut self, arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        self.ticks += 1;
+        arg(&self.ticks);
+        if self.ticks < 3 {
+            BehaviorResult::Running
+        } else {
+            BehaviorResult::Success
+        }
+    }
+
+    fn halt(&mut self) {
+        self.ticks = 0;
+    }
+}
+
+#[test]
+fn test_on_change_halt() {
+    let mut tree = BNContainer::new_node(OnChangeNode::default());
+    tree.add_child(BNContainer::new_node(CountRunning::default()))
+        .unwrap();
+
+    let mut ctx = Context::default();
+    ctx.set("keys", "target".to_string());
+
+    let mut res = vec![];
+    let mut tick = |ctx: &mut Context| {
+        tree.tick(
+            &mut |v| {
+                res.push(*v.downcast_ref::<usize>().unwrap());
+                None
+            },
+            ctx,
+        )
+    };
+    assert_eq!(tick(&mut ctx), BehaviorResult::Running);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Running);
+    ctx.set("target", 1);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Running);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Running);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    assert_eq!(tick(&mut ctx), BehaviorResult::Success);
+    assert_eq!(res, vec![1, 2, 1, 2, 3]);
+}
+
+#[test]
+fn test_subtree_versions() {
+    let (_, source) = crate::parse_file(
+        "tree main = Sequence {
+    Sub (input <- value, result -> ticked)
+}
+tree Sub(in input, out result) = Sequence {
+    OnChange (keys <- \"input\") {
+        Sequence {
+            Append
+            SetBool (value <- \"true\", output -> result)
+        }
+    }
+}",
+    )
+    .unwrap();
+    let mut registry = crate::Registry::default();
+    registry.register("Append", crate::boxify(|| Append::<true>));
+    let mut tree = crate::load(&source, &registry, true).unwrap();
+
+    let mut ctx = Context::default();
+    let notified = Arc::new(AtomicUsize::new(0));
+    let notified2 = notified.clone();
+    ctx.watch("ticked", move |_, _| {
+        notified2.fetch_add(1, Ordering::Relaxed);
+    });
+    ctx.watch("result", |_, _| {
+        panic!("Local variables should not be watched")
+    });
+
+    let mut res = vec![];
+    let mut tick = |ctx: &mut Context| {
+        tree.tick(
+            &mut |_| {
+                res.push(());
+                None
+            },
+            ctx,
+        )
+    };
+    ctx.set("value", 1);
+    for _ in 0..3 {
+        tick(&mut ctx);
+    }
+    ctx.set("value", 2);
+    tick(&mut ctx);
+    assert_eq!(res.len(), 2);
+    // Output ports are written back every tick
+    assert_eq!(notified.load(Ordering::Relaxed), 4);
+    assert_eq!(ctx.version("result"), None);
 }
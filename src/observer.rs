@@ -0,0 +1,57 @@
+//! Hooks to watch what happens inside a tick.
+//!
+//! A [`TickObserver`] is installed on a [`crate::Context`] and gets notified every time
+//! a [`crate::BehaviorNodeContainer`] is entered and exited.
+//! Loggers, profilers and visual debuggers can be built on top of it without
+//! modifying the nodes themselves.
+
+use crate::{BehaviorResult, MaybeSend, NodeId};
+use std::{any::Any, time::Duration};
+
+/// Information about the node being entered or exited.
+#[derive(Debug, Clone, Copy)]
+pub struct TickEvent<'a> {
+    pub id: NodeId,
+    /// See [`crate::BehaviorNodeContainer::path`]
+    pub path: &'a str,
+    /// Name of the type of the node
+    pub name: &'a str,
+}
+
+/// A trait to receive enter/exit notifications of ticks.
+///
+/// Both methods have empty default implementations, so you only need to implement
+/// the ones you are interested in.
+pub trait TickObserver: MaybeSend {
+    /// Called right before the node is ticked.
+    fn on_enter(&mut self, _event: &TickEvent) {}
+
+    /// Called right after the node is ticked, with its result and the time spent
+    /// in the node, including its children.
+    fn on_exit(&mut self, _event: &TickEvent, _result: BehaviorResult, _duration: Duration) {}
+}
+
+/// An object safe extension to [`TickObserver`] that allows us to get the concrete type back
+/// from the [`crate::Context`].
+pub(crate) trait AnyObserver: TickObserver {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: TickObserver + 'static> AnyObserver for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test;
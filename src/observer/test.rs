@@ -0,0 +1,87 @@
+use super::*;
+use crate::{BehaviorCallback, BehaviorNode, BehaviorNodeContainer, Context, SequenceNode};
+
+struct Succeed;
+
+impl BehaviorNode for Succeed {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+}
+
+struct Fail;
+
+impl BehaviorNode for Fail {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Fail
+    }
+}
+
+#[derive(Default)]
+struct EventLog(Vec<String>);
+
+impl TickObserver for EventLog {
+    fn on_enter(&mut self, event: &TickEvent) {
+        self.0
+            .push(format!("enter {} {} {}", event.name, event.id, event.path));
+    }
+
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, _duration: Duration) {
+        self.0
+            .push(format!("exit {} {} {:?}", event.name, event.id, result));
+    }
+}
+
+/// Counts the nodes entered
+#[derive(Default)]
+struct EnterCount(usize);
+
+impl TickObserver for EnterCount {
+    fn on_enter(&mut self, _event: &TickEvent) {
+        self.0 += 1;
+    }
+}
+
+fn named(node: impl BehaviorNode + 'static, name: &str) -> BehaviorNodeContainer {
+    BehaviorNodeContainer::new_node(node).with_name(name.to_owned())
+}
+
+#[test]
+fn test_observer() {
+    let mut tree = named(SequenceNode::default(), "Sequence");
+    tree.add_child(named(Succeed, "Succeed")).unwrap();
+    let mut sub = named(SequenceNode::default(), "Sequence");
+    sub.add_child(named(Fail, "Fail")).unwrap();
+    tree.add_child(sub).unwrap();
+    tree.assign_ids("main");
+
+    let mut ctx = Context::default();
+    ctx.add_observer(EventLog::default());
+    ctx.add_observer(EnterCount::default());
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Fail);
+
+    assert_eq!(
+        ctx.observer::<EventLog>().unwrap().0,
+        vec![
+            "enter Sequence #0 main",
+            "enter Succeed #1 main/Succeed[0]",
+            "exit Succeed #1 Success",
+            "enter Sequence #2 main/Sequence[0]",
+            "enter Fail #3 main/Sequence[0]/Fail[0]",
+            "exit Fail #3 Fail",
+            "exit Sequence #2 Fail",
+            "exit Sequence #0 Fail",
+        ]
+    );
+
+    let log = ctx.remove_observer::<EventLog>().unwrap();
+    assert_eq!(log.0.len(), 8);
+    assert!(ctx.observer::<EventLog>().is_none());
+
+    assert_eq!(ctx.observer::<EnterCount>().unwrap().0, 4);
+
+    // The remaining observer keeps being notified after the other one is removed
+    tree.tick(&mut |_| None, &mut ctx);
+    assert!(ctx.observer::<EventLog>().is_none());
+    assert_eq!(ctx.observer::<EnterCount>().unwrap().0, 8);
+}
@@ -5,8 +5,10 @@ mod yaml_parser;
 pub use self::{
     loader::load,
     nom_parser::{
-        node_def, parse_file, parse_nodes, BlackboardValue, NodeDef, PortMap, PortMapOwned,
-        TreeDef, TreeSource,
+        node_def, parse_file, parse_nodes, BlackboardValue, NodeDef, NodeKind, PortMap,
+        PortMapOwned, SourceLocation, Span, TreeDef, TreeSource,
     },
     yaml_parser::load_yaml,
 };
+
+pub(crate) use self::loader::compile;
@@ -1,10 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
-use super::nom_parser::{TreeDef, TreeSource};
+use super::nom_parser::{PortMap, TreeDef, TreeSource};
 use crate::{
+    blueprint::BlueprintNode,
     error::{AddChildError, LoadError},
     nodes::{IsTrueNode, SubtreeNode, INPUT},
-    BBMap, BehaviorNodeContainer, NumChildren, PortSpec, PortType, Registry, Symbol,
+    registry::{Factory, SharedConstructor},
+    BBMap, BehaviorNode, BehaviorNodeContainer, Env, NodeConfig, NodeMetadata, NumChildren,
+    PortSpec, PortType, Registry, Shared, Symbol,
 };
 
 /// Instantiate a behavior tree from a AST of a tree.
@@ -12,11 +15,25 @@ use crate::{
 /// `check_ports` enables static checking of port availability before actually ticking.
 /// It is useful to catch errors in a behavior tree source file, but you need to
 /// implement [`crate::BehaviorNode::provided_ports`] to use it.
-pub fn load(
+///
+/// The environment type of the tree is the one of the `registry`, see [`crate::Env`].
+///
+/// If you need many instances of the same tree, [`crate::Blueprint`] avoids loading it each time.
+pub fn load<E: Env>(
     tree_source: &TreeSource,
-    registry: &Registry,
+    registry: &Registry<E>,
     check_ports: bool,
-) -> Result<BehaviorNodeContainer, LoadError> {
+) -> Result<BehaviorNodeContainer<E>, LoadError> {
+    compile(tree_source, registry, check_ports)?.into_container()
+}
+
+/// Build the structure of the tree, which [`crate::Blueprint::new`] shares between the instances,
+/// or [`load`] turns into a tree.
+pub(crate) fn compile<E: Env>(
+    tree_source: &TreeSource,
+    registry: &Registry<E>,
+    check_ports: bool,
+) -> Result<BlueprintNode<E>, LoadError> {
     let main = tree_source
         .tree_defs
         .iter()
@@ -30,14 +47,16 @@ pub fn load(
 
     let mut vars = HashSet::new();
 
-    load_recurse(
+    let (mut ret, _) = compile_recurse(
         &main.root,
         registry,
         tree_source,
         check_ports,
         &top,
         &mut vars,
-    )
+    )?;
+    ret.assign_ids(main.name);
+    Ok(ret)
 }
 
 /// A mechanism to detect infinite recursion. It is a linked list in call stack.
@@ -87,16 +106,38 @@ impl<'a, 'src> TreeStack<'a, 'src> {
     }
 }
 
-fn load_recurse(
+/// A compiled node and the metadata of its type, which is used to check the number of
+/// children and the ports.
+type Compiled<E> = (BlueprintNode<E>, NodeMetadata);
+
+fn compile_recurse<E: Env>(
     parent: &TreeDef,
-    registry: &Registry,
+    registry: &Registry<E>,
     tree_source: &TreeSource,
     check_ports: bool,
     parent_stack: &TreeStack,
     vars: &mut HashSet<Symbol>,
-) -> Result<BehaviorNodeContainer, LoadError> {
-    let mut ret = if let Some(ret) = registry.build(parent.ty) {
-        BehaviorNodeContainer::new_raw_with_name(ret, parent.ty.to_string())
+) -> Result<Compiled<E>, LoadError> {
+    let (mut ret, metadata) = if let Some(factory) = registry.factory(parent.ty) {
+        let config = NodeConfig::new(parent.ty, port_map(&parent.port_maps));
+        let known = registry.known_metadata(parent.ty);
+        if let (Factory::Plain(_), Some(metadata)) = (factory, known) {
+            let constructor = factory.constructor(config);
+            (BlueprintNode::new(constructor, parent.ty), metadata.clone())
+        } else {
+            // The node needs to be created anyway to validate the configuration, or to derive
+            // the metadata if the type doesn't declare it. It is kept for `load`.
+            let (instance, constructor) = factory.build(config)?;
+            let metadata = match (factory, known) {
+                (Factory::Plain(_), _) => registry.derive_metadata(parent.ty, &*instance).clone(),
+                (Factory::Configured(_), Some(metadata)) => metadata.clone(),
+                // Not cached, since it may depend on the configuration
+                (Factory::Configured(_), None) => NodeMetadata::of(&*instance),
+            };
+            let mut ret = BlueprintNode::new(constructor, parent.ty);
+            ret.instance = Some(instance);
+            (ret, metadata)
+        }
     } else {
         let tree = tree_source
             .tree_defs
@@ -119,7 +160,7 @@ fn load_recurse(
         // the decision of variable or node.
         let mut vars = HashSet::new();
 
-        let loaded_subtree = load_recurse(
+        let (loaded_subtree, _) = compile_recurse(
             &tree.root,
             registry,
             tree_source,
@@ -127,26 +168,27 @@ fn load_recurse(
             &tree_stack,
             &mut vars,
         )?;
-        BehaviorNodeContainer {
-            name: parent.ty.to_owned(),
-            node: Box::new(SubtreeNode::new(
-                HashMap::new(),
-                tree.ports
-                    .iter()
-                    .map(|port| PortSpec {
-                        key: port.name.into(),
-                        ty: port.direction,
-                    })
-                    .collect(),
-            )),
-            blackboard_map: HashMap::new(),
-            child_nodes: vec![loaded_subtree],
-            last_result: None,
-            is_subtree: true,
-            subtree_expanded: std::cell::Cell::new(false),
-        }
+        let params = tree
+            .ports
+            .iter()
+            .map(|port| PortSpec::new(port.direction, port.name))
+            .collect::<Vec<_>>();
+        let constructor: SharedConstructor<E> = Shared::new(move || {
+            Ok(Box::new(SubtreeNode::new(HashMap::new(), params.clone()))
+                as Box<dyn BehaviorNode<E>>)
+        });
+        let instance = constructor()?;
+        let metadata = NodeMetadata::of(&*instance);
+        let mut ret = BlueprintNode::new(constructor, parent.ty);
+        ret.children.push(loaded_subtree);
+        ret.is_subtree = true;
+        ret.instance = Some(instance);
+        (ret, metadata)
     };
 
+    ret.label = parent.label.map(Shared::from);
+    ret.kind = parent.kind;
+
     // "Hoist" declarations
     for var_def in &parent.vars {
         vars.insert(var_def.name.into());
@@ -160,10 +202,12 @@ fn load_recurse(
                     *INPUT,
                     crate::BlackboardValue::Ref(child.ty.into(), PortType::Input),
                 );
-                Some(
-                    BehaviorNodeContainer::new(Box::new(IsTrueNode), bbmap)
-                        .with_name("IsTrue".to_owned()),
-                )
+                let constructor: SharedConstructor<E> =
+                    Shared::new(|| Ok(Box::new(IsTrueNode) as Box<dyn BehaviorNode<E>>));
+                let mut node = BlueprintNode::new(constructor, "IsTrue");
+                node.blackboard_map = Shared::new(bbmap);
+                node.label = child.label.map(Shared::from);
+                Some(node)
             } else {
                 None
             }
@@ -172,7 +216,7 @@ fn load_recurse(
         };
 
         if new_node.is_none() {
-            let mut child_node = load_recurse(
+            let (mut child_node, child_metadata) = compile_recurse(
                 child,
                 registry,
                 tree_source,
@@ -180,43 +224,29 @@ fn load_recurse(
                 parent_stack,
                 vars,
             )?;
-            let provided_ports = child_node.node.provided_ports();
-            let mut bbmap = BBMap::new();
-            for entry in child.port_maps.iter() {
-                if check_ports {
-                    if let Some(port) = provided_ports.iter().find(|p| p.key == entry.node_port) {
-                        if port.ty != entry.ty {
-                            return Err(LoadError::PortIOUnmatch {
-                                node: child.ty.to_owned(),
-                                port: entry.node_port.to_owned(),
-                            });
-                        }
-                    } else {
-                        return Err(LoadError::PortUnmatch {
+            let provided_ports = &child_metadata.ports;
+            for entry in child.port_maps.iter().filter(|_| check_ports) {
+                if let Some(port) = provided_ports.iter().find(|p| p.key == entry.node_port) {
+                    if port.ty != entry.ty {
+                        return Err(LoadError::PortIOUnmatch {
                             node: child.ty.to_owned(),
                             port: entry.node_port.to_owned(),
                         });
                     }
+                } else {
+                    return Err(LoadError::PortUnmatch {
+                        node: child.ty.to_owned(),
+                        port: entry.node_port.to_owned(),
+                    });
                 }
-                bbmap.insert(
-                    entry.node_port.into(),
-                    match entry.blackboard_value {
-                        super::nom_parser::BlackboardValue::Ref(ref value) => {
-                            crate::BlackboardValue::Ref(value.into(), entry.ty)
-                        }
-                        super::nom_parser::BlackboardValue::Literal(ref value) => {
-                            crate::BlackboardValue::Literal(value.clone())
-                        }
-                    },
-                );
             }
-            child_node.blackboard_map = bbmap;
+            child_node.blackboard_map = Shared::new(port_map(&child.port_maps));
             new_node = Some(child_node);
         }
 
         if let Some(new_node) = new_node {
-            if NumChildren::Finite(ret.child_nodes.len()) < ret.node.max_children() {
-                ret.child_nodes.push(new_node);
+            if NumChildren::Finite(ret.children.len()) < metadata.max_children {
+                ret.children.push(new_node);
             } else {
                 return Err(LoadError::AddChildError(
                     AddChildError::TooManyNodes,
@@ -226,7 +256,41 @@ fn load_recurse(
         }
     }
 
-    Ok(ret)
+    // The labels make the paths of the children, which need to be unique
+    let mut labels = HashSet::new();
+    for child in &ret.children {
+        let Some(label) = child.label.as_deref() else {
+            continue;
+        };
+        if !labels.insert(label) {
+            return Err(LoadError::DuplicateLabel {
+                node: parent.ty.to_string(),
+                label: label.to_owned(),
+            });
+        }
+    }
+
+    Ok((ret, metadata))
+}
+
+fn port_map(port_maps: &[PortMap]) -> BBMap {
+    port_maps
+        .iter()
+        .map(|entry| {
+            let value = match entry.blackboard_value {
+                super::nom_parser::BlackboardValue::Ref(ref value) => {
+                    crate::BlackboardValue::Ref(value.into(), entry.ty)
+                }
+                super::nom_parser::BlackboardValue::Global(ref value) => {
+                    crate::BlackboardValue::Global(value.into(), entry.ty)
+                }
+                super::nom_parser::BlackboardValue::Literal(ref value) => {
+                    crate::BlackboardValue::Literal(value.clone())
+                }
+            };
+            (entry.node_port.into(), value)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -1,5 +1,5 @@
 use super::*;
-use crate::{boxify, error::LoadError, BehaviorNode, BehaviorResult, Context};
+use crate::{boxify, error::LoadError, BehaviorNode, BehaviorResult, Blueprint, Context};
 
 struct PrintNode;
 
@@ -135,6 +135,106 @@ Double (input <- input, output -> output)
     assert_eq!(values, vec![84]);
 }
 
+#[test]
+fn test_subtree_global() {
+    let tree = r#"
+tree main = Sequence {
+sub(input <- "21")
+SendToArg (input <- @doubled)
+}
+
+tree sub(in input) = Sequence {
+Double (input <- input, output -> @doubled)
+inner
+}
+
+tree inner = Sequence {
+SendToArg (input <- @doubled)
+Double (input <- @doubled, output -> local)
+SendToArg (input <- local)
+}
+"#;
+    let (_, tree_source) = crate::parse_file(tree).unwrap();
+    let mut registry = Registry::default();
+    registry.register("SendToArg", boxify(|| SendToArg));
+    registry.register("Double", boxify(|| DoubleNode));
+    let mut tree = load(&tree_source, &registry, true).unwrap();
+
+    let mut values = vec![];
+    let mut ctx = Context::default();
+    let result = tree.tick(
+        &mut |val| {
+            if let Some(val) = val.downcast_ref::<i32>() {
+                values.push(*val)
+            }
+            None
+        },
+        &mut ctx,
+    );
+    assert_eq!(result, BehaviorResult::Success);
+    assert_eq!(values, vec![42, 84, 42]);
+    assert_eq!(ctx.get::<i32>("doubled"), Some(&42));
+    // Local variables do not leak to the global scope
+    assert_eq!(ctx.get::<i32>("local"), None);
+}
+
+#[test]
+fn test_duplicate_label() {
+    let (_, st) = crate::parse_file(
+        "
+tree main = Sequence {
+    Sequence:guard
+    Sequence:guard
+}
+    ",
+    )
+    .unwrap();
+
+    assert_eq!(
+        load(&st, &Registry::default(), false).err(),
+        Some(LoadError::DuplicateLabel {
+            node: "Sequence".to_owned(),
+            label: "guard".to_owned(),
+        })
+    );
+
+    // The same label is fine under different parents
+    let (_, st) = crate::parse_file(
+        "
+tree main = Sequence {
+    Sequence:guard {
+        Sequence:guard
+    }
+}
+    ",
+    )
+    .unwrap();
+    assert!(load(&st, &Registry::default(), false).is_ok());
+
+    // A variable child becomes an IsTrue node with the label
+    let source = "
+tree main = Sequence {
+    var flag = true
+    flag:ready
+    Sequence:LABEL
+}
+    ";
+    let text = source.replace("LABEL", "done");
+    let (_, st) = crate::parse_file(&text).unwrap();
+    let tree = load(&st, &Registry::default(), false).unwrap();
+    assert_eq!(tree.children()[1].path(), "main/ready");
+
+    let text = source.replace("LABEL", "ready");
+    let (_, st) = crate::parse_file(&text).unwrap();
+    assert_eq!(
+        load(&st, &Registry::default(), false).err(),
+        Some(LoadError::DuplicateLabel {
+            node: "Sequence".to_owned(),
+            label: "ready".to_owned(),
+        })
+    );
+}
+
 #[test]
 fn recurse() {
     let (_, st) = crate::parse_file(
@@ -476,3 +576,121 @@ tree main = Sequence {
         BehaviorResult::Success
     );
 }
+
+/// Prints the `message` given as a literal, which is required at load time
+struct PrintMessage(String);
+
+impl BehaviorNode for PrintMessage {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("message")]
+    }
+
+    fn tick(&mut self, arg: crate::BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        arg(&self.0);
+        BehaviorResult::Success
+    }
+}
+
+#[test]
+fn test_node_config() {
+    let mut registry = Registry::default();
+    registry.register_with_config(
+        "PrintMessage",
+        crate::boxify_with_config(|config| {
+            assert_eq!(config.node_type(), "PrintMessage");
+            let message = config.literal("message").ok_or(LoadError::InvalidConfig {
+                node: config.node_type().to_owned(),
+                port: "message".to_owned(),
+                message: "must be a literal".to_owned(),
+            })?;
+            Ok(PrintMessage(message.to_owned()))
+        }),
+    );
+
+    let (_, tree_source) =
+        crate::parse_file(r#"tree main = Sequence { PrintMessage (message <- "hello") }"#).unwrap();
+    let blueprint = Blueprint::new(&tree_source, &registry, true).unwrap();
+    for _ in 0..2 {
+        let mut messages = vec![];
+        blueprint.instantiate().unwrap().tick(
+            &mut |val| {
+                messages.extend(val.downcast_ref::<String>().cloned());
+                None
+            },
+            &mut Context::default(),
+        );
+        assert_eq!(messages, ["hello"]);
+    }
+
+    // A variable is not known at load time
+    let (_, tree_source) =
+        crate::parse_file(r#"tree main = Sequence { PrintMessage (message <- text) }"#).unwrap();
+    assert!(matches!(
+        load(&tree_source, &registry, true),
+        Err(LoadError::InvalidConfig { .. })
+    ));
+}
+
+#[test]
+fn test_constructed_once() {
+    use std::{cell::Cell, rc::Rc};
+
+    // A config type and a plain type that doesn't declare its metadata
+    let configured = Rc::new(Cell::new(0));
+    let plain = Rc::new(Cell::new(0));
+    let mut registry = Registry::default();
+    let configured2 = configured.clone();
+    registry.register_with_config(
+        "PrintMessage",
+        crate::boxify_with_config(move |config| {
+            configured2.set(configured2.get() + 1);
+            Ok(PrintMessage(
+                config.literal("message").unwrap_or("").to_owned(),
+            ))
+        }),
+    );
+    let plain2 = plain.clone();
+    registry.register(
+        "Print",
+        boxify(move || {
+            plain2.set(plain2.get() + 1);
+            PrintNode
+        }),
+    );
+
+    let (_, tree_source) =
+        crate::parse_file(r#"tree main = Sequence { PrintMessage (message <- "hello") Print }"#)
+            .unwrap();
+    load(&tree_source, &registry, true).unwrap();
+    assert_eq!((configured.get(), plain.get()), (1, 1));
+
+    // The metadata of the plain type is known now
+    load(&tree_source, &registry, true).unwrap();
+    assert_eq!((configured.get(), plain.get()), (2, 2));
+    assert!(registry.metadata("Print").is_some());
+    assert_eq!(plain.get(), 2);
+}
+
+#[test]
+fn test_repeat_config() {
+    let registry = Registry::default();
+    let (_, tree_source) =
+        crate::parse_file(r#"tree main = Sequence { Repeat (n <- "two") { Sequence } }"#).unwrap();
+    assert_eq!(
+        load(&tree_source, &registry, true).err(),
+        Some(LoadError::InvalidConfig {
+            node: "Repeat".to_owned(),
+            port: "n".to_owned(),
+            message: "invalid digit found in string".to_owned(),
+        })
+    );
+
+    // The count parsed at load time doesn't need the port any more
+    let (_, tree_source) =
+        crate::parse_file(r#"tree main = Sequence { Repeat (n <- "1") { Sequence } }"#).unwrap();
+    let mut tree = load(&tree_source, &registry, true).unwrap();
+    let mut ctx = Context::default();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+}
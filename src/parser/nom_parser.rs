@@ -12,10 +12,12 @@ use nom::{
 
 use crate::{BlackboardValueOwned, PortType};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct NodeDef<'src> {
     name: &'src str,
     ports: Vec<PortDef<'src>>,
+    span: Option<Span>,
+    location: Option<SourceLocation>,
 }
 
 impl<'src> NodeDef<'src> {
@@ -23,6 +25,8 @@ impl<'src> NodeDef<'src> {
         Self {
             name,
             ports: Vec::new(),
+            span: None,
+            location: None,
         }
     }
 
@@ -33,6 +37,28 @@ impl<'src> NodeDef<'src> {
     pub fn ports(&self) -> &[PortDef<'src>] {
         &self.ports
     }
+
+    /// The range of this definition in the parsed text, see [`Span`].
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// The location of this definition in the parsed text.
+    pub fn location(&self) -> Option<SourceLocation> {
+        self.location
+    }
+
+    fn resolve_spans(&mut self, text: &TextIndex) {
+        (self.span, self.location) = text.resolve(self.span);
+    }
+}
+
+/// The locations are not compared, so that definitions built by hand or parsed from
+/// different texts can be compared by their contents.
+impl PartialEq for NodeDef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.ports == other.ports
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -105,26 +131,80 @@ fn close_brace(i: &str) -> IResult<&str, ()> {
     value((), delimited(space0, char('}'), space0))(i)
 }
 
+/// Parse a node definition. The locations are relative to `i`.
 pub fn node_def(i: &str) -> IResult<&str, NodeDef<'_>> {
-    let (i, _) = delimited(multispace0, tag("node"), space0)(i)?;
+    let text = TextIndex::new(i);
+    let (i, mut node) = node_def_unresolved(i)?;
+    node.resolve_spans(&text);
+    Ok((i, node))
+}
+
+/// Parse node definitions. The locations are relative to `i`.
+pub fn parse_nodes(i: &str) -> IResult<&str, Vec<NodeDef<'_>>> {
+    let text = TextIndex::new(i);
+    let (i, mut nodes) = many0(node_def_unresolved)(i)?;
+    for node in &mut nodes {
+        node.resolve_spans(&text);
+    }
+    Ok((i, nodes))
+}
+
+fn node_def_unresolved(i: &str) -> IResult<&str, NodeDef<'_>> {
+    let (i, _) = multispace0(i)?;
+    let start = i.len();
+
+    let (i, _) = terminated(tag("node"), space0)(i)?;
 
     let (i, name) = delimited(space0, alphanumeric1, space0)(i)?;
 
     let (i, ports) = delimited(open_brace, ports_def, close_brace)(i)?;
 
-    Ok((i, NodeDef { name, ports }))
-}
-
-pub fn parse_nodes(i: &str) -> IResult<&str, Vec<NodeDef<'_>>> {
-    many0(node_def)(i)
+    Ok((
+        i,
+        NodeDef {
+            name,
+            ports,
+            span: Some(Span::unresolved(start, i)),
+            location: None,
+        },
+    ))
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Eq)]
 pub struct TreeDef<'src> {
     pub(crate) ty: &'src str,
+    /// User given name of the node, e.g. `approach` in `MoveTo:approach`
+    pub(crate) label: Option<&'src str>,
     pub(crate) port_maps: Vec<PortMap<'src>>,
     pub(crate) children: Vec<TreeDef<'src>>,
     pub(crate) vars: Vec<VarDef<'src>>,
+    pub(crate) kind: NodeKind,
+    span: Option<Span>,
+    location: Option<SourceLocation>,
+}
+
+/// The locations are not compared, so that trees built by hand or parsed from
+/// different texts can be compared by their structures.
+impl PartialEq for TreeDef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty == other.ty
+            && self.label == other.label
+            && self.port_maps == other.port_maps
+            && self.children == other.children
+            && self.vars == other.vars
+            && self.kind == other.kind
+    }
+}
+
+/// How a node is written in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NodeKind {
+    /// A node written by its name, or inserted by the parser, e.g. `Sequence` for `a && b`
+    Node,
+    /// An `if (condition) { ... } else { ... }` statement, whose children are the condition,
+    /// the then clause and the optional else clause
+    If,
 }
 
 impl<'src> TreeDef<'src> {
@@ -132,6 +212,10 @@ impl<'src> TreeDef<'src> {
         self.ty
     }
 
+    pub fn label(&self) -> Option<&str> {
+        self.label
+    }
+
     pub fn port_maps(&self) -> &[PortMap<'src>] {
         &self.port_maps
     }
@@ -139,6 +223,90 @@ impl<'src> TreeDef<'src> {
     pub fn children(&self) -> &[TreeDef<'src>] {
         &self.children
     }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// The range of this node in the parsed text, see [`Span`].
+    ///
+    /// Nodes implicitly created by the parser, e.g. `Sequence` for `a && b`, do not have one.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// The location of this node in the text given to [`parse_file`].
+    ///
+    /// Nodes implicitly created by the parser do not appear in the source, so the location
+    /// of the first child is returned instead.
+    pub fn location(&self) -> Option<SourceLocation> {
+        self.location
+            .or_else(|| self.children.first().and_then(|child| child.location()))
+    }
+
+    fn resolve_spans(&mut self, text: &TextIndex) {
+        (self.span, self.location) = text.resolve(self.span);
+        for child in &mut self.children {
+            child.resolve_spans(text);
+        }
+    }
+}
+
+/// A position in a source text, both line and column are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A range of a definition in the parsed text, in bytes from the beginning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The parser only sees the rest of the input, so it records the lengths of the rest at
+    /// the start and at the end, which [`TextIndex::resolve`] turns into the offsets later.
+    fn unresolved(start: usize, rest: &str) -> Self {
+        Self {
+            start,
+            end: rest.len(),
+        }
+    }
+}
+
+/// Finds the lines in the text given to the parser, to resolve the spans.
+struct TextIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> TextIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    fn resolve(&self, span: Option<Span>) -> (Option<Span>, Option<SourceLocation>) {
+        let Some(span) = span else {
+            return (None, None);
+        };
+        let span = Span {
+            start: self.text.len() - span.start,
+            end: self.text.len() - span.end,
+        };
+        let line = self.line_starts.partition_point(|&pos| pos <= span.start);
+        let line_start = self.line_starts[line - 1];
+        let location = SourceLocation {
+            line,
+            column: self.text[line_start..span.start].chars().count() + 1,
+        };
+        (Some(span), Some(location))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -158,9 +326,13 @@ impl<'src> TreeDef<'src> {
     fn new(ty: &'src str) -> Self {
         Self {
             ty,
+            label: None,
             port_maps: vec![],
             children: vec![],
             vars: vec![],
+            kind: NodeKind::Node,
+            span: None,
+            location: None,
         }
     }
 
@@ -168,18 +340,26 @@ impl<'src> TreeDef<'src> {
     fn new_with_child(ty: &'src str, child: TreeDef<'src>) -> Self {
         Self {
             ty,
+            label: None,
             port_maps: vec![],
             children: vec![child],
             vars: vec![],
+            kind: NodeKind::Node,
+            span: None,
+            location: None,
         }
     }
 
     fn new_with_children(ty: &'src str, children: Vec<TreeDef<'src>>) -> Self {
         Self {
             ty,
+            label: None,
             port_maps: vec![],
             children,
             vars: vec![],
+            kind: NodeKind::Node,
+            span: None,
+            location: None,
         }
     }
 
@@ -191,9 +371,20 @@ impl<'src> TreeDef<'src> {
     ) -> Self {
         Self {
             ty,
+            label: None,
             port_maps: vec![],
             children,
             vars,
+            kind: NodeKind::Node,
+            span: None,
+            location: None,
+        }
+    }
+
+    fn new_if(children: Vec<TreeDef<'src>>) -> Self {
+        Self {
+            kind: NodeKind::If,
+            ..Self::new_with_children("if", children)
         }
     }
 
@@ -247,18 +438,25 @@ impl<'src> TreeDef<'src> {
 
         Self {
             ty,
+            label: None,
             port_maps,
             children,
             vars,
+            kind: NodeKind::Node,
+            span: None,
+            location: None,
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BlackboardValue<'src> {
     /// Literal value could have been decoded, so it is an owned string.
     Literal(String),
     Ref(&'src str),
+    /// `@name`, a variable in the global blackboard
+    Global(&'src str),
 }
 
 impl<'src> BlackboardValue<'src> {
@@ -266,6 +464,7 @@ impl<'src> BlackboardValue<'src> {
         match self {
             Self::Literal(s) => BlackboardValueOwned::Literal(s.clone()),
             Self::Ref(s) => BlackboardValueOwned::Ref(s.to_string()),
+            Self::Global(s) => BlackboardValueOwned::Global(s.to_string()),
         }
     }
 }
@@ -412,22 +611,31 @@ fn tree_children(i: &str) -> IResult<&str, Vec<TreeElem<'_>>> {
 }
 
 fn parse_tree_node(i: &str) -> IResult<&str, TreeDef<'_>> {
-    let (i, ty) = delimited(space0, identifier, space0)(i)?;
+    let (i, _) = space0(i)?;
+    let start = i.len();
+
+    let (i, ty) = identifier(i)?;
+
+    let (i, label) = opt(preceded(delimited(space0, char(':'), space0), identifier))(i)?;
+
+    let (i, _) = space0(i)?;
 
     let (i, input_ports) = opt(delimited(open_paren, port_maps, close_paren))(i)?;
 
     let (i, children) = opt(delimited(open_brace, tree_children, close_brace))(i)?;
+    let span = Span::unresolved(start, i);
 
     let (i, _) = opt(line_comment_tree_elem)(i)?;
 
-    Ok((
-        i,
-        TreeDef::new_with_ports_and_tree_elems(
-            ty,
-            input_ports.unwrap_or(vec![]),
-            children.unwrap_or(vec![]),
-        ),
-    ))
+    let mut tree_def = TreeDef::new_with_ports_and_tree_elems(
+        ty,
+        input_ports.unwrap_or(vec![]),
+        children.unwrap_or(vec![]),
+    );
+    tree_def.label = label;
+    tree_def.span = Some(span);
+
+    Ok((i, tree_def))
 }
 
 fn parse_tree_elem(i: &str) -> IResult<&str, TreeElem<'_>> {
@@ -471,7 +679,10 @@ fn parse_conditional_expr(i: &str) -> IResult<&str, TreeDef<'_>> {
 }
 
 fn parse_condition_node(i: &str) -> IResult<&str, TreeElem<'_>> {
-    let (i, _ty) = delimited(space0, tag("if"), space0)(i)?;
+    let (i, _) = space0(i)?;
+    let start = i.len();
+
+    let (i, _) = terminated(tag("if"), space0)(i)?;
 
     let (i, condition) = delimited(open_paren, parse_conditional_expr, close_paren)(i)?;
 
@@ -492,10 +703,10 @@ fn parse_condition_node(i: &str) -> IResult<&str, TreeElem<'_>> {
         children.push(TreeDef::new_with_tree_elems("Sequence", else_children));
     }
 
-    Ok((
-        i,
-        TreeElem::Node(TreeDef::new_with_children("if", children)),
-    ))
+    let mut tree_def = TreeDef::new_if(children);
+    tree_def.span = Some(Span::unresolved(start, i));
+
+    Ok((i, TreeElem::Node(tree_def)))
 }
 
 fn var_decl(i: &str) -> IResult<&str, TreeElem<'_>> {
@@ -539,7 +750,8 @@ fn port_map(i: &str) -> IResult<&str, PortMap<'_>> {
 
     let (i, inout) = delimited(space0, alt((tag("<->"), tag("<-"), tag("->"))), space0)(i)?;
 
-    let (i, blackboard_name) = delimited(space0, alt((bb_ref, str_literal)), space0)(i)?;
+    let (i, blackboard_name) =
+        delimited(space0, alt((bb_ref, global_ref, str_literal)), space0)(i)?;
 
     let ty = match inout {
         "<-" => PortType::Input,
@@ -578,6 +790,11 @@ fn bb_ref(i: &str) -> IResult<&str, BlackboardValue<'_>> {
     Ok((i, BlackboardValue::Ref(s)))
 }
 
+fn global_ref(i: &str) -> IResult<&str, BlackboardValue<'_>> {
+    let (i, s) = preceded(char('@'), identifier)(i)?;
+    Ok((i, BlackboardValue::Global(s)))
+}
+
 fn str_literal(input: &str) -> IResult<&str, BlackboardValue<'_>> {
     let (r, val) = delimited(
         preceded(multispace0, char('\"')),
@@ -595,8 +812,17 @@ fn str_literal(input: &str) -> IResult<&str, BlackboardValue<'_>> {
     ))
 }
 
+/// Parse a source text. The locations of the definitions are relative to `i`.
 pub fn parse_file(i: &str) -> Result<(&str, TreeSource<'_>), nom::error::Error<&str>> {
-    source_text(i).finish()
+    let text = TextIndex::new(i);
+    let (rest, mut source) = source_text(i).finish()?;
+    for node in &mut source.node_defs {
+        node.resolve_spans(&text);
+    }
+    for tree in &mut source.tree_defs {
+        tree.root.resolve_spans(&text);
+    }
+    Ok((rest, source))
 }
 
 fn source_text(i: &str) -> IResult<&str, TreeSource<'_>> {
@@ -607,7 +833,7 @@ fn source_text(i: &str) -> IResult<&str, TreeSource<'_>> {
 
     let (i, stmts) = many0(alt((
         delimited(multispace0, line_comment, newline),
-        node_def.map(|node| Some(NodeOrTree::Node(node))),
+        node_def_unresolved.map(|node| Some(NodeOrTree::Node(node))),
         parse_tree.map(|tree| Some(NodeOrTree::Tree(tree))),
     )))(i)?;
 
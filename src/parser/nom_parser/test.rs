@@ -22,6 +22,7 @@ fn test_nodes() {
             vec![NodeDef {
                 name: "A",
                 ports: vec![],
+                ..NodeDef::new("A")
             }]
         ))
     );
@@ -49,6 +50,7 @@ fn test_nodes() {
                         ty: Some("Body"),
                     }
                 ],
+                ..NodeDef::new("A")
             }]
         ))
     );
@@ -154,6 +156,41 @@ fn test_port_literal() {
     );
 }
 
+#[test]
+fn test_port_global() {
+    assert_eq!(
+        parse_tree(
+            r#"tree main = Sequence {
+                PrintBodyNode(in_socket <- @world, out_socket -> @out_val)
+    }"#
+        ),
+        Ok((
+            "",
+            TreeRootDef::new(
+                "main",
+                TreeDef::new_with_child(
+                    "Sequence",
+                    TreeDef::new_with_ports(
+                        "PrintBodyNode",
+                        vec![
+                            PortMap {
+                                ty: PortType::Input,
+                                node_port: "in_socket",
+                                blackboard_value: BlackboardValue::Global("world"),
+                            },
+                            PortMap {
+                                ty: PortType::Output,
+                                node_port: "out_socket",
+                                blackboard_value: BlackboardValue::Global("out_val"),
+                            }
+                        ]
+                    )
+                )
+            )
+        ))
+    );
+}
+
 #[test]
 fn test_file() {
     assert_eq!(
@@ -183,6 +220,7 @@ fn test_file() {
                             ty: Some("Body"),
                         }
                     ],
+                    ..NodeDef::new("A")
                 }],
                 tree_defs: vec![TreeRootDef::new(
                     "main",
@@ -302,13 +340,10 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new("ConditionNode"),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new("ConditionNode"),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                        ])
                     )
                 )]
             }
@@ -336,20 +371,17 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new_with_ports(
-                                    "ConditionNode",
-                                    vec![PortMap {
-                                        ty: PortType::Input,
-                                        node_port: "input",
-                                        blackboard_value: BlackboardValue::Ref("here"),
-                                    }]
-                                ),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new_with_ports(
+                                "ConditionNode",
+                                vec![PortMap {
+                                    ty: PortType::Input,
+                                    node_port: "input",
+                                    blackboard_value: BlackboardValue::Ref("here"),
+                                }]
+                            ),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                        ])
                     )
                 )]
             }
@@ -379,13 +411,10 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new_with_child("ConditionNode", TreeDef::new("No"),),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new_with_child("ConditionNode", TreeDef::new("No"),),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                        ])
                     )
                 )]
             }
@@ -415,14 +444,11 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new("ConditionNode"),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("No")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new("ConditionNode"),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("No")),
+                        ])
                     )
                 )]
             }
@@ -450,13 +476,10 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new_with_child("Inverter", TreeDef::new("ConditionNode")),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new_with_child("Inverter", TreeDef::new("ConditionNode")),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                        ])
                     )
                 )]
             }
@@ -484,19 +507,13 @@ tree main = Sequence {
                     "main",
                     TreeDef::new_with_child(
                         "Sequence",
-                        TreeDef::new_with_children(
-                            "if",
-                            vec![
-                                TreeDef::new_with_child(
-                                    "Inverter",
-                                    TreeDef::new_with_child(
-                                        "Inverter",
-                                        TreeDef::new("ConditionNode")
-                                    )
-                                ),
-                                TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
-                            ],
-                        )
+                        TreeDef::new_if(vec![
+                            TreeDef::new_with_child(
+                                "Inverter",
+                                TreeDef::new_with_child("Inverter", TreeDef::new("ConditionNode"))
+                            ),
+                            TreeDef::new_with_child("Sequence", TreeDef::new("Yes")),
+                        ])
                     )
                 )]
             }
@@ -771,22 +788,19 @@ tree main = Sequence {
                             set_bool("a", "false"),
                             set_bool("b", "true"),
                             set_bool("c", "true"),
-                            TreeDef::new_with_children(
-                                "if",
-                                vec![
-                                    TreeDef::new_with_children(
-                                        "Fallback",
-                                        vec![
-                                            TreeDef::new_with_child("Inverter", TreeDef::new("a")),
-                                            TreeDef::new_with_children(
-                                                "Sequence",
-                                                vec![TreeDef::new("b"), TreeDef::new("c")]
-                                            )
-                                        ]
-                                    ),
-                                    TreeDef::new("Sequence")
-                                ]
-                            )
+                            TreeDef::new_if(vec![
+                                TreeDef::new_with_children(
+                                    "Fallback",
+                                    vec![
+                                        TreeDef::new_with_child("Inverter", TreeDef::new("a")),
+                                        TreeDef::new_with_children(
+                                            "Sequence",
+                                            vec![TreeDef::new("b"), TreeDef::new("c")]
+                                        )
+                                    ]
+                                ),
+                                TreeDef::new("Sequence")
+                            ])
                         ],
                         vec![
                             VarDef {
@@ -834,28 +848,25 @@ tree main = Sequence {
                             set_bool("a", "false"),
                             set_bool("b", "true"),
                             set_bool("c", "true"),
-                            TreeDef::new_with_children(
-                                "if",
-                                vec![
-                                    TreeDef::new_with_children(
-                                        "Sequence",
-                                        vec![
-                                            TreeDef::new_with_children(
-                                                "Fallback",
-                                                vec![
-                                                    TreeDef::new_with_child(
-                                                        "Inverter",
-                                                        TreeDef::new("a")
-                                                    ),
-                                                    TreeDef::new("b")
-                                                ]
-                                            ),
-                                            TreeDef::new("c")
-                                        ]
-                                    ),
-                                    TreeDef::new("Sequence")
-                                ]
-                            )
+                            TreeDef::new_if(vec![
+                                TreeDef::new_with_children(
+                                    "Sequence",
+                                    vec![
+                                        TreeDef::new_with_children(
+                                            "Fallback",
+                                            vec![
+                                                TreeDef::new_with_child(
+                                                    "Inverter",
+                                                    TreeDef::new("a")
+                                                ),
+                                                TreeDef::new("b")
+                                            ]
+                                        ),
+                                        TreeDef::new("c")
+                                    ]
+                                ),
+                                TreeDef::new("Sequence")
+                            ])
                         ],
                         vec![
                             VarDef {
@@ -953,3 +964,74 @@ tree main = Sequence {
         ))
     );
 }
+
+#[test]
+fn test_label() {
+    let mut node = TreeDef::new("MoveTo");
+    node.label = Some("approach");
+    let mut port_node = TreeDef::new_with_ports(
+        "MoveTo",
+        vec![PortMap {
+            ty: PortType::Input,
+            node_port: "target",
+            blackboard_value: BlackboardValue::Ref("enemy"),
+        }],
+    );
+    port_node.label = Some("attack");
+    assert_eq!(
+        parse_tree(
+            "tree main = Sequence {
+                MoveTo:approach
+                MoveTo : attack (target <- enemy)
+                MoveTo # This is a comment, not a label
+                MoveTo#comment
+            }"
+        ),
+        Ok((
+            "",
+            TreeRootDef::new(
+                "main",
+                TreeDef::new_with_children(
+                    "Sequence",
+                    vec![
+                        node,
+                        port_node,
+                        TreeDef::new("MoveTo"),
+                        TreeDef::new("MoveTo")
+                    ]
+                )
+            )
+        ))
+    );
+}
+
+#[test]
+fn test_locations() {
+    let text = "node A {
+}
+tree main = Sequence {
+    Yes:first (input <- here)
+    if (Cond && Other) {
+        Yes
+    }
+}";
+    let (_, source) = parse_file(text).unwrap();
+    let loc = |loc: Option<SourceLocation>| loc.map(|loc| (loc.line, loc.column));
+    assert_eq!(loc(source.node_defs[0].location()), Some((1, 1)));
+    assert_eq!(source.node_defs[0].span(), Some(Span { start: 0, end: 10 }));
+
+    let root = &source.tree_defs[0].root;
+    assert_eq!(loc(root.location()), Some((3, 13)));
+    let first = &root.children()[0];
+    let span = first.span().unwrap();
+    assert_eq!(&text[span.start..span.end], "Yes:first (input <- here)");
+    assert_eq!(loc(first.location()), Some((4, 5)));
+
+    let if_node = &root.children()[1];
+    assert_eq!(if_node.kind(), NodeKind::If);
+    assert_eq!(loc(if_node.location()), Some((5, 5)));
+    // The Sequence inserted for `&&` takes the location of its first child
+    let condition = &if_node.children()[0];
+    assert_eq!(condition.span(), None);
+    assert_eq!(loc(condition.location()), Some((5, 9)));
+}
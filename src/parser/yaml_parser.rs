@@ -52,13 +52,17 @@ fn recurse_parse(value: &serde_yaml::Value, reg: &Registry) -> ParseResult {
     };
 
     Ok(Some(BehaviorNodeContainer {
-        name: name.to_owned(),
+        name: name.into(),
         node,
-        blackboard_map,
+        blackboard_map: blackboard_map.into(),
         child_nodes,
         last_result: None,
         is_subtree: false,
+        kind: crate::parser::NodeKind::Node,
         subtree_expanded: std::cell::Cell::new(false),
+        id: Default::default(),
+        label: None,
+        path: "".into(),
     }))
 }
 
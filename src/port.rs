@@ -10,40 +10,55 @@ pub enum PortType {
     InOut,
 }
 
+/// A port of a node type, created by [`PortSpec::new_in`] and the like so that more fields
+/// can be added without breaking the nodes.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub struct PortSpec {
     pub ty: PortType,
     pub key: Symbol,
+    /// The name of the value type, which is only informative for tools
+    pub value_type: Option<&'static str>,
 }
 
 impl PortSpec {
     pub fn new_in(key: impl Into<Symbol>) -> Self {
-        Self {
-            ty: PortType::Input,
-            key: key.into(),
-        }
+        Self::new(PortType::Input, key)
     }
 
     pub fn new_out(key: impl Into<Symbol>) -> Self {
+        Self::new(PortType::Output, key)
+    }
+
+    pub fn new_inout(key: impl Into<Symbol>) -> Self {
+        Self::new(PortType::InOut, key)
+    }
+
+    pub fn new(ty: PortType, key: impl Into<Symbol>) -> Self {
         Self {
-            ty: PortType::Output,
+            ty,
             key: key.into(),
+            value_type: None,
         }
     }
 
-    pub fn new_inout(key: impl Into<Symbol>) -> Self {
+    /// Set the value type of the port to the name of `T`, like
+    /// `PortSpec::new_in("target").with_type::<Vec2>()`.
+    pub fn with_type<T: ?Sized>(self) -> Self {
         Self {
-            ty: PortType::InOut,
-            key: key.into(),
+            value_type: Some(std::any::type_name::<T>()),
+            ..self
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
 pub enum BlackboardValueOwned {
     /// Literal value could have been decoded, so it is an owned string.
     Literal(String),
     Ref(String),
+    Global(String),
 }
 
 impl crate::BlackboardValue {
@@ -51,6 +66,7 @@ impl crate::BlackboardValue {
         match self {
             Self::Literal(s) => BlackboardValueOwned::Literal(s.clone()),
             Self::Ref(s, _) => BlackboardValueOwned::Ref(s.to_string()),
+            Self::Global(s, _) => BlackboardValueOwned::Global(s.to_string()),
         }
     }
 }
@@ -74,6 +90,7 @@ impl<'src> AbstractPortMap<'src> for PortMap<'src> {
         match &self.blackboard_value {
             BlackboardValue::Literal(s) => BlackboardValueOwned::Literal(s.clone()),
             BlackboardValue::Ref(s) => BlackboardValueOwned::Ref(s.to_string()),
+            BlackboardValue::Global(s) => BlackboardValueOwned::Global(s.to_string()),
         }
     }
 }
@@ -0,0 +1,224 @@
+//! A ready-made [`TickObserver`] that measures how much time each node takes.
+//!
+//! [`Profiler`] is notified like any other observer when [`crate::BehaviorNodeContainer::tick`]
+//! or [`crate::ArenaTree`] ticks a node, so it costs nothing unless it is installed.
+//! It accumulates per-node statistics, which can be printed as a text report sorted by
+//! the most expensive nodes, or written in the folded stack format that
+//! [flamegraph](https://github.com/brendangregg/FlameGraph) tools accept.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, profiler::*};
+//! let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+//! tree.assign_ids("main");
+//! let mut ctx = Context::default();
+//! ctx.add_observer(Profiler::new());
+//! for _ in 0..10 {
+//!     tree.tick(&mut |_| None, &mut ctx);
+//! }
+//! let profiler = ctx.observer::<Profiler>().unwrap();
+//! assert_eq!(profiler.stats_by_path("main").unwrap().count, 10);
+//! println!("{}", profiler.report(SortBy::SelfTime));
+//! ```
+
+use crate::{
+    observer::{TickEvent, TickObserver},
+    BehaviorResult, NodeId,
+};
+use std::{collections::HashMap, fmt::Write as _, io::Write, time::Duration};
+
+/// Accumulated statistics of a node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    pub id: NodeId,
+    /// See [`crate::BehaviorNodeContainer::path`]
+    pub path: String,
+    /// Name of the type of the node
+    pub name: String,
+    /// Number of times the node was ticked
+    pub count: u64,
+    pub success: u64,
+    pub fail: u64,
+    pub running: u64,
+    /// Time spent in the node itself, excluding its children
+    pub total_self: Duration,
+    pub max_self: Duration,
+    /// Time spent in the node including its children
+    pub total_inclusive: Duration,
+    pub max_inclusive: Duration,
+}
+
+impl NodeStats {
+    pub fn mean_self(&self) -> Duration {
+        mean(self.total_self, self.count)
+    }
+
+    pub fn mean_inclusive(&self) -> Duration {
+        mean(self.total_inclusive, self.count)
+    }
+
+    /// Number of times the node returned the given result.
+    pub fn result_count(&self, result: BehaviorResult) -> u64 {
+        match result {
+            BehaviorResult::Success => self.success,
+            BehaviorResult::Fail => self.fail,
+            BehaviorResult::Running => self.running,
+        }
+    }
+}
+
+fn mean(total: Duration, count: u64) -> Duration {
+    if count == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_nanos((total.as_nanos() / count as u128) as u64)
+    }
+}
+
+/// The order of the rows in [`Profiler::report`], from the largest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    SelfTime,
+    InclusiveTime,
+    MaxSelfTime,
+    MaxInclusiveTime,
+    Count,
+}
+
+/// A [`TickObserver`] that accumulates [`NodeStats`] of each node, keyed by [`NodeId`].
+#[derive(Debug, Default)]
+pub struct Profiler {
+    stats: HashMap<NodeId, NodeStats>,
+    /// Inclusive time of the children of each node in the current call stack
+    children_time: Vec<Duration>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self, id: NodeId) -> Option<&NodeStats> {
+        self.stats.get(&id)
+    }
+
+    pub fn stats_by_path(&self, path: &str) -> Option<&NodeStats> {
+        self.stats.values().find(|stats| stats.path == path)
+    }
+
+    /// Iterate statistics of all nodes that have been ticked, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &NodeStats> {
+        self.stats.values()
+    }
+
+    /// Statistics of all nodes sorted by the given key, from the largest.
+    pub fn sorted(&self, sort_by: SortBy) -> Vec<&NodeStats> {
+        let mut ret = self.stats.values().collect::<Vec<_>>();
+        ret.sort_by(|a, b| {
+            let ordering = match sort_by {
+                SortBy::SelfTime => b.total_self.cmp(&a.total_self),
+                SortBy::InclusiveTime => b.total_inclusive.cmp(&a.total_inclusive),
+                SortBy::MaxSelfTime => b.max_self.cmp(&a.max_self),
+                SortBy::MaxInclusiveTime => b.max_inclusive.cmp(&a.max_inclusive),
+                SortBy::Count => b.count.cmp(&a.count),
+            };
+            ordering.then_with(|| a.id.cmp(&b.id))
+        });
+        ret
+    }
+
+    /// Clear all the statistics, e.g. at the start of a frame of interest.
+    pub fn reset(&mut self) {
+        self.stats.clear();
+    }
+
+    /// A human readable table of the statistics. Times are in microseconds.
+    pub fn report(&self, sort_by: SortBy) -> String {
+        let mut ret = String::new();
+        writeln!(
+            ret,
+            "{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>8} {:>8} {:>8} {:>8}  path",
+            "self",
+            "mean self",
+            "max self",
+            "incl",
+            "mean incl",
+            "max incl",
+            "count",
+            "success",
+            "fail",
+            "running"
+        )
+        .unwrap();
+        for stats in self.sorted(sort_by) {
+            writeln!(
+                ret,
+                "{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>8} {:>8} {:>8} {:>8}  {} ({})",
+                stats.total_self.as_micros(),
+                stats.mean_self().as_micros(),
+                stats.max_self.as_micros(),
+                stats.total_inclusive.as_micros(),
+                stats.mean_inclusive().as_micros(),
+                stats.max_inclusive.as_micros(),
+                stats.count,
+                stats.success,
+                stats.fail,
+                stats.running,
+                stats.path,
+                stats.name
+            )
+            .unwrap();
+        }
+        ret
+    }
+
+    /// Write the self time of each node in the folded stack format, one line per node like
+    /// `main;Sequence[0];approach 1234`, where the number is in microseconds.
+    /// The output can be fed into `flamegraph.pl` or `inferno-flamegraph`.
+    pub fn write_folded(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let mut stats = self.stats.values().collect::<Vec<_>>();
+        stats.sort_by_key(|stats| stats.id);
+        for stats in stats {
+            writeln!(
+                writer,
+                "{} {}",
+                stats.path.replace('/', ";"),
+                stats.total_self.as_micros()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl TickObserver for Profiler {
+    fn on_enter(&mut self, _event: &TickEvent) {
+        self.children_time.push(Duration::ZERO);
+    }
+
+    fn on_exit(&mut self, event: &TickEvent, result: BehaviorResult, duration: Duration) {
+        let children_time = self.children_time.pop().unwrap_or_default();
+        if let Some(parent) = self.children_time.last_mut() {
+            *parent += duration;
+        }
+        let self_time = duration.saturating_sub(children_time);
+
+        let stats = self.stats.entry(event.id).or_insert_with(|| NodeStats {
+            id: event.id,
+            path: event.path.to_owned(),
+            name: event.name.to_owned(),
+            ..NodeStats::default()
+        });
+        stats.count += 1;
+        match result {
+            BehaviorResult::Success => stats.success += 1,
+            BehaviorResult::Fail => stats.fail += 1,
+            BehaviorResult::Running => stats.running += 1,
+        }
+        stats.total_self += self_time;
+        stats.max_self = stats.max_self.max(self_time);
+        stats.total_inclusive += duration;
+        stats.max_inclusive = stats.max_inclusive.max(duration);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,110 @@
+use super::*;
+use crate::{BehaviorCallback, BehaviorNode, BehaviorNodeContainer, Context, SequenceNode};
+
+/// Returns Running for the given number of ticks, then Success.
+struct Wait(usize);
+
+impl BehaviorNode for Wait {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        if self.0 == 0 {
+            BehaviorResult::Success
+        } else {
+            self.0 -= 1;
+            BehaviorResult::Running
+        }
+    }
+}
+
+fn event<'a>(id: u32, path: &'a str) -> TickEvent<'a> {
+    TickEvent {
+        id: NodeId(id),
+        path,
+        name: "Node",
+    }
+}
+
+#[test]
+fn test_counts() {
+    let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+    tree.add_child(BehaviorNodeContainer::new_node(Wait(2)).with_name("Wait".to_owned()))
+        .unwrap();
+    tree.assign_ids("main");
+
+    let mut ctx = Context::default();
+    ctx.add_observer(Profiler::new());
+    for _ in 0..4 {
+        tree.tick(&mut |_| None, &mut ctx);
+    }
+
+    let profiler = ctx.observer::<Profiler>().unwrap();
+    let stats = profiler.stats_by_path("main/Wait[0]").unwrap();
+    assert_eq!(stats.id, NodeId(1));
+    assert_eq!(stats.count, 4);
+    assert_eq!(stats.result_count(BehaviorResult::Running), 2);
+    assert_eq!(stats.result_count(BehaviorResult::Success), 2);
+    assert_eq!(stats.fail, 0);
+
+    let root = profiler.stats(NodeId(0)).unwrap();
+    assert!(root.total_self <= root.total_inclusive);
+    assert!(stats.total_inclusive <= root.total_inclusive);
+}
+
+#[test]
+fn test_self_time() {
+    let mut profiler = Profiler::new();
+    // main (10ms) contains a (3ms) and b (4ms), then main is ticked again for 2ms alone
+    profiler.on_enter(&event(0, "main"));
+    profiler.on_enter(&event(1, "main/a"));
+    profiler.on_exit(
+        &event(1, "main/a"),
+        BehaviorResult::Success,
+        Duration::from_millis(3),
+    );
+    profiler.on_enter(&event(2, "main/b"));
+    profiler.on_exit(
+        &event(2, "main/b"),
+        BehaviorResult::Fail,
+        Duration::from_millis(4),
+    );
+    profiler.on_exit(
+        &event(0, "main"),
+        BehaviorResult::Fail,
+        Duration::from_millis(10),
+    );
+    profiler.on_enter(&event(0, "main"));
+    profiler.on_exit(
+        &event(0, "main"),
+        BehaviorResult::Running,
+        Duration::from_millis(2),
+    );
+
+    let main = profiler.stats(NodeId(0)).unwrap();
+    assert_eq!(main.count, 2);
+    assert_eq!(main.total_self, Duration::from_millis(5));
+    assert_eq!(main.max_self, Duration::from_millis(3));
+    assert_eq!(main.total_inclusive, Duration::from_millis(12));
+    assert_eq!(main.max_inclusive, Duration::from_millis(10));
+    assert_eq!(main.mean_inclusive(), Duration::from_millis(6));
+
+    let order = profiler
+        .sorted(SortBy::SelfTime)
+        .iter()
+        .map(|stats| stats.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec!["main", "main/b", "main/a"]);
+
+    let report = profiler.report(SortBy::InclusiveTime);
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].ends_with("main (Node)"));
+
+    let mut folded = vec![];
+    profiler.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "main 5000\nmain;a 3000\nmain;b 4000\n"
+    );
+
+    profiler.reset();
+    assert_eq!(profiler.iter().count(), 0);
+}
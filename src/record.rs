@@ -0,0 +1,533 @@
+//! Recording executions of a tree and replaying them deterministically.
+//!
+//! A [`Recorder`] ticks a tree and captures, for each tick, everything that came from the
+//! outside world:
+//!
+//! * Blackboard values read through [`Context::get`] and [`Context::get_any`]
+//! * Responses of the [`BehaviorCallback`]
+//!
+//! along with the results of each node.
+//! Values are only serialized if their types are registered in the [`CodecRegistry`];
+//! other values are recorded as opaque and read from the live blackboard on replay.
+//!
+//! The responses are only recorded for the default [`crate::CallbackEnv`], since other
+//! [environments](crate::Env) are opaque to the recorder, see [`crate::Env::record_arg`].
+//!
+//! A [`Replayer`] drives a freshly loaded tree by feeding back the recorded inputs
+//! instead of the live ones, and compares what happens with the recording.
+//! It reports the first [`Divergence`], which tells where the behavior started to differ,
+//! e.g. because of hidden state in a node that was not captured.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, codec::CodecRegistry, record::*};
+//! # struct Check;
+//! # impl BehaviorNode for Check {
+//! #     fn tick(&mut self, _: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+//! #         if ctx.get::<bool>("ok") == Some(&true) { BehaviorResult::Success } else { BehaviorResult::Fail }
+//! #     }
+//! # }
+//! let mut tree = BehaviorNodeContainer::new_node(Check);
+//! tree.assign_ids("main");
+//! let mut ctx = Context::default();
+//! ctx.set("ok", true);
+//!
+//! let mut recorder = Recorder::new(CodecRegistry::with_primitives());
+//! recorder.tick(&mut tree, &mut |_| None, &mut ctx);
+//! let recording = recorder.into_recording();
+//!
+//! // The blackboard does not have "ok" this time, but the recorded value is used.
+//! let mut tree = BehaviorNodeContainer::new_node(Check);
+//! tree.assign_ids("main");
+//! let mut replayer = Replayer::new(recording, CodecRegistry::with_primitives());
+//! let res = replayer.tick(&mut tree, &mut |_| None, &mut Context::default());
+//! assert_eq!(res, Some(BehaviorResult::Success));
+//! assert!(replayer.divergence().is_none());
+//! ```
+
+use crate::{
+    codec::CodecRegistry, AnyValue, ArenaTree, BehaviorCallback, BehaviorNodeContainer,
+    BehaviorResult, CallbackEnv, Context, Env, NodeId, Shared, Symbol,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    io::{Read, Write},
+    marker::PhantomData,
+    sync::Mutex,
+};
+
+/// A recorded value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedValue {
+    /// There was no value
+    Missing,
+    /// There was a value, but its type is not registered in the [`CodecRegistry`]
+    Opaque,
+    Value {
+        #[serde(rename = "type")]
+        ty: String,
+        value: serde_json::Value,
+    },
+}
+
+impl RecordedValue {
+    fn encode(codecs: &CodecRegistry, value: Option<&dyn Any>) -> Self {
+        match value {
+            None => Self::Missing,
+            Some(value) => match codecs.encode(value) {
+                Some((ty, value)) => Self::Value {
+                    ty: ty.to_owned(),
+                    value,
+                },
+                None => Self::Opaque,
+            },
+        }
+    }
+
+    fn decode(&self, codecs: &CodecRegistry) -> Option<Box<AnyValue>> {
+        match self {
+            Self::Value { ty, value } => codecs.decode(ty, value.clone())?.ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Something that happened during a tick, in the order it happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordEvent {
+    /// A blackboard variable was read by the node
+    Read {
+        node: NodeId,
+        key: String,
+        value: RecordedValue,
+    },
+    /// The node called the [`BehaviorCallback`] and got the response
+    Callback {
+        node: NodeId,
+        response: RecordedValue,
+    },
+    /// The node returned the result
+    Result {
+        node: NodeId,
+        result: BehaviorResult,
+    },
+}
+
+impl RecordEvent {
+    /// Returns true if it is the same kind of event on the same node, ignoring values.
+    fn same_site(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Read { node, key, .. },
+                Self::Read {
+                    node: n, key: k, ..
+                },
+            ) => node == n && key == k,
+            (Self::Callback { node, .. }, Self::Callback { node: n, .. }) => node == n,
+            (Self::Result { .. }, Self::Result { .. }) => self == other,
+            _ => false,
+        }
+    }
+}
+
+/// Events of all the ticks recorded by a [`Recorder`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub ticks: Vec<Vec<RecordEvent>>,
+}
+
+impl Recording {
+    pub fn write_json(&self, writer: impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn read_json(reader: impl Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+}
+
+/// The first point where a replay differed from the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Index of the tick in the recording
+    pub tick: usize,
+    /// Index of the event in the tick
+    pub index: usize,
+    /// The recorded event, or `None` if the replay produced more events than recorded
+    pub expected: Option<RecordEvent>,
+    /// The event in the replay, or `None` if the tick finished earlier than recorded
+    pub actual: Option<RecordEvent>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Diverged at tick {} event {}: expected {:?}, got {:?}",
+            self.tick, self.index, self.expected, self.actual
+        )
+    }
+}
+
+/// The state of recording or replaying shared by the [`Context`] and the callback wrapper
+/// during a tick.
+struct Tape {
+    codecs: Shared<CodecRegistry>,
+    tick: usize,
+    events: Vec<RecordEvent>,
+    /// Recorded events of this tick, only in replay
+    expected: Option<Vec<RecordEvent>>,
+    divergence: Option<Divergence>,
+    current_node: NodeId,
+}
+
+enum ReadOutcome {
+    Live,
+    Missing,
+    Replayed(usize),
+}
+
+impl Tape {
+    fn push(&mut self, event: RecordEvent) -> Option<&RecordEvent> {
+        let index = self.events.len();
+        self.events.push(event);
+        let expected = self.expected.as_ref()?;
+        if self.divergence.is_some() {
+            return None;
+        }
+        let actual = &self.events[index];
+        match expected.get(index) {
+            Some(expected) if expected.same_site(actual) => Some(expected),
+            expected => {
+                self.divergence = Some(Divergence {
+                    tick: self.tick,
+                    index,
+                    expected: expected.cloned(),
+                    actual: Some(actual.clone()),
+                });
+                None
+            }
+        }
+    }
+
+    fn read(&mut self, key: Symbol, live: Option<&dyn Any>) -> ReadOutcome {
+        let index = self.events.len();
+        let event = RecordEvent::Read {
+            node: self.current_node,
+            key: key.to_string(),
+            value: RecordedValue::encode(&self.codecs, live),
+        };
+        match self.push(event) {
+            Some(RecordEvent::Read {
+                value: RecordedValue::Missing,
+                ..
+            }) => ReadOutcome::Missing,
+            Some(RecordEvent::Read {
+                value: RecordedValue::Value { .. },
+                ..
+            }) => ReadOutcome::Replayed(index),
+            _ => ReadOutcome::Live,
+        }
+    }
+
+    fn finish(&mut self) {
+        let Some(ref expected) = self.expected else {
+            return;
+        };
+        if self.divergence.is_none() && self.events.len() < expected.len() {
+            self.divergence = Some(Divergence {
+                tick: self.tick,
+                index: self.events.len(),
+                expected: Some(expected[self.events.len()].clone()),
+                actual: None,
+            });
+        }
+    }
+}
+
+/// What [`Context`] holds while recording or replaying.
+pub(crate) struct TapeHandle {
+    tape: Shared<Mutex<Tape>>,
+    /// Values to be returned by reads in replay, decoded beforehand because
+    /// [`Context::get`] returns a reference, keyed by the index of the event
+    decoded: HashMap<usize, Shared<AnyValue>>,
+}
+
+/// How a blackboard read should be answered.
+pub(crate) enum ReadOverride<'a> {
+    Live,
+    Missing,
+    Value(&'a Shared<AnyValue>),
+}
+
+impl TapeHandle {
+    pub(crate) fn read(&self, key: Symbol, live: Option<&dyn Any>) -> ReadOverride<'_> {
+        match self.tape.lock().unwrap().read(key, live) {
+            ReadOutcome::Live => ReadOverride::Live,
+            ReadOutcome::Missing => ReadOverride::Missing,
+            ReadOutcome::Replayed(index) => self
+                .decoded
+                .get(&index)
+                .map_or(ReadOverride::Live, ReadOverride::Value),
+        }
+    }
+
+    /// Called when a node is entered, returns the previous node to be restored with [`Self::exit`].
+    pub(crate) fn enter(&self, node: NodeId) -> NodeId {
+        std::mem::replace(&mut self.tape.lock().unwrap().current_node, node)
+    }
+
+    pub(crate) fn exit(&self, prev: NodeId, node: NodeId, result: BehaviorResult) {
+        let mut tape = self.tape.lock().unwrap();
+        tape.push(RecordEvent::Result { node, result });
+        tape.current_node = prev;
+    }
+}
+
+/// What [`Env::record_arg`] records the responses of the environment to.
+pub struct ArgTape(Shared<Mutex<Tape>>);
+
+impl ArgTape {
+    /// Answer a request to the [`BehaviorCallback`], with the recorded response in replay.
+    pub(crate) fn call(&self, arg: BehaviorCallback, req: &dyn Any) -> Option<Box<dyn Any>> {
+        let replayed = {
+            let tape = self.0.lock().unwrap();
+            let index = tape.events.len();
+            match tape.expected.as_ref().and_then(|e| e.get(index)) {
+                Some(RecordEvent::Callback { node, response })
+                    if *node == tape.current_node && tape.divergence.is_none() =>
+                {
+                    let ret = response.decode(&tape.codecs).map(|ret| ret as Box<dyn Any>);
+                    Some((response.clone(), ret))
+                }
+                _ => None,
+            }
+        };
+        let (response, ret) = match replayed {
+            Some((RecordedValue::Opaque, _)) | None => {
+                let ret = arg(req);
+                let response =
+                    RecordedValue::encode(&self.0.lock().unwrap().codecs, ret.as_deref());
+                (response, ret)
+            }
+            Some((response, ret)) => (response, ret),
+        };
+        let mut tape = self.0.lock().unwrap();
+        let node = tape.current_node;
+        tape.push(RecordEvent::Callback { node, response });
+        ret
+    }
+}
+
+/// Tick the tree with the tape installed in the context and the argument wrapped.
+/// Returns the events and the divergence of the tick.
+fn tick_with_tape<E: Env>(
+    tape: Tape,
+    decoded: HashMap<usize, Shared<AnyValue>>,
+    tick: impl FnOnce(E::Arg<'_>, &mut Context<E>) -> BehaviorResult,
+    arg: E::Arg<'_>,
+    ctx: &mut Context<E>,
+) -> (BehaviorResult, Vec<RecordEvent>, Option<Divergence>) {
+    let tape = Shared::new(Mutex::new(tape));
+    ctx.tape.0 = Some(TapeHandle {
+        tape: tape.clone(),
+        decoded,
+    });
+    let res = E::record_arg(arg, &ArgTape(tape.clone()), |arg| tick(arg, ctx));
+    ctx.tape.0 = None;
+
+    // Take the contents rather than the tape itself, which a custom environment may still hold
+    let mut tape = tape.lock().unwrap();
+    tape.finish();
+    (
+        res,
+        std::mem::take(&mut tape.events),
+        tape.divergence.take(),
+    )
+}
+
+/// Ticks a tree while recording inputs and results.
+#[derive(Debug)]
+pub struct Recorder<E: Env = CallbackEnv> {
+    codecs: Shared<CodecRegistry>,
+    recording: Recording,
+    _env: PhantomData<fn() -> E>,
+}
+
+impl Recorder {
+    pub fn new(codecs: CodecRegistry) -> Self {
+        Self::new_generic(codecs)
+    }
+}
+
+impl<E: Env> Recorder<E> {
+    /// Create a recorder for a tree with any environment type, see [`Context::new_generic`].
+    pub fn new_generic(codecs: CodecRegistry) -> Self {
+        Self {
+            codecs: Shared::new(codecs),
+            recording: Recording::default(),
+            _env: PhantomData,
+        }
+    }
+
+    /// Tick the tree once and record it, in place of [`BehaviorNodeContainer::tick`].
+    pub fn tick(
+        &mut self,
+        tree: &mut BehaviorNodeContainer<E>,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> BehaviorResult {
+        self.record(|arg, ctx| tree.tick(arg, ctx), arg, ctx)
+    }
+
+    /// Tick an [`ArenaTree`] once and record it, in place of [`ArenaTree::tick`].
+    pub fn tick_arena(
+        &mut self,
+        tree: &mut ArenaTree<E>,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> BehaviorResult {
+        self.record(|arg, ctx| tree.tick(arg, ctx), arg, ctx)
+    }
+
+    fn record(
+        &mut self,
+        tick: impl FnOnce(E::Arg<'_>, &mut Context<E>) -> BehaviorResult,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> BehaviorResult {
+        let tape = Tape {
+            codecs: self.codecs.clone(),
+            tick: self.recording.ticks.len(),
+            events: vec![],
+            expected: None,
+            divergence: None,
+            current_node: NodeId::default(),
+        };
+        let (res, events, _) = tick_with_tape(tape, HashMap::new(), tick, arg, ctx);
+        self.recording.ticks.push(events);
+        res
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Ticks a tree with the inputs from a [`Recording`] and checks the results match.
+#[derive(Debug)]
+pub struct Replayer<E: Env = CallbackEnv> {
+    codecs: Shared<CodecRegistry>,
+    recording: Recording,
+    tick: usize,
+    divergence: Option<Divergence>,
+    _env: PhantomData<fn() -> E>,
+}
+
+impl Replayer {
+    /// The codecs should have the same types registered as the ones used for recording.
+    pub fn new(recording: Recording, codecs: CodecRegistry) -> Self {
+        Self::new_generic(recording, codecs)
+    }
+}
+
+impl<E: Env> Replayer<E> {
+    /// Create a replayer for a tree with any environment type, see [`Context::new_generic`].
+    pub fn new_generic(recording: Recording, codecs: CodecRegistry) -> Self {
+        Self {
+            codecs: Shared::new(codecs),
+            recording,
+            tick: 0,
+            divergence: None,
+            _env: PhantomData,
+        }
+    }
+
+    /// Replay the next tick. Returns `None` if all the recorded ticks have been replayed.
+    ///
+    /// The callback is only called if the recorded response was opaque, or after the
+    /// replay diverged.
+    pub fn tick(
+        &mut self,
+        tree: &mut BehaviorNodeContainer<E>,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Option<BehaviorResult> {
+        self.replay(|arg, ctx| tree.tick(arg, ctx), arg, ctx)
+    }
+
+    /// Replay the next tick on an [`ArenaTree`], like [`Self::tick`].
+    pub fn tick_arena(
+        &mut self,
+        tree: &mut ArenaTree<E>,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Option<BehaviorResult> {
+        self.replay(|arg, ctx| tree.tick(arg, ctx), arg, ctx)
+    }
+
+    fn replay(
+        &mut self,
+        tick: impl FnOnce(E::Arg<'_>, &mut Context<E>) -> BehaviorResult,
+        arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Option<BehaviorResult> {
+        let expected = self.recording.ticks.get(self.tick)?.clone();
+        let decoded = expected
+            .iter()
+            .enumerate()
+            .filter_map(|(i, event)| match event {
+                RecordEvent::Read { value, .. } => {
+                    Some((i, Shared::from(value.decode(&self.codecs)?)))
+                }
+                _ => None,
+            })
+            .collect();
+        let tape = Tape {
+            codecs: self.codecs.clone(),
+            tick: self.tick,
+            events: vec![],
+            expected: Some(expected),
+            divergence: None,
+            current_node: NodeId::default(),
+        };
+        let (res, _, divergence) = tick_with_tape(tape, decoded, tick, arg, ctx);
+        if self.divergence.is_none() {
+            self.divergence = divergence;
+        }
+        self.tick += 1;
+        Some(res)
+    }
+
+    /// Replay all the remaining ticks and return the first divergence if any.
+    pub fn run(
+        &mut self,
+        tree: &mut BehaviorNodeContainer<E>,
+        mut arg: E::Arg<'_>,
+        ctx: &mut Context<E>,
+    ) -> Option<&Divergence> {
+        while self.tick(tree, E::reborrow(&mut arg), ctx).is_some() {}
+        self.divergence.as_ref()
+    }
+
+    /// The first divergence found so far.
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// Number of ticks replayed so far.
+    pub fn ticks_replayed(&self) -> usize {
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod test;
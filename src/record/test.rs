@@ -0,0 +1,192 @@
+use super::*;
+use crate::{BehaviorNode, Blackboard, MutEnv, SequenceNode};
+
+/// Succeeds if the "x" in the blackboard is larger than the threshold and the callback agrees.
+struct Check(f64);
+
+impl BehaviorNode for Check {
+    fn tick(&mut self, arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let x = ctx.get::<f64>("x").copied().unwrap_or(0.);
+        let agreed = arg(&x)
+            .and_then(|res| res.downcast_ref::<bool>().copied())
+            .unwrap_or(false);
+        if self.0 < x && agreed {
+            BehaviorResult::Success
+        } else {
+            BehaviorResult::Fail
+        }
+    }
+}
+
+struct Opaque(i32);
+
+struct ReadOpaque;
+
+impl BehaviorNode for ReadOpaque {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        match ctx.get::<Opaque>("opaque") {
+            Some(Opaque(1)) => BehaviorResult::Success,
+            _ => BehaviorResult::Fail,
+        }
+    }
+}
+
+fn build_tree(threshold: f64) -> BehaviorNodeContainer {
+    let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+    tree.add_child(BehaviorNodeContainer::new_node(Check(threshold)).with_name("Check".to_owned()))
+        .unwrap();
+    tree.assign_ids("main");
+    tree
+}
+
+fn record() -> Recording {
+    let mut tree = build_tree(1.);
+    let mut ctx = Context::default();
+    let mut recorder = Recorder::new(CodecRegistry::with_primitives());
+    for x in [0.5, 2.] {
+        ctx.set("x", x);
+        recorder.tick(&mut tree, &mut |_| Some(Box::new(true)), &mut ctx);
+    }
+    recorder.into_recording()
+}
+
+#[test]
+fn test_record() {
+    let recording = record();
+    assert_eq!(recording.ticks.len(), 2);
+    assert_eq!(
+        recording.ticks[1],
+        vec![
+            RecordEvent::Read {
+                node: NodeId(1),
+                key: "x".to_owned(),
+                value: RecordedValue::Value {
+                    ty: "f64".to_owned(),
+                    value: 2.0.into()
+                }
+            },
+            RecordEvent::Callback {
+                node: NodeId(1),
+                response: RecordedValue::Value {
+                    ty: "bool".to_owned(),
+                    value: true.into()
+                }
+            },
+            RecordEvent::Result {
+                node: NodeId(1),
+                result: BehaviorResult::Success
+            },
+            RecordEvent::Result {
+                node: NodeId(0),
+                result: BehaviorResult::Success
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_replay() {
+    let mut buf = vec![];
+    record().write_json(&mut buf).unwrap();
+    let recording = Recording::read_json(&buf[..]).unwrap();
+
+    // Neither the blackboard nor the callback is the same as recorded
+    let mut tree = build_tree(1.);
+    let mut replayer = Replayer::new(recording, CodecRegistry::with_primitives());
+    let mut ctx = Context::default();
+    let mut results = vec![];
+    while let Some(res) = replayer.tick(&mut tree, &mut |_| None, &mut ctx) {
+        results.push(res);
+    }
+    assert_eq!(results, vec![BehaviorResult::Fail, BehaviorResult::Success]);
+    assert_eq!(replayer.divergence(), None);
+    assert_eq!(replayer.ticks_replayed(), 2);
+}
+
+#[test]
+fn test_divergence() {
+    let mut tree = build_tree(3.);
+    let mut replayer = Replayer::new(record(), CodecRegistry::with_primitives());
+    let divergence = replayer
+        .run(&mut tree, &mut |_| None, &mut Context::default())
+        .unwrap();
+    assert_eq!(divergence.tick, 1);
+    assert_eq!(divergence.index, 2);
+    assert_eq!(
+        divergence.expected,
+        Some(RecordEvent::Result {
+            node: NodeId(1),
+            result: BehaviorResult::Success
+        })
+    );
+    assert_eq!(
+        divergence.actual,
+        Some(RecordEvent::Result {
+            node: NodeId(1),
+            result: BehaviorResult::Fail
+        })
+    );
+}
+
+#[test]
+fn test_opaque() {
+    let mut tree = BehaviorNodeContainer::new_node(ReadOpaque);
+    let mut ctx = Context::default();
+    ctx.set("opaque", Opaque(1));
+    let mut recorder = Recorder::new(CodecRegistry::new());
+    recorder.tick(&mut tree, &mut |_| None, &mut ctx);
+    let recording = recorder.into_recording();
+    assert!(matches!(
+        recording.ticks[0][0],
+        RecordEvent::Read {
+            value: RecordedValue::Opaque,
+            ..
+        }
+    ));
+
+    // Opaque values are read from the live blackboard
+    let mut replayer = Replayer::new(recording, CodecRegistry::new());
+    let mut ctx = Context::default();
+    ctx.set("opaque", Opaque(2));
+    let res = replayer.run(&mut tree, &mut |_| None, &mut ctx);
+    assert_eq!(
+        res.unwrap().actual,
+        Some(RecordEvent::Result {
+            node: NodeId(0),
+            result: BehaviorResult::Fail
+        })
+    );
+}
+
+/// Succeeds if the "x" in the blackboard is larger than the value of the environment.
+struct Above;
+
+impl BehaviorNode<MutEnv<f64>> for Above {
+    fn tick(&mut self, threshold: &mut f64, ctx: &mut Context<MutEnv<f64>>) -> BehaviorResult {
+        if *threshold < ctx.get::<f64>("x").copied().unwrap_or(0.) {
+            BehaviorResult::Success
+        } else {
+            BehaviorResult::Fail
+        }
+    }
+}
+
+#[test]
+fn test_generic_env() {
+    let mut tree = BehaviorNodeContainer::new_generic(Above);
+    tree.assign_ids("main");
+    let mut ctx = Context::new_generic(Blackboard::new());
+    ctx.set("x", 2.);
+    let mut recorder = Recorder::new_generic(CodecRegistry::with_primitives());
+    recorder.tick(&mut tree, &mut 1., &mut ctx);
+    let recording = recorder.into_recording();
+    // The environment is not recorded
+    assert_eq!(recording.ticks[0].len(), 2);
+
+    // The recorded "x" is used, while the environment is live
+    let mut ctx = Context::new_generic(Blackboard::new());
+    let mut replayer = Replayer::new_generic(recording.clone(), CodecRegistry::with_primitives());
+    assert_eq!(replayer.run(&mut tree, &mut 1., &mut ctx), None);
+    let mut replayer = Replayer::new_generic(recording, CodecRegistry::with_primitives());
+    assert!(replayer.run(&mut tree, &mut 3., &mut ctx).is_some());
+}
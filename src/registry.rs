@@ -1,29 +1,247 @@
 use super::{
+    error::LoadError,
     nodes::{
         FallbackNode, ForceFailureNode, ForceSuccessNode, IfNode, InverterNode, IsTrueNode,
-        ReactiveFallbackNode, ReactiveSequenceNode, RepeatNode, RetryNode, SequenceNode,
-        SetBoolNode,
+        OnChangeNode, ReactiveFallbackNode, ReactiveSequenceNode, RepeatNode, RetryNode,
+        SequenceNode, SetBoolNode,
     },
-    BehaviorNode, Symbol,
+    BBMap, BehaviorNode, BlackboardValue, CallbackEnv, Env, NumChildren, PortSpec, Shared, Symbol,
 };
-use std::collections::HashMap;
+use std::{cell::OnceCell, collections::HashMap, fmt::Display, str::FromStr};
 
-pub trait Constructor: Fn() -> Box<dyn BehaviorNode> {}
+pub trait Constructor<E: Env = CallbackEnv>: Fn() -> Box<dyn BehaviorNode<E>> {}
 
-pub fn boxify<T>(cons: impl (Fn() -> T) + 'static) -> Box<dyn Fn() -> Box<dyn BehaviorNode>>
+/// Wrap a constructor of a node type for [`Registry::register`].
+///
+/// The [`NodeMetadata`] of the type is given by [`BehaviorNode::type_metadata`] if the type
+/// declares it, and derived from a node created by the constructor otherwise, the first time
+/// the registry is asked for it.
+pub fn boxify<T, E: Env>(cons: impl (Fn() -> T) + 'static) -> NodeConstructor<E>
 where
-    for<'a> T: BehaviorNode + 'static,
+    for<'a> T: BehaviorNode<E> + 'static,
 {
-    Box::new(move || Box::new(cons()))
+    NodeConstructor {
+        constructor: Box::new(move || Box::new(cons())),
+        metadata: T::type_metadata(),
+    }
+}
+
+/// Wrap a constructor that takes the [`NodeConfig`] for [`Registry::register_with_config`],
+/// like [`boxify`].
+pub fn boxify_with_config<T, E: Env>(
+    cons: impl (Fn(&NodeConfig) -> Result<T, LoadError>) + 'static,
+) -> ConfigConstructor<E>
+where
+    T: BehaviorNode<E> + 'static,
+{
+    ConfigConstructor {
+        constructor: Box::new(move |config| Ok(Box::new(cons(config)?))),
+        metadata: T::type_metadata(),
+    }
+}
+
+/// A constructor of a node type for [`Registry::register`], with the metadata of the type
+/// if it is known, see [`boxify`].
+pub struct NodeConstructor<E: Env = CallbackEnv> {
+    constructor: Box<dyn Fn() -> Box<dyn BehaviorNode<E>>>,
+    metadata: Option<NodeMetadata>,
+}
+
+impl<E: Env> From<Box<dyn Fn() -> Box<dyn BehaviorNode<E>>>> for NodeConstructor<E> {
+    fn from(constructor: Box<dyn Fn() -> Box<dyn BehaviorNode<E>>>) -> Self {
+        Self {
+            constructor,
+            metadata: None,
+        }
+    }
+}
+
+type BoxedConfigConstructor<E> =
+    Box<dyn Fn(&NodeConfig) -> Result<Box<dyn BehaviorNode<E>>, LoadError>>;
+
+/// A constructor that receives the static configuration of the node in the tree source,
+/// with the metadata of the type if it is known, see [`boxify_with_config`].
+pub struct ConfigConstructor<E: Env = CallbackEnv> {
+    constructor: BoxedConfigConstructor<E>,
+    metadata: Option<NodeMetadata>,
+}
+
+impl<E: Env> From<BoxedConfigConstructor<E>> for ConfigConstructor<E> {
+    fn from(constructor: BoxedConfigConstructor<E>) -> Self {
+        Self {
+            constructor,
+            metadata: None,
+        }
+    }
+}
+
+type PlainConstructor<E> = Shared<dyn Fn() -> Box<dyn BehaviorNode<E>>>;
+
+/// A constructor of the nodes of a [`crate::Blueprint`], which may share the constructor of
+/// the registry.
+///
+/// It can fail if the registered constructor takes a [`NodeConfig`] and rejects it this time.
+pub(crate) type SharedConstructor<E> =
+    Shared<dyn Fn() -> Result<Box<dyn BehaviorNode<E>>, LoadError>>;
+
+type SharedConfigConstructor<E> =
+    Shared<dyn Fn(&NodeConfig) -> Result<Box<dyn BehaviorNode<E>>, LoadError>>;
+
+/// A node and the constructor of its further instances.
+pub(crate) type Built<E> = (Box<dyn BehaviorNode<E>>, SharedConstructor<E>);
+
+/// What kind of node a type is, for tools like editors that list the registered types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeCategory {
+    Action,
+    Condition,
+    Control,
+    Decorator,
+}
+
+/// The description of a node type, available without instantiating the node through
+/// [`Registry::metadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeMetadata {
+    pub ports: Vec<PortSpec>,
+    pub max_children: NumChildren,
+    pub category: NodeCategory,
+    pub description: String,
+}
+
+impl NodeMetadata {
+    /// Derive the metadata from the methods of a node.
+    pub fn of<E: Env>(node: &dyn BehaviorNode<E>) -> Self {
+        Self {
+            ports: node.provided_ports(),
+            max_children: node.max_children(),
+            category: node.category(),
+            description: node.description().to_owned(),
+        }
+    }
+}
+
+/// A registered node type.
+struct NodeEntry<E: Env> {
+    factory: Factory<E>,
+    /// Derived on demand unless given by the constructor or [`Registry::set_metadata`]
+    metadata: OnceCell<NodeMetadata>,
+}
+
+impl<E: Env> NodeEntry<E> {
+    fn new(factory: Factory<E>, metadata: Option<NodeMetadata>) -> Self {
+        Self {
+            factory,
+            metadata: metadata.map_or_else(OnceCell::new, OnceCell::from),
+        }
+    }
+}
+
+/// How a registered node type is constructed.
+pub(crate) enum Factory<E: Env> {
+    Plain(PlainConstructor<E>),
+    Configured(SharedConfigConstructor<E>),
+}
+
+impl<E: Env> Factory<E> {
+    /// A constructor that creates the nodes with the configuration.
+    pub(crate) fn constructor(&self, config: NodeConfig) -> SharedConstructor<E> {
+        match self {
+            Self::Plain(constructor) => {
+                let constructor = constructor.clone();
+                Shared::new(move || Ok(constructor()))
+            }
+            Self::Configured(constructor) => {
+                let constructor = constructor.clone();
+                Shared::new(move || constructor(&config))
+            }
+        }
+    }
+
+    /// Construct a node with the configuration, and a constructor that creates more
+    /// instances with the same configuration.
+    pub(crate) fn build(&self, config: NodeConfig) -> Result<Built<E>, LoadError> {
+        let constructor = self.constructor(config);
+        Ok((constructor()?, constructor))
+    }
 }
 
-pub struct Registry {
-    node_types: HashMap<String, Box<dyn Fn() -> Box<dyn BehaviorNode>>>,
+/// The static configuration of a node given in the tree source, which a constructor
+/// registered with [`Registry::register_with_config`] receives at load time.
+///
+/// The values of the ports mapped to literals are known at this point, so the node can
+/// parse and validate them once, instead of every tick.
+/// The ports mapped to variables can only be read with [`crate::Context`] when ticked.
+#[derive(Debug, Default)]
+pub struct NodeConfig {
+    node_type: String,
+    port_map: BBMap,
+}
+
+impl NodeConfig {
+    pub(crate) fn new(node_type: &str, port_map: BBMap) -> Self {
+        Self {
+            node_type: node_type.to_owned(),
+            port_map,
+        }
+    }
+
+    pub fn node_type(&self) -> &str {
+        &self.node_type
+    }
+
+    /// The literal value of the port, like `"3"` in `Repeat (n <- "3")`.
+    /// Returns `None` if the port is mapped to a variable or not mapped at all.
+    pub fn literal(&self, port: impl Into<Symbol>) -> Option<&str> {
+        match self.port_map.get(&port.into()) {
+            Some(BlackboardValue::Literal(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the port is mapped to a variable, local or global.
+    pub fn is_variable(&self, port: impl Into<Symbol>) -> bool {
+        matches!(
+            self.port_map.get(&port.into()),
+            Some(BlackboardValue::Ref(..) | BlackboardValue::Global(..))
+        )
+    }
+
+    /// Parse the [literal](Self::literal) value of the port.
+    /// Returns `Ok(None)` if the port is not a literal, and [`LoadError::InvalidConfig`] if it
+    /// fails to parse.
+    pub fn parse<T: FromStr>(&self, port: impl Into<Symbol>) -> Result<Option<T>, LoadError>
+    where
+        T::Err: Display,
+    {
+        let port = port.into();
+        self.literal(port)
+            .map(|value| {
+                value.parse().map_err(|e: T::Err| LoadError::InvalidConfig {
+                    node: self.node_type.clone(),
+                    port: port.to_string(),
+                    message: e.to_string(),
+                })
+            })
+            .transpose()
+    }
+}
+
+pub struct Registry<E: Env = CallbackEnv> {
+    node_types: HashMap<String, NodeEntry<E>>,
     pub(crate) key_names: HashMap<String, Symbol>,
 }
 
 impl Default for Registry {
     fn default() -> Self {
+        Self::new_generic()
+    }
+}
+
+impl<E: Env> Registry<E> {
+    /// Create a registry with a custom environment type, with the built-in nodes registered
+    /// like [`Registry::default`].
+    pub fn new_generic() -> Self {
         let mut ret = Self {
             node_types: HashMap::new(),
             key_names: HashMap::new(),
@@ -35,27 +253,112 @@ impl Default for Registry {
         ret.register("ForceSuccess", boxify(ForceSuccessNode::default));
         ret.register("ForceFailure", boxify(ForceFailureNode::default));
         ret.register("Inverter", boxify(InverterNode::default));
-        ret.register("Repeat", boxify(RepeatNode::default));
-        ret.register("Retry", boxify(RetryNode::default));
+        ret.register_with_config("Repeat", boxify_with_config(RepeatNode::new));
+        ret.register_with_config("Retry", boxify_with_config(RetryNode::new));
         ret.register("IsTrue", boxify(|| IsTrueNode));
         ret.register("if", boxify(IfNode::default));
         ret.register("SetBool", boxify(|| SetBoolNode));
+        ret.register("OnChange", boxify(OnChangeNode::default));
         ret
     }
-}
 
-impl Registry {
     pub fn register(
         &mut self,
         type_name: impl ToString,
-        constructor: Box<dyn Fn() -> Box<dyn BehaviorNode>>,
+        constructor: impl Into<NodeConstructor<E>>,
     ) {
-        self.node_types.insert(type_name.to_string(), constructor);
+        let NodeConstructor {
+            constructor,
+            metadata,
+        } = constructor.into();
+        self.node_types.insert(
+            type_name.to_string(),
+            NodeEntry::new(Factory::Plain(constructor.into()), metadata),
+        );
     }
 
-    pub fn build(&self, type_name: &str) -> Option<Box<dyn BehaviorNode>> {
-        self.node_types
-            .get(type_name)
-            .map(|constructor| constructor())
+    /// Register a node type whose constructor receives the [`NodeConfig`] of each node
+    /// in the tree source, and can reject it with a [`LoadError`].
+    ///
+    /// A [`crate::Blueprint`] calls the constructor again with the same configuration for
+    /// each instance, and [`crate::Blueprint::instantiate`] returns the error if it fails.
+    pub fn register_with_config(
+        &mut self,
+        type_name: impl ToString,
+        constructor: impl Into<ConfigConstructor<E>>,
+    ) {
+        let ConfigConstructor {
+            constructor,
+            metadata,
+        } = constructor.into();
+        self.node_types.insert(
+            type_name.to_string(),
+            NodeEntry::new(Factory::Configured(constructor.into()), metadata),
+        );
+    }
+
+    /// Construct a node of the type, with an empty [`NodeConfig`] if it takes one.
+    /// Returns `None` if the type is not registered or the constructor fails.
+    pub fn build(&self, type_name: &str) -> Option<Box<dyn BehaviorNode<E>>> {
+        match self.node_types.get(type_name)?.factory {
+            Factory::Plain(ref constructor) => Some(constructor()),
+            Factory::Configured(ref constructor) => {
+                constructor(&NodeConfig::new(type_name, BBMap::new())).ok()
+            }
+        }
+    }
+
+    /// Set the metadata of a registered type, e.g. to give it a description, or to avoid
+    /// creating a node to derive it if the type doesn't declare it with
+    /// [`BehaviorNode::type_metadata`]. Returns false if the type is not registered.
+    pub fn set_metadata(&mut self, type_name: &str, metadata: NodeMetadata) -> bool {
+        let Some(entry) = self.node_types.get_mut(type_name) else {
+            return false;
+        };
+        entry.metadata = OnceCell::from(metadata);
+        true
+    }
+
+    /// The metadata of a registered type, which is declared by [`BehaviorNode::type_metadata`]
+    /// or given by [`Self::set_metadata`].
+    /// Otherwise, it is derived from a node created by [`Self::build`] the first time.
+    ///
+    /// Returns `None` if the type is not registered, or it doesn't declare the metadata, takes a
+    /// [`NodeConfig`] and can't be created without one.
+    pub fn metadata(&self, type_name: &str) -> Option<&NodeMetadata> {
+        let entry = self.node_types.get(type_name)?;
+        if let Some(metadata) = entry.metadata.get() {
+            return Some(metadata);
+        }
+        let node = self.build(type_name)?;
+        Some(entry.metadata.get_or_init(|| NodeMetadata::of(&*node)))
+    }
+
+    /// The names of the registered types, in arbitrary order.
+    pub fn node_types(&self) -> impl Iterator<Item = &str> {
+        self.node_types.keys().map(String::as_str)
+    }
+
+    /// The metadata if it is declared or already derived, without creating a node.
+    pub(crate) fn known_metadata(&self, type_name: &str) -> Option<&NodeMetadata> {
+        self.node_types.get(type_name)?.metadata.get()
+    }
+
+    /// The metadata of a registered type, derived from `node` if it isn't known yet.
+    pub(crate) fn derive_metadata(
+        &self,
+        type_name: &str,
+        node: &dyn BehaviorNode<E>,
+    ) -> &NodeMetadata {
+        self.node_types[type_name]
+            .metadata
+            .get_or_init(|| NodeMetadata::of(node))
+    }
+
+    pub(crate) fn factory(&self, type_name: &str) -> Option<&Factory<E>> {
+        self.node_types.get(type_name).map(|entry| &entry.factory)
     }
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,178 @@
+use super::*;
+use crate::{parse_file, BehaviorCallback, BehaviorResult, Blueprint, Context, PortType};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+struct Move;
+
+impl BehaviorNode for Move {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("target").with_type::<(f64, f64)>()]
+    }
+
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+}
+
+fn counting_registry(created: &Arc<AtomicUsize>) -> Registry {
+    let mut registry = Registry::default();
+    let created = created.clone();
+    registry.register(
+        "Move",
+        boxify(move || {
+            created.fetch_add(1, Ordering::SeqCst);
+            Move
+        }),
+    );
+    registry
+}
+
+#[test]
+fn test_builtin_metadata() {
+    let registry = Registry::default();
+    let repeat = registry.metadata("Repeat").unwrap();
+    assert_eq!(repeat.ports, [PortSpec::new_in("n")]);
+    assert_eq!(repeat.max_children, NumChildren::Finite(1));
+    assert_eq!(repeat.category, NodeCategory::Decorator);
+    assert!(!repeat.description.is_empty());
+
+    assert_eq!(
+        registry.metadata("IsTrue").unwrap().category,
+        NodeCategory::Condition
+    );
+    assert_eq!(
+        registry.metadata("SetBool").unwrap().category,
+        NodeCategory::Action
+    );
+    assert_eq!(
+        registry.metadata("Sequence").unwrap().category,
+        NodeCategory::Control
+    );
+    assert!(registry.metadata("Missing").is_none());
+
+    let mut types = registry.node_types().collect::<Vec<_>>();
+    types.sort();
+    assert_eq!(types.len(), 13);
+    assert_eq!(types[0], "Fallback");
+}
+
+#[test]
+fn test_derived_once() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let registry = counting_registry(&created);
+    let metadata = registry.metadata("Move").unwrap();
+    assert_eq!(metadata.ports[0].ty, PortType::Input);
+    assert_eq!(metadata.ports[0].value_type, Some("(f64, f64)"));
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+
+    // Loading uses the cached metadata to check the ports
+    let (_, source) =
+        parse_file(r#"tree main = Sequence { Move (target <- a) Move (target <- b) }"#).unwrap();
+    Blueprint::new(&source, &registry, true).unwrap();
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_set_metadata() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let mut registry = counting_registry(&created);
+    assert!(registry.set_metadata(
+        "Move",
+        NodeMetadata {
+            ports: vec![PortSpec::new_in("goal")],
+            max_children: NumChildren::Finite(0),
+            category: NodeCategory::Action,
+            description: "Moves to the goal".to_owned(),
+        },
+    ));
+    assert!(!registry.set_metadata("Missing", registry.metadata("Move").unwrap().clone()));
+
+    let (_, source) = parse_file(r#"tree main = Sequence { Move (target <- a) }"#).unwrap();
+    assert!(matches!(
+        Blueprint::new(&source, &registry, true),
+        Err(LoadError::PortUnmatch { .. })
+    ));
+    let (_, source) = parse_file(r#"tree main = Sequence { Move (goal <- a) }"#).unwrap();
+    Blueprint::new(&source, &registry, true).unwrap();
+    assert_eq!(created.load(Ordering::SeqCst), 0);
+    assert_eq!(
+        registry.metadata("Move").unwrap().description,
+        "Moves to the goal"
+    );
+}
+
+#[test]
+fn test_configured_metadata() {
+    let mut registry = Registry::default();
+    registry.register_with_config(
+        "Move",
+        boxify_with_config(|config| match config.literal("target") {
+            Some(_) => Ok(Move),
+            None => Err(LoadError::InvalidConfig {
+                node: "Move".to_owned(),
+                port: "target".to_owned(),
+                message: "required".to_owned(),
+            }),
+        }),
+    );
+    // It can't be created without a configuration
+    assert!(registry.metadata("Move").is_none());
+
+    let (_, source) = parse_file(r#"tree main = Sequence { Move (target <- "1, 2") }"#).unwrap();
+    Blueprint::new(&source, &registry, true).unwrap();
+}
+
+/// Declares its metadata, because it can't be created without a target
+struct Goto;
+
+impl BehaviorNode for Goto {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("target")]
+    }
+
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+
+    fn type_metadata() -> Option<NodeMetadata> {
+        Some(NodeMetadata {
+            ports: vec![PortSpec::new_in("target")],
+            max_children: NumChildren::Finite(0),
+            category: NodeCategory::Action,
+            description: "Goes to the target".to_owned(),
+        })
+    }
+}
+
+#[test]
+fn test_type_metadata() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let created2 = created.clone();
+    let mut registry = Registry::default();
+    registry.register_with_config(
+        "Goto",
+        boxify_with_config(move |config| {
+            created2.fetch_add(1, Ordering::SeqCst);
+            match config.literal("target") {
+                Some(_) => Ok(Goto),
+                None => Err(LoadError::InvalidConfig {
+                    node: "Goto".to_owned(),
+                    port: "target".to_owned(),
+                    message: "required".to_owned(),
+                }),
+            }
+        }),
+    );
+    let metadata = registry.metadata("Goto").unwrap();
+    assert_eq!(metadata.ports, [PortSpec::new_in("target")]);
+    assert_eq!(metadata.description, "Goes to the target");
+    assert_eq!(created.load(Ordering::SeqCst), 0);
+
+    // The configuration is still validated at load time
+    let (_, source) = parse_file(r#"tree main = Sequence { Goto (target <- "1, 2") }"#).unwrap();
+    Blueprint::new(&source, &registry, true).unwrap();
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+}
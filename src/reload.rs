@@ -0,0 +1,247 @@
+//! Hot reloading of a tree while it is running.
+//!
+//! [`reload`] builds a new tree from an updated [`TreeSource`] and moves the node instances
+//! of the unchanged parts over from the old tree, so that e.g. a `Sequence` in the middle of
+//! its children keeps running from where it was.
+//!
+//! Nodes are matched by their [paths](BehaviorNodeContainer::path). A node is considered
+//! unchanged if it has the same type, the same port mappings, the same
+//! [provided ports](crate::BehaviorNode::provided_ports) (which are the parameters of a
+//! subtree) and the same types of children in the same order.
+//! Old nodes that were removed or replaced are [halted](crate::BehaviorNode::halt) if they
+//! were `Running`. A replaced node starts over, so its unchanged descendants keep their
+//! instances but are halted too.
+//!
+//! [`FileWatcher`] is a small helper to poll a source file for changes, e.g. once per frame.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, reload::*};
+//! let registry = Registry::default();
+//! let (_, source) = parse_file("tree main = Sequence { Fallback }").unwrap();
+//! let tree = load(&source, &registry, false).unwrap();
+//!
+//! let (_, source) = parse_file("tree main = Sequence { Fallback\n Inverter }").unwrap();
+//! let (tree, report) = reload(tree, &source, &registry, false).unwrap();
+//! assert_eq!(report.kept, vec!["main/Fallback[0]"]);
+//! assert_eq!(report.replaced, vec!["main"]);
+//! assert_eq!(report.added, vec!["main/Inverter[0]"]);
+//! ```
+
+use crate::{
+    error::LoadError, load, parser::TreeSource, BBMap, BehaviorNode, BehaviorNodeContainer,
+    BehaviorResult, Env, PortSpec, Registry, Shared,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// What happened to each node in [`reload`], by paths in depth-first pre-order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Nodes that reused the old instances
+    pub kept: Vec<String>,
+    /// Nodes that exist in both trees but were changed, so new instances were created
+    pub replaced: Vec<String>,
+    /// Nodes that only exist in the new tree
+    pub added: Vec<String>,
+    /// Nodes that only exist in the old tree
+    pub removed: Vec<String>,
+    /// Old nodes that were halted because they were running
+    pub halted: Vec<String>,
+}
+
+/// A node taken out of the old tree.
+struct OldNode<E: Env> {
+    order: usize,
+    name: Shared<str>,
+    blackboard_map: Shared<BBMap>,
+    child_names: Vec<Shared<str>>,
+    ports: Vec<PortSpec>,
+    node: Box<dyn BehaviorNode<E>>,
+    last_result: Option<BehaviorResult>,
+    subtree_expanded: bool,
+}
+
+impl<E: Env> OldNode<E> {
+    fn is_compatible(&self, new: &BehaviorNodeContainer<E>) -> bool {
+        self.name == new.name
+            && self.blackboard_map == new.blackboard_map
+            && self.ports == new.node.provided_ports()
+            && self
+                .child_names
+                .iter()
+                .eq(new.child_nodes.iter().map(|child| &child.name))
+    }
+}
+
+/// A running node to be halted after merging.
+struct Halt<E: Env> {
+    order: usize,
+    path: String,
+    /// The old instance, or `None` if it was kept in the new tree.
+    node: Option<Box<dyn BehaviorNode<E>>>,
+}
+
+/// Load the tree from `source` and reuse compatible nodes from `old`.
+///
+/// The arguments other than `old` are the same as [`load`].
+pub fn reload<E: Env>(
+    old: BehaviorNodeContainer<E>,
+    source: &TreeSource,
+    registry: &Registry<E>,
+    check_ports: bool,
+) -> Result<(BehaviorNodeContainer<E>, ReloadReport), LoadError> {
+    let mut new = load(source, registry, check_ports)?;
+
+    let mut old_nodes = HashMap::new();
+    flatten(old, &mut old_nodes);
+
+    let mut report = ReloadReport::default();
+    let mut halts = vec![];
+    merge(&mut new, &mut old_nodes, &mut report, &mut halts, false);
+
+    let mut removed = old_nodes.into_iter().collect::<Vec<_>>();
+    removed.sort_by_key(|(_, old)| old.order);
+    for (path, old) in removed {
+        report.removed.push(path.clone());
+        if old.last_result == Some(BehaviorResult::Running) {
+            halts.push(Halt {
+                order: old.order,
+                path,
+                node: Some(old.node),
+            });
+        }
+    }
+
+    // Halt descendants before ancestors
+    halts.sort_by_key(|halt| std::cmp::Reverse(halt.order));
+    for halt in halts {
+        if let Some(mut node) = halt.node {
+            node.halt();
+        } else if let Some(kept) = new.find_by_path_mut(&halt.path) {
+            kept.node.halt();
+        }
+        report.halted.push(halt.path);
+    }
+
+    Ok((new, report))
+}
+
+fn flatten<E: Env>(node: BehaviorNodeContainer<E>, out: &mut HashMap<String, OldNode<E>>) {
+    let BehaviorNodeContainer {
+        name,
+        node,
+        blackboard_map,
+        child_nodes,
+        last_result,
+        subtree_expanded,
+        path,
+        ..
+    } = node;
+    let old = OldNode {
+        order: out.len(),
+        name,
+        blackboard_map,
+        child_names: child_nodes.iter().map(|child| child.name.clone()).collect(),
+        ports: node.provided_ports(),
+        node,
+        last_result,
+        subtree_expanded: subtree_expanded.get(),
+    };
+    out.insert(path.to_string(), old);
+    for child in child_nodes {
+        flatten(child, out);
+    }
+}
+
+/// `restart` tells if an ancestor was replaced, in which case kept nodes lose their state.
+fn merge<E: Env>(
+    new: &mut BehaviorNodeContainer<E>,
+    old_nodes: &mut HashMap<String, OldNode<E>>,
+    report: &mut ReloadReport,
+    halts: &mut Vec<Halt<E>>,
+    mut restart: bool,
+) {
+    if let Some(old) = old_nodes.remove(&*new.path) {
+        let running = old.last_result == Some(BehaviorResult::Running);
+        if old.is_compatible(new) {
+            new.node = old.node;
+            new.subtree_expanded.set(old.subtree_expanded);
+            if !restart {
+                new.last_result = old.last_result;
+            } else if running {
+                halts.push(Halt {
+                    order: old.order,
+                    path: new.path.to_string(),
+                    node: None,
+                });
+            }
+            report.kept.push(new.path.to_string());
+        } else {
+            restart = true;
+            report.replaced.push(new.path.to_string());
+            if running {
+                halts.push(Halt {
+                    order: old.order,
+                    path: new.path.to_string(),
+                    node: Some(old.node),
+                });
+            }
+        }
+    } else {
+        report.added.push(new.path.to_string());
+    }
+
+    for child in &mut new.child_nodes {
+        merge(child, old_nodes, report, halts, restart);
+    }
+}
+
+/// Polls modification time of a file to detect changes.
+///
+/// ```no_run
+/// # use behavior_tree_lite::reload::FileWatcher;
+/// let mut watcher = FileWatcher::new("main.btc");
+/// loop {
+///     if let Ok(Some(text)) = watcher.poll() {
+///         // parse and reload
+///     }
+///     // tick the tree
+/// #   break;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    /// The first [`Self::poll`] always returns the contents of the file.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            modified: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the contents of the file if it has been modified since the last call.
+    pub fn poll(&mut self) -> std::io::Result<Option<String>> {
+        let modified = std::fs::metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&self.path)?;
+        self.modified = Some(modified);
+        Ok(Some(text))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,177 @@
+use super::*;
+use crate::{boxify, parse_file, BehaviorCallback, Context};
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Counts how many times it has been ticked.
+struct Step(Arc<AtomicUsize>);
+
+impl BehaviorNode for Step {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        BehaviorResult::Success
+    }
+}
+
+/// Keeps running forever, and remembers if it was halted.
+struct Work(Arc<AtomicBool>);
+
+impl BehaviorNode for Work {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Running
+    }
+
+    fn halt(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Fixture {
+    registry: Registry,
+    steps: Arc<AtomicUsize>,
+    halted: Arc<AtomicBool>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let steps = Arc::new(AtomicUsize::new(0));
+        let halted = Arc::new(AtomicBool::new(false));
+        let mut registry = Registry::default();
+        let steps2 = steps.clone();
+        registry.register("Step", boxify(move || Step(steps2.clone())));
+        let halted2 = halted.clone();
+        registry.register("Work", boxify(move || Work(halted2.clone())));
+        Self {
+            registry,
+            steps,
+            halted,
+        }
+    }
+
+    fn load(&self, text: &str) -> BehaviorNodeContainer {
+        let (_, source) = parse_file(text).unwrap();
+        load(&source, &self.registry, false).unwrap()
+    }
+
+    fn reload(
+        &self,
+        old: BehaviorNodeContainer,
+        text: &str,
+    ) -> (BehaviorNodeContainer, ReloadReport) {
+        let (_, source) = parse_file(text).unwrap();
+        reload(old, &source, &self.registry, false).unwrap()
+    }
+}
+
+const SOURCE: &str = "tree main = Sequence {\n Step\n Work\n}";
+
+#[test]
+fn test_keep_running_state() {
+    let fixture = Fixture::new();
+    let mut tree = fixture.load(SOURCE);
+    let mut ctx = Context::default();
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(fixture.steps.load(Ordering::Relaxed), 1);
+
+    let (mut tree, report) = fixture.reload(tree, SOURCE);
+    assert_eq!(report.kept, vec!["main", "main/Step[0]", "main/Work[0]"]);
+    assert!(report.replaced.is_empty() && report.added.is_empty() && report.halted.is_empty());
+    assert_eq!(tree.last_result(), Some(BehaviorResult::Running));
+
+    // The Sequence resumes from Work, so Step is not ticked again
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(fixture.steps.load(Ordering::Relaxed), 1);
+    assert!(!fixture.halted.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_replace_and_halt() {
+    let fixture = Fixture::new();
+    let mut tree = fixture.load(SOURCE);
+    tree.tick(&mut |_| None, &mut Context::default());
+
+    let (mut tree, report) = fixture.reload(tree, "tree main = Sequence {\n Step\n}");
+    assert_eq!(
+        report,
+        ReloadReport {
+            kept: vec!["main/Step[0]".to_owned()],
+            replaced: vec!["main".to_owned()],
+            added: vec![],
+            removed: vec!["main/Work[0]".to_owned()],
+            halted: vec!["main/Work[0]".to_owned(), "main".to_owned()],
+        }
+    );
+    assert!(fixture.halted.load(Ordering::Relaxed));
+
+    // The new Sequence starts over
+    assert_eq!(
+        tree.tick(&mut |_| None, &mut Context::default()),
+        BehaviorResult::Success
+    );
+    assert_eq!(fixture.steps.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_port_change() {
+    let fixture = Fixture::new();
+    let tree = fixture.load("tree main = Sequence {\n Repeat (n <- \"2\") {\n Step\n }\n}");
+    let (_, report) = fixture.reload(
+        tree,
+        "tree main = Sequence {\n Repeat (n <- \"3\") {\n Step\n }\n}",
+    );
+    assert_eq!(report.kept, vec!["main", "main/Repeat[0]/Step[0]"]);
+    assert_eq!(report.replaced, vec!["main/Repeat[0]"]);
+}
+
+#[test]
+fn test_halt_under_replaced_parent() {
+    let fixture = Fixture::new();
+    let mut tree = fixture.load(SOURCE);
+    tree.tick(&mut |_| None, &mut Context::default());
+
+    let (mut tree, report) = fixture.reload(tree, "tree main = Fallback {\n Step\n Work\n}");
+    assert_eq!(report.kept, vec!["main/Step[0]", "main/Work[0]"]);
+    assert_eq!(report.replaced, vec!["main"]);
+    // Work keeps its instance, but the new Fallback will not resume it
+    assert_eq!(report.halted, vec!["main/Work[0]", "main"]);
+    assert!(fixture.halted.load(Ordering::Relaxed));
+    assert_eq!(tree.children()[1].last_result(), None);
+
+    assert_eq!(
+        tree.tick(&mut |_| None, &mut Context::default()),
+        BehaviorResult::Success
+    );
+    assert_eq!(fixture.steps.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_subtree_params_change() {
+    let fixture = Fixture::new();
+    let tree = fixture.load("tree main = Sequence {\n sub(a <- \"1\")\n}\ntree sub(in a) = Step");
+    let (tree, report) = fixture.reload(
+        tree,
+        "tree main = Sequence {\n sub(a <- \"1\")\n}\ntree sub(in a, in b) = Step",
+    );
+    assert_eq!(report.kept, vec!["main", "main/sub[0]/Step[0]"]);
+    assert_eq!(report.replaced, vec!["main/sub[0]"]);
+    assert_eq!(tree.children()[0].node.provided_ports().len(), 2);
+}
+
+#[test]
+fn test_file_watcher() {
+    let path = std::env::temp_dir().join(format!("btl-watcher-{}.btc", std::process::id()));
+    std::fs::write(&path, SOURCE).unwrap();
+    let mut watcher = FileWatcher::new(&path);
+    assert_eq!(watcher.poll().unwrap().as_deref(), Some(SOURCE));
+    assert_eq!(watcher.poll().unwrap(), None);
+
+    std::fs::write(&path, "tree main = Step").unwrap();
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+        .unwrap();
+    drop(file);
+    assert_eq!(watcher.poll().unwrap().as_deref(), Some("tree main = Step"));
+    std::fs::remove_file(&path).unwrap();
+}
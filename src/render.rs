@@ -0,0 +1,220 @@
+//! Rendering a tree as indented text for terminal debugging, log files and test failure messages.
+
+use crate::{BehaviorNodeContainer, BehaviorResult, BlackboardValue, Env, PortType};
+use std::fmt::{self, Write};
+
+/// A configurable text renderer of a [`BehaviorNodeContainer`] and its descendants.
+///
+/// ```
+/// # use behavior_tree_lite::*;
+/// let mut tree = BehaviorNodeContainer::new_node(SequenceNode::default());
+/// tree.assign_ids("main");
+/// let text = TreeRenderer::new().ascii().render(&tree);
+/// assert_eq!(text, "main -\n");
+/// ```
+///
+/// Each line shows the label or the path segment of the node, the node type name,
+/// port mappings and the last result of the node (`-` if it has not been ticked yet).
+/// Subtrees are only expanded if [`BehaviorNodeContainer::is_subtree_expanded`] is true,
+/// unless [`Self::expand_subtrees`] is set.
+#[derive(Debug, Clone)]
+pub struct TreeRenderer {
+    unicode: bool,
+    color: bool,
+    ports: bool,
+    ids: bool,
+    expand_subtrees: bool,
+}
+
+impl Default for TreeRenderer {
+    fn default() -> Self {
+        Self {
+            unicode: true,
+            color: false,
+            ports: true,
+            ids: false,
+            expand_subtrees: false,
+        }
+    }
+}
+
+struct Glyphs {
+    branch: &'static str,
+    last_branch: &'static str,
+    pipe: &'static str,
+    blank: &'static str,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    branch: "├── ",
+    last_branch: "└── ",
+    pipe: "│   ",
+    blank: "    ",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    branch: "|-- ",
+    last_branch: "`-- ",
+    pipe: "|   ",
+    blank: "    ",
+};
+
+impl TreeRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use plain ASCII characters instead of box-drawing characters.
+    pub fn ascii(mut self) -> Self {
+        self.unicode = false;
+        self
+    }
+
+    /// Color the results with ANSI escape sequences.
+    pub fn color(mut self, b: bool) -> Self {
+        self.color = b;
+        self
+    }
+
+    /// Show port mappings of each node.
+    pub fn ports(mut self, b: bool) -> Self {
+        self.ports = b;
+        self
+    }
+
+    /// Show node ids.
+    pub fn ids(mut self, b: bool) -> Self {
+        self.ids = b;
+        self
+    }
+
+    /// Expand all subtrees regardless of [`BehaviorNodeContainer::is_subtree_expanded`].
+    pub fn expand_subtrees(mut self, b: bool) -> Self {
+        self.expand_subtrees = b;
+        self
+    }
+
+    pub fn render<E: Env>(&self, tree: &BehaviorNodeContainer<E>) -> String {
+        let mut ret = String::new();
+        // Writing to a String never fails
+        self.write(&mut ret, tree).unwrap();
+        ret
+    }
+
+    pub fn write<E: Env>(
+        &self,
+        f: &mut impl Write,
+        tree: &BehaviorNodeContainer<E>,
+    ) -> fmt::Result {
+        self.write_line(f, tree)?;
+        self.write_children(f, tree, &mut String::new())
+    }
+
+    fn write_children<E: Env>(
+        &self,
+        f: &mut impl Write,
+        node: &BehaviorNodeContainer<E>,
+        prefix: &mut String,
+    ) -> fmt::Result {
+        if node.is_subtree() && !(self.expand_subtrees || node.is_subtree_expanded()) {
+            return Ok(());
+        }
+        let glyphs = if self.unicode {
+            &UNICODE_GLYPHS
+        } else {
+            &ASCII_GLYPHS
+        };
+        let num_children = node.children().len();
+        for (i, child) in node.children().iter().enumerate() {
+            let last = i + 1 == num_children;
+            f.write_str(prefix)?;
+            f.write_str(if last {
+                glyphs.last_branch
+            } else {
+                glyphs.branch
+            })?;
+            self.write_line(f, child)?;
+
+            let len = prefix.len();
+            prefix.push_str(if last { glyphs.blank } else { glyphs.pipe });
+            self.write_children(f, child, prefix)?;
+            prefix.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn write_line<E: Env>(
+        &self,
+        f: &mut impl Write,
+        node: &BehaviorNodeContainer<E>,
+    ) -> fmt::Result {
+        let segment = node.path().rsplit('/').next().unwrap_or_default();
+        match (segment.is_empty(), node.name().is_empty()) {
+            (false, false) => write!(f, "{} ({})", segment, node.name())?,
+            (false, true) => f.write_str(segment)?,
+            (true, _) => f.write_str(node.name())?,
+        }
+
+        if self.ids {
+            write!(f, " {}", node.id())?;
+        }
+
+        if self.ports && !node.blackboard_map().is_empty() {
+            let mut ports = node.blackboard_map().iter().collect::<Vec<_>>();
+            ports.sort_by_key(|(key, _)| key.as_str());
+            f.write_str(" (")?;
+            for (i, (key, value)) in ports.into_iter().enumerate() {
+                if i != 0 {
+                    f.write_str(", ")?;
+                }
+                match value {
+                    BlackboardValue::Ref(value, ty) => {
+                        write!(f, "{key} {} {value}", arrow(*ty))?;
+                    }
+                    BlackboardValue::Global(value, ty) => {
+                        write!(f, "{key} {} @{value}", arrow(*ty))?;
+                    }
+                    BlackboardValue::Literal(value) => write!(f, "{key} <- {value:?}")?,
+                }
+            }
+            f.write_str(")")?;
+        }
+
+        if node.is_subtree() {
+            if self.expand_subtrees || node.is_subtree_expanded() {
+                f.write_str(" [subtree]")?;
+            } else {
+                f.write_str(" [subtree ...]")?;
+            }
+        }
+
+        let (status, color) = match node.last_result() {
+            Some(BehaviorResult::Success) => ("Success", "\x1b[32m"),
+            Some(BehaviorResult::Fail) => ("Fail", "\x1b[31m"),
+            Some(BehaviorResult::Running) => ("Running", "\x1b[33m"),
+            None => ("-", "\x1b[90m"),
+        };
+        if self.color {
+            writeln!(f, " {color}{status}\x1b[0m")
+        } else {
+            writeln!(f, " {status}")
+        }
+    }
+}
+
+fn arrow(ty: PortType) -> &'static str {
+    match ty {
+        PortType::Input => "<-",
+        PortType::Output => "->",
+        PortType::InOut => "<->",
+    }
+}
+
+impl<E: Env> fmt::Display for BehaviorNodeContainer<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        TreeRenderer::default().write(f, self)
+    }
+}
+
+#[cfg(test)]
+mod test;
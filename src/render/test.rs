@@ -0,0 +1,77 @@
+use super::*;
+use crate::{boxify, load, parse_file, BehaviorCallback, BehaviorNode, Context, Registry};
+
+struct Succeed;
+
+impl BehaviorNode for Succeed {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Success
+    }
+}
+
+fn load_tree() -> BehaviorNodeContainer {
+    let (_, tree_source) = parse_file(
+        r#"
+tree main = Sequence {
+    MoveTo:approach (target <- enemy)
+    Patrol
+    Inverter {
+        MoveTo (target <- "home")
+    }
+}
+
+tree Patrol = Sequence {
+    MoveTo
+}
+"#,
+    )
+    .unwrap();
+    let mut registry = Registry::default();
+    registry.register("MoveTo", boxify(|| Succeed));
+    load(&tree_source, &registry, false).unwrap()
+}
+
+#[test]
+fn test_render_unicode() {
+    let mut tree = load_tree();
+    tree.tick(&mut |_| None, &mut Context::default());
+    assert_eq!(
+        tree.to_string(),
+        r#"main (Sequence) Fail
+├── approach (MoveTo) (target <- enemy) Success
+├── Patrol[0] (Patrol) [subtree ...] Success
+└── Inverter[0] (Inverter) Fail
+    └── MoveTo[0] (MoveTo) (target <- "home") Success
+"#
+    );
+}
+
+#[test]
+fn test_render_subtree_expanded() {
+    let tree = load_tree();
+    tree.children()[1].expand_subtree(true);
+    assert_eq!(
+        TreeRenderer::new()
+            .ascii()
+            .ports(false)
+            .ids(true)
+            .render(&tree),
+        r#"main (Sequence) #0 -
+|-- approach (MoveTo) #1 -
+|-- Patrol[0] (Patrol) #2 [subtree] -
+|   `-- Sequence[0] (Sequence) #3 -
+|       `-- MoveTo[0] (MoveTo) #4 -
+`-- Inverter[0] (Inverter) #5 -
+    `-- MoveTo[0] (MoveTo) #6 -
+"#
+    );
+}
+
+#[test]
+fn test_render_color() {
+    let mut tree = load_tree();
+    tree.tick(&mut |_| None, &mut Context::default());
+    let text = TreeRenderer::new().color(true).render(&tree);
+    assert!(text.starts_with("main (Sequence) \x1b[31mFail\x1b[0m\n"));
+    assert!(text.contains("\x1b[32mSuccess\x1b[0m"));
+}
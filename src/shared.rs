@@ -0,0 +1,47 @@
+//! Types that switch to thread-safe versions with the `sync` feature.
+//!
+//! By default, blackboard values are shared by [`Rc`](std::rc::Rc) and nodes don't need to
+//! be [`Send`], which is the cheapest option for single-threaded applications.
+//! With the `sync` feature, values are shared by [`Arc`](std::sync::Arc) and nodes, observers
+//! and callbacks stored in the [`crate::Context`] are required to be `Send`, so that
+//! trees and contexts can be moved to other threads.
+
+#[cfg(not(feature = "sync"))]
+mod imp {
+    /// Reference counted pointer to share blackboard values, `Rc` or `Arc` with `sync` feature.
+    pub type Shared<T> = std::rc::Rc<T>;
+
+    /// The type of a blackboard value, `dyn Any` or `dyn Any + Send + Sync` with `sync` feature.
+    pub type AnyValue = dyn std::any::Any;
+
+    /// A bound that is `Send` only with `sync` feature.
+    pub trait MaybeSend {}
+
+    impl<T: ?Sized> MaybeSend for T {}
+
+    /// A bound that is `Send + Sync` only with `sync` feature, required for blackboard values.
+    pub trait MaybeSendSync {}
+
+    impl<T: ?Sized> MaybeSendSync for T {}
+}
+
+#[cfg(feature = "sync")]
+mod imp {
+    /// Reference counted pointer to share blackboard values, `Rc` or `Arc` with `sync` feature.
+    pub type Shared<T> = std::sync::Arc<T>;
+
+    /// The type of a blackboard value, `dyn Any` or `dyn Any + Send + Sync` with `sync` feature.
+    pub type AnyValue = dyn std::any::Any + Send + Sync;
+
+    /// A bound that is `Send` only with `sync` feature.
+    pub trait MaybeSend: Send {}
+
+    impl<T: ?Sized + Send> MaybeSend for T {}
+
+    /// A bound that is `Send + Sync` only with `sync` feature, required for blackboard values.
+    pub trait MaybeSendSync: Send + Sync {}
+
+    impl<T: ?Sized + Send + Sync> MaybeSendSync for T {}
+}
+
+pub use imp::*;
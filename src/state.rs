@@ -0,0 +1,234 @@
+//! Saving and restoring the runtime state of a tree, e.g. for save games.
+//!
+//! The state of a tree consists of two parts:
+//!
+//! * The internal state of each node, such as the child index a `Sequence` is running.
+//!   It is saved by [`BehaviorNodeContainer::save_state`] into a [`TreeState`], keyed by
+//!   [node paths](BehaviorNodeContainer::path), so it stays valid as long as the tree
+//!   has the same shape.
+//! * The blackboard, saved by [`Context::save_blackboard`].
+//!
+//! Blackboard values are `dyn Any`, so their types need to be registered in a
+//! [`CodecRegistry`] to be saved.
+//!
+//! ```
+//! # use behavior_tree_lite::{*, codec::CodecRegistry};
+//! let (_, source) = parse_file("tree main = Sequence { Fallback }").unwrap();
+//! let tree = load(&source, &Registry::default(), false).unwrap();
+//! let mut ctx = Context::default();
+//! ctx.set("hp", 10i32);
+//!
+//! let codecs = CodecRegistry::with_primitives();
+//! let tree_state = serde_json::to_string(&tree.save_state(&codecs).unwrap()).unwrap();
+//! let bb_state = serde_json::to_string(&ctx.save_blackboard(&codecs).unwrap()).unwrap();
+//!
+//! // Load the game
+//! let mut tree = load(&source, &Registry::default(), false).unwrap();
+//! tree.restore_state(&serde_json::from_str(&tree_state).unwrap(), &codecs).unwrap();
+//! let mut ctx = Context::default();
+//! ctx.restore_blackboard(&serde_json::from_str(&bb_state).unwrap(), &codecs).unwrap();
+//! assert_eq!(ctx.get::<i32>("hp"), Some(&10));
+//! ```
+
+use crate::{
+    codec::CodecRegistry, error::StateError, BehaviorNodeContainer, BehaviorResult, Blackboard,
+    Context, Env, Shared,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A serialized blackboard value with its registered type name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedValue {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: serde_json::Value,
+}
+
+/// A serialized [`Blackboard`], keyed by variable names.
+pub type SavedBlackboard = BTreeMap<String, SavedValue>;
+
+/// Serialize all the variables in the blackboard.
+/// Fails if any of them has a type not registered in `codecs`.
+pub fn save_blackboard(
+    blackboard: &Blackboard,
+    codecs: &CodecRegistry,
+) -> Result<SavedBlackboard, StateError> {
+    blackboard
+        .iter()
+        .map(|(key, value)| {
+            let (ty, value) =
+                codecs
+                    .encode(&**value)
+                    .ok_or_else(|| StateError::UnregisteredType {
+                        key: key.to_string(),
+                    })?;
+            Ok((
+                key.to_string(),
+                SavedValue {
+                    ty: ty.to_owned(),
+                    value,
+                },
+            ))
+        })
+        .collect()
+}
+
+pub fn restore_blackboard(
+    saved: &SavedBlackboard,
+    codecs: &CodecRegistry,
+) -> Result<Blackboard, StateError> {
+    saved
+        .iter()
+        .map(|(key, saved)| {
+            let value = codecs
+                .decode(&saved.ty, saved.value.clone())
+                .ok_or_else(|| StateError::UnknownTypeName {
+                    key: key.clone(),
+                    name: saved.ty.clone(),
+                })??;
+            Ok((key.as_str().into(), Shared::from(value)))
+        })
+        .collect()
+}
+
+/// Saved state of a node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeState {
+    /// Name of the type of the node, to detect changes in the tree
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_result: Option<BehaviorResult>,
+    /// The value returned by [`crate::BehaviorNode::save_state`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<serde_json::Value>,
+}
+
+/// Saved state of all the nodes in a tree, keyed by node paths.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TreeState {
+    pub nodes: BTreeMap<String, NodeState>,
+}
+
+impl<E: Env> BehaviorNodeContainer<E> {
+    /// Save the internal state of this node and all its descendants.
+    ///
+    /// Paths need to be assigned by [`Self::assign_ids`], which [`crate::load`] does for you.
+    pub fn save_state(&self, codecs: &CodecRegistry) -> Result<TreeState, StateError> {
+        let mut ret = TreeState::default();
+        self.save_state_recurse(codecs, &mut ret)?;
+        Ok(ret)
+    }
+
+    fn save_state_recurse(
+        &self,
+        codecs: &CodecRegistry,
+        out: &mut TreeState,
+    ) -> Result<(), StateError> {
+        let state = self
+            .node
+            .save_state(codecs)
+            .map_err(|e| StateError::InvalidState {
+                path: self.path.to_string(),
+                message: e.to_string(),
+            })?;
+        out.nodes.insert(
+            self.path.to_string(),
+            NodeState {
+                name: self.name.to_string(),
+                last_result: self.last_result,
+                state,
+            },
+        );
+        for child in &self.child_nodes {
+            child.save_state_recurse(codecs, out)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the state saved by [`Self::save_state`].
+    ///
+    /// The shape of the tree is checked before restoring anything, and if any node was added,
+    /// removed or changed its type, an error is returned and the tree is left untouched.
+    pub fn restore_state(
+        &mut self,
+        saved: &TreeState,
+        codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        let mut visited = 0;
+        self.check_shape(saved, &mut visited)?;
+        if visited != saved.nodes.len() {
+            let path = saved
+                .nodes
+                .keys()
+                .find(|path| self.find_by_path(path).is_none())
+                .cloned()
+                .unwrap_or_default();
+            return Err(StateError::MissingNode { path });
+        }
+        self.restore_state_recurse(saved, codecs)
+    }
+
+    fn check_shape(&self, saved: &TreeState, visited: &mut usize) -> Result<(), StateError> {
+        let node = saved
+            .nodes
+            .get(&*self.path)
+            .ok_or_else(|| StateError::UnexpectedNode {
+                path: self.path.to_string(),
+            })?;
+        if *node.name != *self.name {
+            return Err(StateError::NodeTypeMismatch {
+                path: self.path.to_string(),
+                saved: node.name.clone(),
+                actual: self.name.to_string(),
+            });
+        }
+        *visited += 1;
+        for child in &self.child_nodes {
+            child.check_shape(saved, visited)?;
+        }
+        Ok(())
+    }
+
+    fn restore_state_recurse(
+        &mut self,
+        saved: &TreeState,
+        codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        let node = &saved.nodes[&*self.path];
+        self.last_result = node.last_result;
+        if let Some(ref state) = node.state {
+            self.node
+                .restore_state(state, codecs)
+                .map_err(|e| StateError::InvalidState {
+                    path: self.path.to_string(),
+                    message: e.to_string(),
+                })?;
+        }
+        for child in &mut self.child_nodes {
+            child.restore_state_recurse(saved, codecs)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: Env> Context<E> {
+    /// Save the blackboard of the context. See [`save_blackboard`].
+    pub fn save_blackboard(&self, codecs: &CodecRegistry) -> Result<SavedBlackboard, StateError> {
+        save_blackboard(&self.blackboard, codecs)
+    }
+
+    /// Replace the blackboard of the context with the saved one.
+    pub fn restore_blackboard(
+        &mut self,
+        saved: &SavedBlackboard,
+        codecs: &CodecRegistry,
+    ) -> Result<(), StateError> {
+        self.blackboard = restore_blackboard(saved, codecs)?;
+        self.touch_all();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,171 @@
+use super::*;
+use crate::{load, parse_file, BehaviorCallback, BehaviorNode, Registry};
+
+/// Counts how many times it has been ticked in the blackboard, and keeps running
+/// until the "done" flag is set.
+struct Work;
+
+impl BehaviorNode for Work {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let count = ctx.get::<i32>("count").copied().unwrap_or(0);
+        ctx.set("count", count + 1);
+        if ctx.get::<bool>("done") == Some(&true) {
+            BehaviorResult::Success
+        } else {
+            BehaviorResult::Running
+        }
+    }
+}
+
+const SOURCE: &str = r#"tree main = Sequence {
+    Work
+    Repeat (n <- "2") {
+        Work
+    }
+}
+"#;
+
+fn load_tree(source: &str) -> Result<BehaviorNodeContainer, crate::error::LoadError> {
+    let (_, source) = parse_file(source).unwrap();
+    let mut registry = Registry::default();
+    registry.register("Work", crate::boxify(|| Work));
+    load(&source, &registry, false)
+}
+
+#[test]
+fn test_save_restore() {
+    let codecs = CodecRegistry::with_primitives();
+    let mut tree = load_tree(SOURCE).unwrap();
+    let mut ctx = Context::default();
+    tree.tick(&mut |_| None, &mut ctx);
+    ctx.set("done", true);
+    // The first Work succeeds, Repeat enters the 2nd iteration
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(ctx.get::<i32>("count"), Some(&3));
+
+    let tree_state = serde_json::to_string(&tree.save_state(&codecs).unwrap()).unwrap();
+    let bb_state = serde_json::to_string(&ctx.save_blackboard(&codecs).unwrap()).unwrap();
+
+    let tree_state: TreeState = serde_json::from_str(&tree_state).unwrap();
+    assert_eq!(
+        tree_state.nodes["main/Repeat[0]"].state,
+        Some(serde_json::json!(1))
+    );
+    assert_eq!(
+        tree_state.nodes["main"].last_result,
+        Some(BehaviorResult::Running)
+    );
+
+    let mut tree = load_tree(SOURCE).unwrap();
+    tree.restore_state(&tree_state, &codecs).unwrap();
+    let mut ctx = Context::default();
+    ctx.restore_blackboard(&serde_json::from_str(&bb_state).unwrap(), &codecs)
+        .unwrap();
+    assert_eq!(tree.last_result(), Some(BehaviorResult::Running));
+
+    // Resumes from the Repeat node, skipping the first Work
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Running);
+    assert_eq!(ctx.get::<i32>("count"), Some(&4));
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+}
+
+#[test]
+fn test_on_change() {
+    let source = "tree main = Sequence {\n OnChange (keys <- \"target\") {\n Work\n }\n}";
+    let codecs = CodecRegistry::with_primitives();
+    let mut tree = load_tree(source).unwrap();
+    let mut ctx = Context::default();
+    ctx.set("done", true);
+    ctx.set("target", 1i32);
+    tree.tick(&mut |_| None, &mut ctx);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(ctx.get::<i32>("count"), Some(&1));
+
+    let saved = tree.save_state(&codecs).unwrap();
+    assert_eq!(
+        saved.nodes["main/OnChange[0]"].state,
+        Some(serde_json::json!("Success"))
+    );
+    let bb_state = ctx.save_blackboard(&codecs).unwrap();
+
+    let mut tree = load_tree(source).unwrap();
+    tree.restore_state(&saved, &codecs).unwrap();
+    let mut ctx = Context::default();
+    ctx.restore_blackboard(&bb_state, &codecs).unwrap();
+
+    // The child is not ticked again until the target changes
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(ctx.get::<i32>("count"), Some(&1));
+    ctx.set("target", 2i32);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(ctx.get::<i32>("count"), Some(&2));
+}
+
+#[test]
+fn test_shape_mismatch() {
+    let codecs = CodecRegistry::new();
+    let saved = load_tree(SOURCE).unwrap().save_state(&codecs).unwrap();
+
+    let mut tree = load_tree("tree main = Sequence { Work }").unwrap();
+    assert!(matches!(
+        tree.restore_state(&saved, &codecs),
+        Err(StateError::MissingNode { path }) if path == "main/Repeat[0]"
+    ));
+
+    let mut tree =
+        load_tree("tree main = Sequence { Work\n Repeat (n <- \"2\") { Work }\n Work }").unwrap();
+    assert!(matches!(
+        tree.restore_state(&saved, &codecs),
+        Err(StateError::UnexpectedNode { path }) if path == "main/Work[1]"
+    ));
+
+    let mut tree =
+        load_tree("tree main = Fallback { Work\n Repeat (n <- \"2\") { Work } }").unwrap();
+    let err = tree.restore_state(&saved, &codecs).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Node \"main\" was saved as \"Sequence\", but it is \"Fallback\" in the tree"
+    );
+}
+
+#[test]
+fn test_subtree_blackboard() {
+    let source = "tree main = Sequence {\n sub(input <- \"hello\")\n}\ntree sub(in input) = Work\n";
+    let codecs = CodecRegistry::with_primitives();
+    let mut tree = load_tree(source).unwrap();
+    tree.tick(&mut |_| None, &mut Context::default());
+
+    let saved = tree.save_state(&codecs).unwrap();
+    let state = saved.nodes["main/sub[0]"].state.clone().unwrap();
+    let bb: SavedBlackboard = serde_json::from_value(state).unwrap();
+    assert_eq!(bb["count"].value, serde_json::json!(1));
+    assert_eq!(bb["input"].ty, "String");
+
+    let mut tree = load_tree(source).unwrap();
+    tree.restore_state(&saved, &codecs).unwrap();
+    assert_eq!(tree.save_state(&codecs).unwrap(), saved);
+}
+
+#[test]
+fn test_unregistered() {
+    struct Opaque;
+    let mut ctx = Context::default();
+    ctx.set("opaque", Opaque);
+    assert!(matches!(
+        ctx.save_blackboard(&CodecRegistry::with_primitives()),
+        Err(StateError::UnregisteredType { key }) if key == "opaque"
+    ));
+
+    let mut saved = SavedBlackboard::new();
+    saved.insert(
+        "a".to_owned(),
+        SavedValue {
+            ty: "Vector3".to_owned(),
+            value: serde_json::Value::Null,
+        },
+    );
+    assert!(matches!(
+        ctx.restore_blackboard(&saved, &CodecRegistry::new()),
+        Err(StateError::UnknownTypeName { key, name }) if key == "a" && name == "Vector3"
+    ));
+}
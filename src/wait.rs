@@ -0,0 +1,162 @@
+//! Declaring what a `Running` node waits for, so that an event-driven loop can skip the ticks
+//! in which nothing would change. See [`Context::needs_tick`].
+
+use crate::{context::Mapped, BehaviorResult, Context, Env, Symbol};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    /// A variable of the root (global) blackboard with the version when the wait was declared
+    Variable(Symbol, Option<u64>),
+    Timer(Instant),
+    Event(Symbol),
+}
+
+/// The waits declared in the last tick, and the events posted to the tree.
+#[derive(Debug, Default)]
+pub(crate) struct WaitState {
+    conditions: Vec<Condition>,
+    /// The number of waits declared, including the ones that can never fire
+    declared: usize,
+    /// Set if a node needs the next tick regardless of the conditions
+    every_tick: bool,
+    /// Events posted since the last tick
+    pending_events: Vec<Symbol>,
+    /// Events visible to the nodes in the current tick
+    events: Vec<Symbol>,
+}
+
+impl<E: Env> Context<E> {
+    /// Declare that the node returning `Running` doesn't need to be ticked until the variable
+    /// mapped to the port changes. A literal never changes.
+    ///
+    /// Only the variables of the root blackboard and the global variables can be waited for.
+    /// If the port refers to a local variable of a subtree, the node is ticked every tick.
+    ///
+    /// These `wait_for_*` methods only have effect if the tree is ticked by an event-driven
+    /// loop, like [`crate::executor::Executor::run_event_driven`].
+    /// If a node returns `Running` without declaring any wait, and none of its descendants
+    /// did, it is assumed that the node needs the next tick.
+    pub fn wait_for_key(&mut self, key: impl Into<Symbol>) {
+        match self.map_key(key.into(), false) {
+            Some(Mapped::Local(key)) => self.wait_for_variable(key, false),
+            Some(Mapped::Global(key)) => self.wait_for_variable(key, true),
+            Some(Mapped::Literal(_)) | None => self.waits.declared += 1,
+        }
+    }
+
+    /// Wait for a variable of the current blackboard, or the global blackboard if `global`,
+    /// bypassing port mapping.
+    pub(crate) fn wait_for_variable(&mut self, key: Symbol, global: bool) {
+        if global {
+            let version = self.global_version(key);
+            self.waits
+                .conditions
+                .push(Condition::Variable(key, version));
+        } else if self.in_subtree() {
+            self.waits.every_tick = true;
+        } else {
+            let version = self.version(key);
+            self.waits
+                .conditions
+                .push(Condition::Variable(key, version));
+        }
+        self.waits.declared += 1;
+    }
+
+    /// Declare that the node returning `Running` doesn't need to be ticked until the time.
+    pub fn wait_until(&mut self, deadline: Instant) {
+        self.waits.conditions.push(Condition::Timer(deadline));
+        self.waits.declared += 1;
+    }
+
+    /// Shorthand for [`Self::wait_until`] the duration after now.
+    pub fn wait_for_duration(&mut self, duration: Duration) {
+        self.wait_until(Instant::now() + duration);
+    }
+
+    /// Declare that the node returning `Running` doesn't need to be ticked until the event is
+    /// posted by [`Self::post_event`].
+    pub fn wait_for_event(&mut self, event: impl Into<Symbol>) {
+        self.waits.conditions.push(Condition::Event(event.into()));
+        self.waits.declared += 1;
+    }
+
+    /// Declare that the node returning `Running` is resumed by the [waker](Self::waker),
+    /// which makes the executor tick the tree.
+    pub fn wait_for_wake(&mut self) {
+        self.waits.declared += 1;
+    }
+
+    /// Declare that the node needs the next tick regardless of the other waits, e.g. a reactive
+    /// node that checks conditions every tick.
+    pub fn wait_for_next_tick(&mut self) {
+        self.waits.every_tick = true;
+        self.waits.declared += 1;
+    }
+
+    /// Post an event to the tree, which the nodes can see with [`Self::has_event`] during
+    /// the next tick.
+    pub fn post_event(&mut self, event: impl Into<Symbol>) {
+        self.waits.pending_events.push(event.into());
+    }
+
+    /// Returns true if the event was posted before this tick.
+    pub fn has_event(&self, event: impl Into<Symbol>) -> bool {
+        self.waits.events.contains(&event.into())
+    }
+
+    /// Returns true if any of the waits declared in the last tick has fired, or a node needs
+    /// the next tick anyway, assuming the last tick returned `Running`.
+    /// Wake-ups through the [waker](Self::waker) are not counted, since the context can't
+    /// see them.
+    pub fn needs_tick(&self) -> bool {
+        let waits = &self.waits;
+        if waits.every_tick || waits.declared == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        waits.conditions.iter().any(|condition| match *condition {
+            // Between ticks, the current blackboard is the global one
+            Condition::Variable(key, version) => self.version(key) != version,
+            Condition::Timer(deadline) => deadline <= now,
+            Condition::Event(event) => waits.pending_events.contains(&event),
+        })
+    }
+
+    /// The earliest time declared by [`Self::wait_until`] in the last tick.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.waits
+            .conditions
+            .iter()
+            .filter_map(|condition| match *condition {
+                Condition::Timer(deadline) => Some(deadline),
+                _ => None,
+            })
+            .min()
+    }
+
+    /// Clear the waits of the last tick and make the posted events visible.
+    pub(crate) fn begin_waits(&mut self) {
+        let waits = &mut self.waits;
+        waits.conditions.clear();
+        waits.declared = 0;
+        waits.every_tick = false;
+        waits.events = std::mem::take(&mut waits.pending_events);
+    }
+
+    pub(crate) fn waits_declared(&self) -> usize {
+        self.waits.declared
+    }
+
+    /// A node that returned `Running` without declaring waits by itself or its descendants
+    /// needs the next tick.
+    pub(crate) fn check_waits(&mut self, declared_before: usize, res: BehaviorResult) {
+        if res == BehaviorResult::Running && self.waits.declared == declared_before {
+            self.waits.every_tick = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,158 @@
+use super::*;
+use crate::{
+    boxify, executor::Executor, load, parse_file, BehaviorCallback, BehaviorNode, PortSpec,
+    Registry,
+};
+
+/// Returns `Running` until the input is true
+struct WaitKey;
+
+impl BehaviorNode for WaitKey {
+    fn provided_ports(&self) -> Vec<PortSpec> {
+        vec![PortSpec::new_in("input")]
+    }
+
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        if ctx.get::<bool>("input") == Some(&true) {
+            return BehaviorResult::Success;
+        }
+        ctx.wait_for_key("input");
+        BehaviorResult::Running
+    }
+}
+
+/// Returns `Running` until the event `go` is posted
+struct WaitEvent;
+
+impl BehaviorNode for WaitEvent {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        if ctx.has_event("go") {
+            return BehaviorResult::Success;
+        }
+        ctx.wait_for_event("go");
+        BehaviorResult::Running
+    }
+}
+
+/// Returns `Running` for 50 milliseconds
+#[derive(Default)]
+struct WaitTimer(Option<Instant>);
+
+impl BehaviorNode for WaitTimer {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let deadline = *self
+            .0
+            .get_or_insert_with(|| Instant::now() + Duration::from_millis(50));
+        if deadline <= Instant::now() {
+            return BehaviorResult::Success;
+        }
+        ctx.wait_until(deadline);
+        BehaviorResult::Running
+    }
+}
+
+/// Returns `Running` forever without declaring what it waits for
+struct Forever;
+
+impl BehaviorNode for Forever {
+    fn tick(&mut self, _arg: BehaviorCallback, _ctx: &mut Context) -> BehaviorResult {
+        BehaviorResult::Running
+    }
+}
+
+fn executor(source: &str) -> Executor {
+    let (_, source) = parse_file(source).unwrap();
+    let mut registry = Registry::default();
+    registry.register("WaitKey", boxify(|| WaitKey));
+    registry.register("WaitEvent", boxify(|| WaitEvent));
+    registry.register("WaitTimer", boxify(WaitTimer::default));
+    registry.register("Forever", boxify(|| Forever));
+    Executor::new(load(&source, &registry, true).unwrap(), Context::default())
+}
+
+#[test]
+fn test_wait_for_key() {
+    let mut executor = executor("tree main = Sequence { WaitKey (input <- flag) }");
+    let tick = |executor: &mut Executor| executor.tick_if_needed(&mut |_| None);
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Running));
+    assert_eq!(tick(&mut executor), None);
+    assert_eq!(tick(&mut executor), None);
+
+    executor.context_mut().set("flag", true);
+    assert!(executor.context().needs_tick());
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Success));
+    assert_eq!(executor.context().tick_count(), 2);
+
+    // The tree is ticked again once it has finished
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Success));
+}
+
+#[test]
+fn test_on_change() {
+    let mut executor = executor(
+        r#"
+tree main = Sequence {
+    OnChange (keys <- "target") {
+        WaitEvent
+    }
+}
+"#,
+    );
+    let tick = |executor: &mut Executor| executor.tick_if_needed(&mut |_| None);
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Running));
+    assert_eq!(tick(&mut executor), None);
+
+    // OnChange waits for its keys in addition to the child
+    executor.context_mut().set("target", 1);
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Running));
+    assert_eq!(tick(&mut executor), None);
+
+    executor.post_event("go");
+    assert_eq!(tick(&mut executor), Some(BehaviorResult::Success));
+}
+
+#[test]
+fn test_fallback_to_every_tick() {
+    for source in [
+        "tree main = ReactiveSequence { WaitKey (input <- flag) }",
+        "tree main = Sequence { Forever }",
+        // A local variable of a subtree can't be checked between ticks
+        "tree main = Sequence { Sub } tree Sub = Sequence { var flag = false WaitKey (input <- flag) }",
+    ] {
+        let mut executor = executor(source);
+        for _ in 0..3 {
+            assert_eq!(
+                executor.tick_if_needed(&mut |_| None),
+                Some(BehaviorResult::Running),
+                "{source}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_timer() {
+    let mut executor = executor("tree main = Sequence { WaitTimer }");
+    let start = Instant::now();
+    let res = executor.run_event_driven(&mut |_| None, Duration::from_millis(1));
+    assert_eq!(res, BehaviorResult::Success);
+    assert!(Duration::from_millis(50) <= start.elapsed());
+    assert_eq!(executor.context().tick_count(), 2);
+}
+
+#[test]
+fn test_event_from_thread() {
+    let mut executor = executor("tree main = Sequence { WaitEvent }");
+    let handle = executor.wake_handle();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        handle.post_event("other");
+        std::thread::sleep(Duration::from_millis(20));
+        handle.post_event("go");
+    });
+    let res = executor.run_event_driven(&mut |_| None, Duration::from_millis(1));
+    thread.join().unwrap();
+    assert_eq!(res, BehaviorResult::Success);
+    // The event that no one waits for doesn't tick the tree
+    assert_eq!(executor.context().tick_count(), 2);
+}
@@ -0,0 +1,140 @@
+use behavior_tree_lite::{
+    boxify, load, parse_file, BehaviorCallback, BehaviorNode, BehaviorResult, Context, PortSpec,
+    Registry,
+};
+use behavior_tree_lite_derive::{provided_ports, Ports};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Arm {
+    name: String,
+}
+
+#[derive(Debug)]
+struct Body {
+    left_arm: Arm,
+    right_arm: Arm,
+}
+
+#[derive(Ports)]
+#[input(body: Body)]
+#[output(left_arm: Arm, right_arm: Arm)]
+struct SplitBody;
+
+#[provided_ports]
+impl BehaviorNode for SplitBody {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let Some(body) = self.input_body(ctx) else {
+            return BehaviorResult::Fail;
+        };
+        let (left_arm, right_arm) = (body.left_arm.clone(), body.right_arm.clone());
+        self.output_left_arm(ctx, left_arm);
+        self.output_right_arm(ctx, right_arm);
+        BehaviorResult::Success
+    }
+}
+
+#[derive(Ports)]
+#[inout(count: usize)]
+struct Increment;
+
+#[provided_ports]
+impl BehaviorNode for Increment {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        let count = self.input_count(ctx).copied().unwrap_or(0);
+        self.output_count(ctx, count + 1);
+        BehaviorResult::Success
+    }
+}
+
+/// Declares the ports on the fields, which keep the last values
+#[derive(Ports, Default)]
+struct Accumulate {
+    #[input]
+    amount: f64,
+    #[output]
+    total: f64,
+}
+
+#[provided_ports]
+impl BehaviorNode for Accumulate {
+    fn tick(&mut self, _arg: BehaviorCallback, ctx: &mut Context) -> BehaviorResult {
+        self.amount = self.input_amount(ctx).copied().unwrap_or(0.);
+        self.total += self.amount;
+        self.output_total(ctx, self.total);
+        BehaviorResult::Success
+    }
+}
+
+#[test]
+fn test_derived_ports() {
+    assert_eq!(
+        SplitBody.provided_ports(),
+        [
+            PortSpec::new_in("body").with_type::<Body>(),
+            PortSpec::new_out("left_arm").with_type::<Arm>(),
+            PortSpec::new_out("right_arm").with_type::<Arm>(),
+        ]
+    );
+    assert_eq!(
+        Increment::ports(),
+        [PortSpec::new_inout("count").with_type::<usize>()]
+    );
+    assert_eq!(
+        Accumulate::ports(),
+        [
+            PortSpec::new_in("amount").with_type::<f64>(),
+            PortSpec::new_out("total").with_type::<f64>(),
+        ]
+    );
+}
+
+#[test]
+fn test_field_ports() {
+    let (_, source) =
+        parse_file("tree main = Sequence { Accumulate (amount <- amount, total -> total) }")
+            .unwrap();
+    let mut registry = Registry::default();
+    registry.register("Accumulate", boxify(Accumulate::default));
+    let mut tree = load(&source, &registry, true).unwrap();
+
+    let mut ctx = Context::default();
+    ctx.set("amount", 1.5);
+    tree.tick(&mut |_| None, &mut ctx);
+    ctx.set("amount", 2.);
+    tree.tick(&mut |_| None, &mut ctx);
+    assert_eq!(ctx.get::<f64>("total"), Some(&3.5));
+}
+
+#[test]
+fn test_derived_accessors() {
+    let (_, source) = parse_file(
+        r#"
+tree main = Sequence {
+    SplitBody (body <- body, left_arm -> left, right_arm -> right)
+    Increment (count <-> counter)
+    Increment (count <-> counter)
+}
+"#,
+    )
+    .unwrap();
+    let mut registry = Registry::default();
+    registry.register("SplitBody", boxify(|| SplitBody));
+    registry.register("Increment", boxify(|| Increment));
+    let mut tree = load(&source, &registry, true).unwrap();
+
+    let mut ctx = Context::default();
+    let arm = |name: &str| Arm {
+        name: name.to_owned(),
+    };
+    ctx.set(
+        "body",
+        Body {
+            left_arm: arm("left"),
+            right_arm: arm("right"),
+        },
+    );
+    assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+    assert_eq!(ctx.get::<Arm>("left"), Some(&arm("left")));
+    assert_eq!(ctx.get::<Arm>("right"), Some(&arm("right")));
+    assert_eq!(ctx.get::<usize>("counter"), Some(&2));
+}
@@ -70,3 +70,31 @@ fn test_fallback() {
         BehaviorResult::Success
     );
 }
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_send() {
+    use behavior_tree_lite::{load, parse_file, Registry};
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    let (_, source) = parse_file(
+        "tree main = Sequence {
+    SetBool (value <- \"true\", output -> flag)
+}",
+    )
+    .unwrap();
+    let mut tree = load(&source, &Registry::default(), false).unwrap();
+    let mut ctx = Context::default();
+    assert_send(&tree);
+    assert_send(&ctx);
+
+    let (tree, ctx) = std::thread::spawn(move || {
+        assert_eq!(tree.tick(&mut |_| None, &mut ctx), BehaviorResult::Success);
+        (tree, ctx)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(ctx.get::<bool>("flag"), Some(&true));
+    drop(tree);
+}